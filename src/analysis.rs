@@ -0,0 +1,598 @@
+use crate::model::{Column, DatabaseSchema, Reference, Table};
+use crate::rng::SeededRng;
+use petgraph::algo::kosaraju_scc;
+use petgraph::graph::{Graph, NodeIndex};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// Upper bound on the passes `communities` makes over the tables.
+const COMMUNITY_PASSES: usize = 100;
+
+pub fn tables_without_primary_key(schema: &DatabaseSchema) -> Vec<&Table> {
+    schema
+        .tables
+        .iter()
+        .filter(|table| !table.external && !table.table_type && table.primary_key.is_empty())
+        .collect()
+}
+
+/// The two columns of `reference` when their data types differ, such as an
+/// `int` foreign key into a `bigint` key. References whose columns aren't in
+/// the model, e.g. into external stubs, are never reported.
+pub fn type_mismatch<'a>(schema: &'a DatabaseSchema, reference: &Reference) -> Option<(&'a Column, &'a Column)> {
+    let column = |table_schema: &str, table: &str, column: &str| {
+        schema
+            .tables
+            .iter()
+            .find(|candidate| candidate.schema == table_schema && candidate.name == table)?
+            .columns
+            .iter()
+            .find(|candidate| candidate.name == column)
+    };
+    let from = column(&reference.schema, &reference.table, &reference.column)?;
+    let to = column(&reference.referenced_schema, &reference.referenced_table, &reference.referenced_column)?;
+    (from.data_type != to.data_type).then_some((from, to))
+}
+
+/// The endpoints of `reference`, as `(table, column)`, whose column is
+/// missing from its table's column list, as when a column is dropped between
+/// the column and reference queries. Tables outside the model, external
+/// stubs and tables read without columns (`--topology-only`) aren't checked.
+pub fn missing_columns<'a>(schema: &DatabaseSchema, reference: &'a Reference) -> Vec<(&'a str, &'a str)> {
+    let endpoints = [
+        (&reference.schema, &reference.table, &reference.column),
+        (&reference.referenced_schema, &reference.referenced_table, &reference.referenced_column),
+    ];
+    endpoints
+        .into_iter()
+        .filter(|(table_schema, table, column)| {
+            schema
+                .tables
+                .iter()
+                .find(|candidate| &candidate.schema == *table_schema && &candidate.name == *table)
+                .is_some_and(|table| {
+                    !table.external
+                        && !table.columns.is_empty()
+                        && !table.columns.iter().any(|candidate| &candidate.name == *column)
+                })
+        })
+        .map(|(_, table, column)| (table.as_str(), column.as_str()))
+        .collect()
+}
+
+/// Describes the structural problems worth flagging in `schema`: tables
+/// without a primary key, circular foreign keys, foreign keys whose columns
+/// differ in type or are missing from the column model, and disabled
+/// foreign keys.
+pub fn design_warnings(schema: &DatabaseSchema) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for table in tables_without_primary_key(schema) {
+        warnings.push(format!("table {}.{} has no primary key", table.schema, table.name));
+    }
+    for cycle in find_cycles(schema) {
+        let members: Vec<String> = cycle
+            .iter()
+            .map(|table| format!("{}.{}", table.schema, table.name))
+            .collect();
+        warnings.push(format!("circular foreign keys between {}", members.join(", ")));
+    }
+    let mut disabled: Vec<&str> = Vec::new();
+    for reference in &schema.references {
+        if let Some((from, to)) = type_mismatch(schema, reference) {
+            warnings.push(format!(
+                "foreign key {} joins {}.{} ({}) to {}.{} ({})",
+                reference.constraint_name,
+                reference.table,
+                reference.column,
+                from.data_type,
+                reference.referenced_table,
+                reference.referenced_column,
+                to.data_type
+            ));
+        }
+        for (table, column) in missing_columns(schema, reference) {
+            warnings.push(format!(
+                "foreign key {} uses column {}.{}, which is not among the extracted columns",
+                reference.constraint_name, table, column
+            ));
+        }
+        if reference.is_disabled && !disabled.contains(&reference.constraint_name.as_str()) {
+            disabled.push(&reference.constraint_name);
+            warnings.push(format!(
+                "foreign key {} on {}.{} is disabled",
+                reference.constraint_name, reference.schema, reference.table
+            ));
+        }
+    }
+    warnings
+}
+
+/// Counts columns by base data type across the schema, most common first and
+/// then alphabetically. Type names are compared case-insensitively.
+pub fn type_counts(schema: &DatabaseSchema) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for column in schema.tables.iter().flat_map(|table| &table.columns) {
+        *counts.entry(column.data_type.base.to_lowercase()).or_default() += 1;
+    }
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|(a_name, a_count), (b_name, b_count)| b_count.cmp(a_count).then_with(|| a_name.cmp(b_name)));
+    counts
+}
+
+/// Each stored procedure referencing tables of the schema, in name order,
+/// with the tables it references in model order.
+pub fn procedure_dependencies(schema: &DatabaseSchema) -> Vec<(&str, Vec<&Table>)> {
+    let mut procedures: BTreeMap<&str, Vec<&Table>> = BTreeMap::new();
+    for table in &schema.tables {
+        for procedure in &table.procedures {
+            procedures.entry(procedure).or_default().push(table);
+        }
+    }
+    procedures.into_iter().collect()
+}
+
+/// Each view selecting from tables of the schema, in name order, with the
+/// tables it selects from in model order.
+pub fn view_dependencies(schema: &DatabaseSchema) -> Vec<(&str, Vec<&Table>)> {
+    let mut views: BTreeMap<&str, Vec<&Table>> = BTreeMap::new();
+    for table in &schema.tables {
+        for view in &table.views {
+            views.entry(view).or_default().push(table);
+        }
+    }
+    views.into_iter().collect()
+}
+
+/// Builds the directed table graph: one node per table, in model order so
+/// that `NodeIndex::new(i)` is `schema.tables[i]`, and one edge per reference
+/// from the referencing table to the referenced one. References to tables
+/// outside the schema are left out.
+pub fn to_graph(schema: &DatabaseSchema) -> Graph<&Table, &Reference> {
+    let mut graph = Graph::with_capacity(schema.tables.len(), schema.references.len());
+    for table in &schema.tables {
+        graph.add_node(table);
+    }
+    let index = node_index(schema);
+    for reference in &schema.references {
+        if let Some((from, to)) = endpoints(&index, reference) {
+            graph.add_edge(from, to, reference);
+        }
+    }
+    graph
+}
+
+/// Maps `(schema, table)` to the table's node in `to_graph`.
+fn node_index(schema: &DatabaseSchema) -> HashMap<(&str, &str), NodeIndex> {
+    schema
+        .tables
+        .iter()
+        .enumerate()
+        .map(|(i, table)| ((table.schema.as_str(), table.name.as_str()), NodeIndex::new(i)))
+        .collect()
+}
+
+/// The nodes `reference` runs from and to, when both tables are in the graph.
+fn endpoints(index: &HashMap<(&str, &str), NodeIndex>, reference: &Reference) -> Option<(NodeIndex, NodeIndex)> {
+    let from = index.get(&(reference.schema.as_str(), reference.table.as_str()))?;
+    let to = index.get(&(reference.referenced_schema.as_str(), reference.referenced_table.as_str()))?;
+    Some((*from, *to))
+}
+
+/// Labels each table, by position, with its strongly connected component.
+fn strongly_connected_components(graph: &Graph<&Table, &Reference>) -> Vec<usize> {
+    let mut component = vec![0; graph.node_count()];
+    for (label, members) in kosaraju_scc(graph).into_iter().enumerate() {
+        for node in members {
+            component[node.index()] = label;
+        }
+    }
+    component
+}
+
+/// Finds circular foreign-key chains (A → B → A), returning the tables of
+/// each cycle. Self-references are left out since they are the usual way to
+/// model hierarchies.
+pub fn find_cycles(schema: &DatabaseSchema) -> Vec<Vec<&Table>> {
+    let component = strongly_connected_components(&to_graph(schema));
+    let mut groups: Vec<(usize, Vec<&Table>)> = Vec::new();
+    for (table, &label) in schema.tables.iter().zip(&component) {
+        match groups.iter_mut().find(|(group, _)| *group == label) {
+            Some((_, members)) => members.push(table),
+            None => groups.push((label, vec![table])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(_, members)| members)
+        .filter(|members| members.len() > 1)
+        .collect()
+}
+
+/// Flags, for each reference in order, whether it is an edge of a circular foreign-key chain.
+pub fn cycle_references(schema: &DatabaseSchema) -> Vec<bool> {
+    let index = node_index(schema);
+    let component = strongly_connected_components(&to_graph(schema));
+    schema
+        .references
+        .iter()
+        .map(|reference| match endpoints(&index, reference) {
+            Some((from, to)) => from != to && component[from.index()] == component[to.index()],
+            None => false,
+        })
+        .collect()
+}
+
+/// Groups tables into clusters linked by foreign keys in either direction,
+/// in order of each cluster's first table, keeping model order within them.
+pub fn connected_components(schema: &DatabaseSchema) -> Vec<Vec<&Table>> {
+    let graph = to_graph(schema);
+    let mut component = vec![usize::MAX; schema.tables.len()];
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    for start in 0..schema.tables.len() {
+        if component[start] != usize::MAX {
+            continue;
+        }
+        component[start] = components.len();
+        let mut members = vec![start];
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            for next in graph.neighbors_undirected(NodeIndex::new(node)).map(|next| next.index()) {
+                if component[next] == usize::MAX {
+                    component[next] = components.len();
+                    members.push(next);
+                    stack.push(next);
+                }
+            }
+        }
+        members.sort_unstable();
+        components.push(members);
+    }
+    components
+        .into_iter()
+        .map(|members| members.into_iter().map(|i| &schema.tables[i]).collect())
+        .collect()
+}
+
+/// Groups tables into tightly linked communities, for the PlantUML
+/// `--cluster-layout`. Starting from one community per table, each table in
+/// turn joins the neighbouring community that most improves the modularity of
+/// the split, until no table moves (the local-moving phase of Louvain). Edges
+/// are undirected and weighted by the number of references between two
+/// tables. Communities are in order of their first table, keeping model order
+/// within them; a community never spans two connected components.
+pub fn communities(schema: &DatabaseSchema) -> Vec<Vec<&Table>> {
+    let graph = to_graph(schema);
+    let count = schema.tables.len();
+    let mut weights: Vec<BTreeMap<usize, f64>> = vec![BTreeMap::new(); count];
+    for edge in graph.raw_edges() {
+        let (from, to) = (edge.source().index(), edge.target().index());
+        if from != to {
+            *weights[from].entry(to).or_default() += 1.0;
+            *weights[to].entry(from).or_default() += 1.0;
+        }
+    }
+    let degree: Vec<f64> = weights.iter().map(|neighbours| neighbours.values().sum()).collect();
+    let total: f64 = degree.iter().sum();
+    let mut community: Vec<usize> = (0..count).collect();
+    let mut community_degree = degree.clone();
+    for _ in 0..COMMUNITY_PASSES {
+        let mut moved = false;
+        for node in 0..count {
+            if degree[node] == 0.0 {
+                continue;
+            }
+            let current = community[node];
+            community_degree[current] -= degree[node];
+            let mut links: BTreeMap<usize, f64> = BTreeMap::new();
+            for (&neighbour, &weight) in &weights[node] {
+                *links.entry(community[neighbour]).or_default() += weight;
+            }
+            // The modularity gain of joining a community, up to a constant factor
+            let gain = |target: usize| {
+                links.get(&target).copied().unwrap_or_default() - degree[node] * community_degree[target] / total
+            };
+            let mut best = current;
+            let mut best_gain = gain(current);
+            for &target in links.keys() {
+                if gain(target) > best_gain + f64::EPSILON {
+                    best = target;
+                    best_gain = gain(target);
+                }
+            }
+            community[node] = best;
+            community_degree[best] += degree[node];
+            moved |= best != current;
+        }
+        if !moved {
+            break;
+        }
+    }
+    let mut groups: Vec<(usize, Vec<&Table>)> = Vec::new();
+    for (table, &label) in schema.tables.iter().zip(&community) {
+        match groups.iter_mut().find(|(group, _)| *group == label) {
+            Some((_, members)) => members.push(table),
+            None => groups.push((label, vec![table])),
+        }
+    }
+    groups.into_iter().map(|(_, members)| members).collect()
+}
+
+/// Ranks tables for the PlantUML `--layered` layout. Tables that reference
+/// no other table form layer 0, and every other table sits one layer below
+/// the deepest table it references. When only circular chains are left, the
+/// first table in model order of a chain that references no unplaced table
+/// outside it is placed as if its references to unplaced tables didn't
+/// exist; with a `seed`, the table is drawn from those candidates by
+/// `SeededRng` instead. The references broken are returned with the layers,
+/// which keep model order within them. Self-references are ignored.
+pub fn layers(schema: &DatabaseSchema, seed: Option<u64>) -> (Vec<Vec<&Table>>, Vec<&Reference>) {
+    let graph = to_graph(schema);
+    let component = strongly_connected_components(&graph);
+    let count = schema.tables.len();
+    let mut outgoing: Vec<Vec<(usize, &Reference)>> = vec![Vec::new(); count];
+    let mut incoming: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for edge in graph.raw_edges() {
+        let (from, to) = (edge.source().index(), edge.target().index());
+        if from != to {
+            outgoing[from].push((to, edge.weight));
+            incoming[to].push(from);
+        }
+    }
+
+    let mut unplaced: Vec<usize> = outgoing.iter().map(Vec::len).collect();
+    let mut layer: Vec<Option<usize>> = vec![None; count];
+    let mut broken = Vec::new();
+    let mut ready: VecDeque<usize> = (0..count).filter(|&node| unplaced[node] == 0).collect();
+    let mut rng = seed.map(SeededRng::new);
+    let mut placed = 0;
+    while placed < count {
+        let node = match ready.pop_front() {
+            Some(node) => node,
+            None => {
+                // Tables waiting on a chain wait until it is broken
+                let candidates: Vec<usize> = (0..count)
+                    .filter(|&node| {
+                        layer[node].is_none()
+                            && (0..count).filter(|&other| component[other] == component[node]).all(|member| {
+                                outgoing[member]
+                                    .iter()
+                                    .all(|(to, _)| component[*to] == component[node] || layer[*to].is_some())
+                            })
+                    })
+                    .collect();
+                assert!(!candidates.is_empty(), "a circular chain is left to break");
+                let node = candidates[rng.as_mut().map_or(0, |rng| rng.below(candidates.len()))];
+                broken.extend(
+                    outgoing[node]
+                        .iter()
+                        .filter(|(to, _)| layer[*to].is_none())
+                        .map(|(_, reference)| *reference),
+                );
+                node
+            }
+        };
+        if layer[node].is_some() {
+            continue;
+        }
+        let depth = outgoing[node].iter().filter_map(|(to, _)| layer[*to]).max();
+        layer[node] = Some(depth.map_or(0, |depth| depth + 1));
+        placed += 1;
+        for &from in &incoming[node] {
+            unplaced[from] -= 1;
+            if unplaced[from] == 0 && layer[from].is_none() {
+                ready.push_back(from);
+            }
+        }
+    }
+
+    let mut layers: Vec<Vec<&Table>> = Vec::new();
+    for (table, layer) in schema.tables.iter().zip(layer) {
+        let layer = layer.expect("every table is placed");
+        if layers.len() <= layer {
+            layers.resize(layer + 1, Vec::new());
+        }
+        layers[layer].push(table);
+    }
+    (layers, broken)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{reference, schema, table, two_triangles};
+
+    fn names(tables: &[&Table]) -> Vec<String> {
+        tables.iter().map(|table| format!("{}.{}", table.schema, table.name)).collect()
+    }
+
+    /// Order → Customer ⇄ Account, a self-referencing Employee and a
+    /// reference to a table outside the schema.
+    fn sample() -> DatabaseSchema {
+        schema(
+            vec![
+                table("dbo.Order", &["Id", "CustomerId", "RegionId"], &["Id"]),
+                table("dbo.Customer", &["Id", "AccountId"], &["Id"]),
+                table("dbo.Account", &["Id", "OwnerId"], &["Id"]),
+                table("dbo.Employee", &["Id", "ManagerId"], &["Id"]),
+            ],
+            vec![
+                reference("FK_Order_Customer", "dbo.Order.CustomerId", "dbo.Customer.Id"),
+                reference("FK_Customer_Account", "dbo.Customer.AccountId", "dbo.Account.Id"),
+                reference("FK_Account_Owner", "dbo.Account.OwnerId", "dbo.Customer.Id"),
+                reference("FK_Employee_Manager", "dbo.Employee.ManagerId", "dbo.Employee.Id"),
+                reference("FK_Order_Region", "dbo.Order.RegionId", "ref.Region.Id"),
+            ],
+        )
+    }
+
+    #[test]
+    fn communities_split_at_the_bridge() {
+        let schema = two_triangles();
+        let groups: Vec<Vec<String>> = communities(&schema).iter().map(|group| names(group)).collect();
+        assert_eq!(groups, [["dbo.a", "dbo.b", "dbo.c"], ["dbo.d", "dbo.e", "dbo.f"]]);
+        assert_eq!(connected_components(&schema).len(), 1);
+    }
+
+    /// OrderLine → Order → Customer and OrderLine → Product, listed leaves first.
+    fn chain() -> DatabaseSchema {
+        schema(
+            vec![
+                table("dbo.OrderLine", &["Id", "OrderId", "ProductId"], &["Id"]),
+                table("dbo.Order", &["Id", "CustomerId"], &["Id"]),
+                table("dbo.Customer", &["Id"], &["Id"]),
+                table("dbo.Product", &["Id"], &["Id"]),
+            ],
+            vec![
+                reference("FK_OrderLine_Order", "dbo.OrderLine.OrderId", "dbo.Order.Id"),
+                reference("FK_OrderLine_Product", "dbo.OrderLine.ProductId", "dbo.Product.Id"),
+                reference("FK_Order_Customer", "dbo.Order.CustomerId", "dbo.Customer.Id"),
+            ],
+        )
+    }
+
+    #[test]
+    fn layers_rank_an_acyclic_chain_from_its_roots() {
+        let schema = chain();
+        let (layers, broken) = layers(&schema, None);
+        let layers: Vec<Vec<String>> = layers.iter().map(|layer| names(layer)).collect();
+        assert_eq!(layers, [vec!["dbo.Customer", "dbo.Product"], vec!["dbo.Order"], vec!["dbo.OrderLine"]]);
+        assert!(broken.is_empty());
+    }
+
+    #[test]
+    fn layers_break_cycles_at_the_first_table() {
+        let schema = sample();
+        let (layers, broken) = layers(&schema, None);
+        let layers: Vec<Vec<String>> = layers.iter().map(|layer| names(layer)).collect();
+        assert_eq!(layers, [vec!["dbo.Customer", "dbo.Employee"], vec!["dbo.Order", "dbo.Account"]]);
+        let broken: Vec<&str> = broken.iter().map(|reference| reference.constraint_name.as_str()).collect();
+        assert_eq!(broken, ["FK_Customer_Account"]);
+    }
+
+    #[test]
+    fn seeded_layers_still_break_each_chain_once() {
+        for seed in 0..8 {
+            let schema = sample();
+            let (layers, broken) = layers(&schema, Some(seed));
+            assert_eq!(layers.iter().map(Vec::len).sum::<usize>(), 4);
+            assert_eq!(broken.len(), 1, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn graph_has_a_node_per_table_and_an_edge_per_reference() {
+        let schema = sample();
+        let graph = to_graph(&schema);
+        assert_eq!(graph.node_count(), 4);
+        // The reference to ref.Region has no node to point at
+        assert_eq!(graph.edge_count(), 4);
+        for (i, table) in schema.tables.iter().enumerate() {
+            assert!(std::ptr::eq(graph[NodeIndex::new(i)], table));
+        }
+        let edge = &graph.raw_edges()[0];
+        assert_eq!((edge.source().index(), edge.target().index()), (0, 1));
+        assert_eq!(edge.weight.constraint_name, "FK_Order_Customer");
+    }
+
+    #[test]
+    fn cycles_leave_out_self_references() {
+        let schema = sample();
+        let cycles: Vec<Vec<String>> = find_cycles(&schema).iter().map(|cycle| names(cycle)).collect();
+        assert_eq!(cycles, [["dbo.Customer", "dbo.Account"]]);
+        assert_eq!(cycle_references(&schema), [false, true, true, false, false]);
+    }
+
+    #[test]
+    fn components_follow_references_both_ways() {
+        let mut schema = sample();
+        schema.tables.push(table("dbo.Setting", &["Id"], &["Id"]));
+        let components: Vec<Vec<String>> =
+            connected_components(&schema).iter().map(|component| names(component)).collect();
+        assert_eq!(
+            components,
+            [vec!["dbo.Order", "dbo.Customer", "dbo.Account"], vec!["dbo.Employee"], vec!["dbo.Setting"]]
+        );
+    }
+
+    #[test]
+    fn a_two_table_cycle_is_warned_about_and_its_edges_flagged() {
+        let schema = schema(
+            vec![
+                table("dbo.Author", &["Id", "LatestBookId"], &["Id"]),
+                table("dbo.Book", &["Id", "AuthorId"], &["Id"]),
+                table("dbo.Review", &["Id", "BookId"], &["Id"]),
+            ],
+            vec![
+                reference("FK_Author_LatestBook", "dbo.Author.LatestBookId", "dbo.Book.Id"),
+                reference("FK_Book_Author", "dbo.Book.AuthorId", "dbo.Author.Id"),
+                reference("FK_Review_Book", "dbo.Review.BookId", "dbo.Book.Id"),
+            ],
+        );
+        assert_eq!(design_warnings(&schema), ["circular foreign keys between dbo.Author, dbo.Book"]);
+        let flagged: Vec<&str> = schema
+            .references
+            .iter()
+            .zip(cycle_references(&schema))
+            .filter(|(_, in_cycle)| *in_cycle)
+            .map(|(reference, _)| reference.constraint_name.as_str())
+            .collect();
+        assert_eq!(flagged, ["FK_Author_LatestBook", "FK_Book_Author"]);
+    }
+
+    #[test]
+    fn mismatched_foreign_key_types_are_warned_about() {
+        let schema = schema(
+            vec![
+                table("dbo.Customer", &["Id:bigint"], &["Id"]),
+                table("dbo.Region", &["Id"], &["Id"]),
+                table("dbo.Orders", &["Id", "CustomerId", "RegionId"], &["Id"]),
+            ],
+            vec![
+                reference("FK_Orders_Customer", "dbo.Orders.CustomerId", "dbo.Customer.Id"),
+                reference("FK_Orders_Region", "dbo.Orders.RegionId", "dbo.Region.Id"),
+            ],
+        );
+        assert_eq!(
+            design_warnings(&schema),
+            ["foreign key FK_Orders_Customer joins Orders.CustomerId (int) to Customer.Id (bigint)"]
+        );
+        assert!(type_mismatch(&schema, &schema.references[1]).is_none());
+        let options = crate::render::RenderOptions {
+            highlight_type_mismatches: true,
+            ..crate::render::RenderOptions::default()
+        };
+        let plantuml = crate::render::generate_plantuml(&schema, &options);
+        let edges: Vec<&str> = plantuml.lines().filter(|line| line.contains("-->")).collect();
+        assert_eq!(
+            edges,
+            [
+                "Orders::CustomerId --> Customer::Id : CustomerId <<type mismatch>>",
+                "Orders::RegionId --> Region::Id : RegionId",
+            ]
+        );
+    }
+
+    #[test]
+    fn references_to_columns_missing_from_the_model_are_warned_about() {
+        let mut schema = schema(
+            vec![
+                table("dbo.Customer", &["Id"], &["Id"]),
+                table("dbo.Orders", &["Id"], &["Id"]),
+                table("dbo.Lines", &[], &[]),
+            ],
+            vec![
+                reference("FK_Orders_Customer", "dbo.Orders.CustomerId", "dbo.Customer.Key"),
+                reference("FK_Lines_Orders", "dbo.Lines.OrderId", "dbo.Orders.Id"),
+                reference("FK_Orders_Region", "dbo.Orders.Id", "ref.Region.Id"),
+            ],
+        );
+        schema.tables.push(Table::external_stub("ref", "Region"));
+        let missing = |column: &str| {
+            format!("foreign key FK_Orders_Customer uses column {}, which is not among the extracted columns", column)
+        };
+        // Column-less tables, as under --topology-only, and stubs aren't checked
+        assert_eq!(
+            design_warnings(&schema),
+            ["table dbo.Lines has no primary key".to_string(), missing("Orders.CustomerId"), missing("Customer.Key")]
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use crate::error::DiagramError;
+use crate::model::DatabaseSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// The placeholders `anonymize` handed out, written with `--anonymize-map`
+/// so the original names can be looked up again.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnonymizationMap {
+    pub tables: Vec<TableMapping>,
+    /// The schema-qualified stored procedures of `Table::procedures`.
+    #[serde(default)]
+    pub procedures: Vec<ProcedureMapping>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableMapping {
+    pub placeholder: String,
+    pub schema: String,
+    pub name: String,
+    pub columns: Vec<ColumnMapping>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnMapping {
+    pub placeholder: String,
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcedureMapping {
+    pub placeholder: String,
+    pub name: String,
+}
+
+impl AnonymizationMap {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("the anonymization map serializes to JSON")
+    }
+
+    pub fn from_json(json: &str) -> Result<AnonymizationMap, DiagramError> {
+        serde_json::from_str(json)
+            .map_err(|err| DiagramError::InvalidArgument(format!("invalid anonymization map: {}", err)))
+    }
+
+    /// The placeholder for `schema.table`, handing out the next `TableN`
+    /// the first time the table is seen.
+    fn table(&mut self, schema: &str, table: &str) -> String {
+        let i = self.table_index(schema, table);
+        self.tables[i].placeholder.clone()
+    }
+
+    fn table_index(&mut self, schema: &str, table: &str) -> usize {
+        if let Some(i) = self.tables.iter().position(|mapping| mapping.schema == schema && mapping.name == table) {
+            return i;
+        }
+        self.tables.push(TableMapping {
+            placeholder: format!("Table{}", self.tables.len() + 1),
+            schema: schema.to_string(),
+            name: table.to_string(),
+            columns: Vec::new(),
+        });
+        self.tables.len() - 1
+    }
+
+    /// The placeholder for a column of `schema.table`, numbered `ColN`
+    /// within its table.
+    fn column(&mut self, schema: &str, table: &str, column: &str) -> String {
+        let i = self.table_index(schema, table);
+        let columns = &mut self.tables[i].columns;
+        if let Some(mapping) = columns.iter().find(|mapping| mapping.name == column) {
+            return mapping.placeholder.clone();
+        }
+        let placeholder = format!("Col{}", columns.len() + 1);
+        columns.push(ColumnMapping {
+            placeholder: placeholder.clone(),
+            name: column.to_string(),
+        });
+        placeholder
+    }
+}
+
+/// Replaces table and column names with `Table1`, `Col1`, ... placeholders
+/// for sharing a schema's structure without its business names. Tables are
+/// numbered in schema and name order and columns in column order, so the
+/// same schema always anonymizes the same way. Types, keys and relationships
+/// are kept; anything else that may carry names or data (constraint, index,
+/// trigger and procedure names, descriptions, properties, filter predicates,
+/// bound defaults and rules, view and synonym names, and sample rows) is
+/// renamed or dropped. Procedures become `ProcN`, numbered in name order,
+/// and a temporal table's history table is renamed like any other table.
+/// Schema names are kept.
+pub fn anonymize(mut schema: DatabaseSchema) -> (DatabaseSchema, AnonymizationMap) {
+    let mut map = AnonymizationMap::default();
+    let procedures: BTreeSet<&String> = schema.tables.iter().flat_map(|table| &table.procedures).collect();
+    map.procedures = procedures
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let placeholder = match name.split_once('.') {
+                Some((procedure_schema, _)) => format!("{}.Proc{}", procedure_schema, i + 1),
+                None => format!("Proc{}", i + 1),
+            };
+            ProcedureMapping {
+                placeholder,
+                name: name.clone(),
+            }
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..schema.tables.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (&schema.tables[a], &schema.tables[b]);
+        (&a.schema, &a.name).cmp(&(&b.schema, &b.name))
+    });
+    for i in order {
+        let table = &mut schema.tables[i];
+        for column in &mut table.columns {
+            column.name = map.column(&table.schema, &table.name, &column.name);
+            column.description = None;
+            column.bound_default = None;
+            column.bound_rule = None;
+            column.fulltext_catalog = None;
+        }
+        for column in &mut table.primary_key {
+            *column = map.column(&table.schema, &table.name, column);
+        }
+        for (n, index) in table.indexes.iter_mut().enumerate() {
+            index.name = format!("Index{}", n + 1);
+            for column in index.columns.iter_mut().chain(&mut index.descending).chain(&mut index.included) {
+                *column = map.column(&table.schema, &table.name, column);
+            }
+            index.filter = None;
+        }
+        for (n, trigger) in table.triggers.iter_mut().enumerate() {
+            trigger.name = format!("Trigger{}", n + 1);
+        }
+        for procedure in &mut table.procedures {
+            if let Some(mapping) = map.procedures.iter().find(|mapping| &mapping.name == procedure) {
+                procedure.clone_from(&mapping.placeholder);
+            }
+        }
+        table.description = None;
+        table.annotation = None;
+        table.properties.clear();
+        table.views.clear();
+        table.synonyms.clear();
+        table.sample_rows.clear();
+        table.name = map.table(&table.schema, &table.name);
+    }
+    // After every table has its placeholder, so a history table that is also
+    // diagrammed keeps the number its name earned
+    for table in &mut schema.tables {
+        if let Some((history_schema, history_table)) = &mut table.history_table {
+            *history_table = map.table(history_schema, history_table);
+        }
+    }
+
+    let mut constraints: HashMap<String, String> = HashMap::new();
+    for reference in &mut schema.references {
+        let next = format!("FK{}", constraints.len() + 1);
+        reference.constraint_name = constraints
+            .entry(format!("{}.{}.{}", reference.schema, reference.table, reference.constraint_name))
+            .or_insert(next)
+            .clone();
+        reference.column = map.column(&reference.schema, &reference.table, &reference.column);
+        reference.table = map.table(&reference.schema, &reference.table);
+        reference.referenced_column = map.column(
+            &reference.referenced_schema,
+            &reference.referenced_table,
+            &reference.referenced_column,
+        );
+        reference.referenced_table = map.table(&reference.referenced_schema, &reference.referenced_table);
+        reference.via_synonym = None;
+    }
+    (schema, map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{reference, schema, table};
+
+    fn sample() -> DatabaseSchema {
+        schema(
+            vec![
+                table("sales.Order", &["OrderId", "CustomerId", "Total:decimal(10,2)"], &["OrderId"]),
+                table("dbo.Customer", &["CustomerId", "Email:nvarchar(200)"], &["CustomerId"]),
+            ],
+            vec![reference("FK_Order_Customer", "sales.Order.CustomerId", "dbo.Customer.CustomerId")],
+        )
+    }
+
+    #[test]
+    fn relationships_still_connect() {
+        let (anonymized, map) = anonymize(sample());
+
+        let names: Vec<(&str, &str)> =
+            anonymized.tables.iter().map(|table| (table.schema.as_str(), table.name.as_str())).collect();
+        assert_eq!(names, [("sales", "Table2"), ("dbo", "Table1")]);
+        let order = &anonymized.tables[0];
+        assert_eq!(order.primary_key, ["Col1"]);
+        assert_eq!(order.columns[2].data_type.to_string(), "decimal(10,2)");
+
+        let reference = &anonymized.references[0];
+        assert_eq!(reference.constraint_name, "FK1");
+        assert_eq!((reference.table.as_str(), reference.column.as_str()), ("Table2", "Col2"));
+        assert!(order.columns.iter().any(|column| column.name == reference.column));
+        let customer = &anonymized.tables[1];
+        assert_eq!((reference.referenced_table.as_str(), reference.referenced_column.as_str()), ("Table1", "Col1"));
+        assert_eq!(customer.primary_key, [reference.referenced_column.as_str()]);
+
+        let customer_mapping = map.tables.iter().find(|mapping| mapping.placeholder == "Table1").unwrap();
+        assert_eq!((customer_mapping.schema.as_str(), customer_mapping.name.as_str()), ("dbo", "Customer"));
+        assert_eq!(customer_mapping.columns[1].name, "Email");
+    }
+
+    #[test]
+    fn map_round_trips_and_output_is_deterministic() {
+        let (first, map) = anonymize(sample());
+        assert_eq!(AnonymizationMap::from_json(&map.to_json()).unwrap(), map);
+
+        let (second, again) = anonymize(sample());
+        assert_eq!(serde_json::to_string(&first).unwrap(), serde_json::to_string(&second).unwrap());
+        assert_eq!(map, again);
+    }
+
+    #[test]
+    fn invalid_map_is_rejected() {
+        match AnonymizationMap::from_json("{\"tables\": 3}") {
+            Err(DiagramError::InvalidArgument(message)) => assert!(message.starts_with("invalid anonymization map: ")),
+            other => panic!("expected an invalid map, got {:?}", other.map_err(|err| err.to_string())),
+        }
+    }
+
+    #[test]
+    fn procedures_become_numbered_placeholders() {
+        let mut customer = table("dbo.Customer", &["Id"], &["Id"]);
+        customer.procedures = vec!["sales.usp_Rebate".to_string(), "dbo.usp_Audit".to_string()];
+        let mut order = table("dbo.Order", &["Id", "CustomerId"], &["Id"]);
+        order.procedures = vec!["dbo.usp_Audit".to_string()];
+        let (anonymized, map) = anonymize(schema(
+            vec![customer, order],
+            vec![reference("FK_Order_Customer", "dbo.Order.CustomerId", "dbo.Customer.Id")],
+        ));
+
+        assert_eq!(anonymized.tables[0].procedures, ["sales.Proc2", "dbo.Proc1"]);
+        assert_eq!(anonymized.tables[1].procedures, ["dbo.Proc1"]);
+        let names: Vec<(&str, &str)> = map
+            .procedures
+            .iter()
+            .map(|mapping| (mapping.placeholder.as_str(), mapping.name.as_str()))
+            .collect();
+        assert_eq!(names, [("dbo.Proc1", "dbo.usp_Audit"), ("sales.Proc2", "sales.usp_Rebate")]);
+    }
+
+    #[test]
+    fn history_table_follows_its_placeholder() {
+        let mut price = table("dbo.Price", &["Id"], &["Id"]);
+        price.history_table = Some(("dbo".to_string(), "PriceHistory".to_string()));
+        let mut archived = table("dbo.Archive", &["Id"], &["Id"]);
+        archived.history_table = Some(("history".to_string(), "ArchiveLog".to_string()));
+        let history = table("dbo.PriceHistory", &["Id"], &["Id"]);
+        let (anonymized, map) = anonymize(schema(vec![price, archived, history], Vec::new()));
+
+        let names: Vec<&str> = anonymized.tables.iter().map(|table| table.name.as_str()).collect();
+        assert_eq!(names, ["Table2", "Table1", "Table3"]);
+        assert_eq!(anonymized.tables[0].history_table, Some(("dbo".to_string(), "Table3".to_string())));
+        assert_eq!(anonymized.tables[1].history_table, Some(("history".to_string(), "Table4".to_string())));
+        assert_eq!(map.tables[3].name, "ArchiveLog");
+    }
+}
@@ -0,0 +1,135 @@
+use crate::error::DiagramError;
+use serde::Deserialize;
+
+/// One database listed in a `--batch` file. Connection settings left out
+/// fall back to the ones given on the command line.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchEntry {
+    /// Names the entry's output directory; derived from the server and
+    /// catalog when missing.
+    pub name: Option<String>,
+    pub ip_address: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// The catalog to diagram; comma-separate to merge several, as with
+    /// `--initial_catalog`.
+    pub initial_catalog: String,
+}
+
+impl BatchEntry {
+    /// The name of the directory under `--output-dir` that the entry's files
+    /// are written to: its `name`, or `<server>_<catalog>`, reduced to
+    /// characters that are safe in file names.
+    pub fn directory_name(&self, default_server: &str) -> String {
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => format!(
+                "{}_{}",
+                self.ip_address.as_deref().unwrap_or(default_server),
+                self.initial_catalog
+            ),
+        };
+        let name: String = name
+            .trim()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.') { c } else { '_' })
+            .collect();
+        // Keep the directory inside --output-dir
+        match name.trim_start_matches('.') {
+            "" => "_".to_string(),
+            trimmed => trimmed.to_string(),
+        }
+    }
+}
+
+/// Parses a `--batch` file: one JSON object per line, such as
+/// `{"name": "sales", "initial_catalog": "Sales"}`. Blank lines and lines
+/// starting with `#` are skipped.
+pub fn parse_batch(contents: &str) -> Result<Vec<BatchEntry>, DiagramError> {
+    let mut entries = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry = serde_json::from_str(line).map_err(|err| {
+            DiagramError::InvalidArgument(format!("batch file line {}: {}", number + 1, err))
+        })?;
+        entries.push(entry);
+    }
+    if entries.is_empty() {
+        return Err(DiagramError::InvalidArgument("the batch file lists no databases".to_string()));
+    }
+    Ok(entries)
+}
+
+/// The output directory names of `entries`, in order, suffixed with `_2`,
+/// `_3`, ... where two entries would otherwise share one.
+pub fn directory_names(entries: &[BatchEntry], default_server: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for entry in entries {
+        let base = entry.directory_name(default_server);
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while names.contains(&name) {
+            name = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        names.push(name);
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BATCH: &str = r#"
+# production databases
+{"name": "sales", "initial_catalog": "Sales"}
+{"ip_address": "10.0.0.5,1433", "username": "reader", "initial_catalog": "Crm"}
+
+{"initial_catalog": "Crm"}
+{"initial_catalog": "Crm"}
+{"name": "../../etc", "initial_catalog": "Hr"}
+"#;
+
+    #[test]
+    fn parses_one_entry_per_line() {
+        let entries = parse_batch(BATCH).unwrap();
+        assert_eq!(entries.len(), 5);
+        assert_eq!(
+            entries[1],
+            BatchEntry {
+                name: None,
+                ip_address: Some("10.0.0.5,1433".to_string()),
+                username: Some("reader".to_string()),
+                password: None,
+                initial_catalog: "Crm".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn directory_names_are_safe_and_unique() {
+        let entries = parse_batch(BATCH).unwrap();
+        assert_eq!(
+            directory_names(&entries, "db01"),
+            ["sales", "10.0.0.5_1433_Crm", "db01_Crm", "db01_Crm_2", "_.._etc"]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_lines_and_empty_files() {
+        for (contents, expected) in [
+            ("{\"initial_catalog\": \"Sales\"}\n{\"catalog\": \"Crm\"}", "batch file line 2: unknown field `catalog`"),
+            ("# nothing yet\n", "the batch file lists no databases"),
+        ] {
+            match parse_batch(contents) {
+                Err(DiagramError::InvalidArgument(message)) => assert!(message.starts_with(expected), "{}", message),
+                other => panic!("expected {:?} to be rejected, got {:?}", contents, other.map(|entries| entries.len())),
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use similar::TextDiff;
+
+/// Compares freshly generated output with the contents of an existing file,
+/// returning a unified diff when they differ. A missing file compares as empty.
+pub fn diff_output(path: &str, existing: Option<&str>, generated: &str) -> Option<String> {
+    let existing = existing.unwrap_or_default();
+    if existing == generated {
+        return None;
+    }
+    let diff = TextDiff::from_lines(existing, generated)
+        .unified_diff()
+        .context_radius(3)
+        .header(path, &format!("{} (generated)", path))
+        .to_string();
+    Some(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{header_banner, lookup_format, RenderOptions};
+    use crate::testing::two_triangles;
+
+    #[test]
+    fn identical_contents_give_no_diff() {
+        assert_eq!(diff_output("schema.puml", Some("@startuml\n@enduml\n"), "@startuml\n@enduml\n"), None);
+    }
+
+    #[test]
+    fn differing_contents_give_a_unified_diff() {
+        let diff = diff_output("schema.puml", Some("@startuml\nclass a\n@enduml\n"), "@startuml\nclass b\n@enduml\n");
+        let diff = diff.expect("a diff");
+        assert!(diff.starts_with("--- schema.puml\n+++ schema.puml (generated)\n"), "{}", diff);
+        assert!(diff.contains("\n-class a\n+class b\n"), "{}", diff);
+
+        let diff = diff_output("schema.puml", None, "@startuml\n").expect("a diff against no file");
+        assert!(diff.contains("\n+@startuml\n"), "{}", diff);
+    }
+
+    #[test]
+    fn a_regenerated_banner_is_not_stale() {
+        // Banners carry only the --header-comment text, never a generation
+        // time, so a second run renders the same bytes
+        let options = RenderOptions {
+            header_comment: vec!["Generated from Sales; do not edit".to_string()],
+            ..RenderOptions::default()
+        };
+        let format = lookup_format("plantuml").unwrap();
+        let generate = || header_banner(format, &options) + &format.render(&two_triangles(), &options).unwrap();
+        let committed = generate();
+        assert!(committed.starts_with("' Generated from Sales; do not edit\n"), "{}", committed);
+        assert_eq!(diff_output("schema.puml", Some(&committed), &generate()), None);
+    }
+}
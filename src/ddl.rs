@@ -0,0 +1,759 @@
+use crate::model::{Column, DataType, DatabaseSchema, Reference, Table};
+
+/// A token of a T-SQL script.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    /// A keyword, unquoted identifier or number.
+    Word(String),
+    /// A `[bracketed]` or `"quoted"` identifier, unquoted.
+    Quoted(String),
+    /// A string literal, whose text never matters here.
+    Text,
+    Symbol(char),
+}
+
+/// Splits a script into tokens, each with the line it starts on, dropping
+/// whitespace and `--` and (nested) `/* */` comments.
+fn tokenize(script: &str) -> Vec<(Token, usize)> {
+    let mut tokens = Vec::new();
+    let mut chars = script.chars().peekable();
+    let mut line = 1;
+    let is_word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '$');
+    while let Some(c) = chars.next() {
+        let start = line;
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '-' if chars.peek() == Some(&'-') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut depth = 1;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\n' => line += 1,
+                        '/' if chars.peek() == Some(&'*') => {
+                            chars.next();
+                            depth += 1;
+                        }
+                        '*' if chars.peek() == Some(&'/') => {
+                            chars.next();
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            '[' | '"' | '\'' => {
+                // `]]`, `""` and `''` escape the closing character
+                let close = if c == '[' { ']' } else { c };
+                let mut text = String::new();
+                while let Some(c) = chars.next() {
+                    if c == close {
+                        if chars.peek() != Some(&close) {
+                            break;
+                        }
+                        chars.next();
+                    }
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    text.push(c);
+                }
+                tokens.push((if c == '\'' { Token::Text } else { Token::Quoted(text) }, start));
+            }
+            c if is_word(c) => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek().filter(|&&c| is_word(c)) {
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push((Token::Word(word), start));
+            }
+            c => tokens.push((Token::Symbol(c), start)),
+        }
+    }
+    tokens
+}
+
+/// A foreign key as declared, before its target columns are resolved.
+struct ForeignKey {
+    name: Option<String>,
+    schema: String,
+    table: String,
+    columns: Vec<String>,
+    referenced_schema: String,
+    referenced_table: String,
+    /// Empty when the declaration leaves them to the referenced primary key.
+    referenced_columns: Vec<String>,
+    line: usize,
+}
+
+/// A constraint declared inside `CREATE TABLE` or added by `ALTER TABLE`.
+enum Constraint {
+    PrimaryKey(Vec<String>),
+    ForeignKey(ForeignKey),
+}
+
+/// Walks the tokens of a script, collecting the tables and constraints of
+/// the statements it recognizes.
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    tables: Vec<Table>,
+    foreign_keys: Vec<ForeignKey>,
+    /// `(schema, table, constraint)` of `NOCHECK CONSTRAINT` statements, with
+    /// `None` for `NOCHECK CONSTRAINT ALL`.
+    disabled: Vec<(String, String, Option<String>)>,
+    warnings: Vec<String>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_symbol(&mut self, symbol: char) -> bool {
+        let found = self.peek() == Some(&Token::Symbol(symbol));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> Result<(), String> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}' on line {}", symbol, self.line()))
+        }
+    }
+
+    /// Whether the current token is a `GO` batch separator: a `GO` starting
+    /// its line.
+    fn at_go(&self) -> bool {
+        self.is_keyword("GO") && (self.pos == 0 || self.tokens[self.pos - 1].1 < self.tokens[self.pos].1)
+    }
+
+    fn identifier(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(Token::Word(word) | Token::Quoted(word)) => {
+                let identifier = word.clone();
+                self.pos += 1;
+                Ok(identifier)
+            }
+            _ => Err(format!("expected a name on line {}", self.line())),
+        }
+    }
+
+    /// A name of up to four dot-separated parts, such as `[dbo].[Orders]`
+    /// or `Sales..Orders`, returned as `(schema, name)` with the schema
+    /// defaulting to `dbo`.
+    fn table_name(&mut self) -> Result<(String, String), String> {
+        let mut parts = vec![self.identifier()?];
+        while self.eat_symbol('.') {
+            if self.peek() == Some(&Token::Symbol('.')) {
+                parts.push(String::new());
+            } else {
+                parts.push(self.identifier()?);
+            }
+        }
+        let name = parts.pop().unwrap_or_default();
+        let schema = parts.pop().filter(|schema| !schema.is_empty()).unwrap_or_else(|| "dbo".to_string());
+        Ok((schema, name))
+    }
+
+    /// Skips a parenthesized group, including any nested ones, when the
+    /// current token opens one.
+    fn skip_parens(&mut self) {
+        if !self.eat_symbol('(') {
+            return;
+        }
+        let mut depth = 1;
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Symbol('(') => depth += 1,
+                Token::Symbol(')') => depth -= 1,
+                _ => {}
+            }
+            self.pos += 1;
+            if depth == 0 {
+                break;
+            }
+        }
+    }
+
+    /// Skips to the `,` or `)` ending the current table element, without
+    /// consuming it. An element of `ALTER TABLE ... ADD` has no closing
+    /// parenthesis, so the end of the statement stops the skip too.
+    fn skip_element(&mut self) {
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Symbol(',' | ')' | ';') => break,
+                Token::Symbol('(') => self.skip_parens(),
+                _ if self.at_go() || self.is_keyword("CREATE") || self.is_keyword("ALTER") => break,
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    /// Skips to the start of the next statement: past a `;`, or up to a
+    /// `GO` or a `CREATE` or `ALTER` outside parentheses.
+    fn skip_statement(&mut self) {
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Symbol(';') => {
+                    self.pos += 1;
+                    break;
+                }
+                Token::Symbol('(') => self.skip_parens(),
+                _ if self.at_go() || self.is_keyword("CREATE") || self.is_keyword("ALTER") => break,
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    /// Skips to just past the next `GO`, for batches such as procedure
+    /// bodies whose statements aren't the script's own.
+    fn skip_batch(&mut self) {
+        while self.pos < self.tokens.len() && !self.at_go() {
+            self.pos += 1;
+        }
+        self.pos += 1;
+    }
+
+    /// A parenthesized column list, such as `([OrderId] ASC, LineNo)`.
+    fn column_list(&mut self) -> Result<Vec<String>, String> {
+        self.expect_symbol('(')?;
+        let mut columns = Vec::new();
+        loop {
+            columns.push(self.identifier()?);
+            if !self.eat_keyword("ASC") {
+                self.eat_keyword("DESC");
+            }
+            if !self.eat_symbol(',') {
+                break;
+            }
+        }
+        self.expect_symbol(')')?;
+        Ok(columns)
+    }
+
+    /// `REFERENCES table [(columns)]`, after which actions such as `ON
+    /// DELETE CASCADE` are left to the caller to skip.
+    fn references(
+        &mut self,
+        name: Option<String>,
+        table: &(String, String),
+        columns: Vec<String>,
+    ) -> Result<ForeignKey, String> {
+        let line = self.line();
+        if !self.eat_keyword("REFERENCES") {
+            return Err(format!("expected REFERENCES on line {}", line));
+        }
+        let (referenced_schema, referenced_table) = self.table_name()?;
+        let referenced_columns = if self.peek() == Some(&Token::Symbol('(')) {
+            self.column_list()?
+        } else {
+            Vec::new()
+        };
+        Ok(ForeignKey {
+            name,
+            schema: table.0.clone(),
+            table: table.1.clone(),
+            columns,
+            referenced_schema,
+            referenced_table,
+            referenced_columns,
+            line,
+        })
+    }
+
+    /// A table constraint such as `CONSTRAINT PK_Orders PRIMARY KEY
+    /// CLUSTERED (Id)` or `FOREIGN KEY (CustomerId) REFERENCES Customers`.
+    /// Unique, check and other constraints are skipped as `None`.
+    fn table_constraint(&mut self, table: &(String, String)) -> Result<Option<Constraint>, String> {
+        let name = if self.eat_keyword("CONSTRAINT") {
+            Some(self.identifier()?)
+        } else {
+            None
+        };
+        let constraint = if self.eat_keyword("PRIMARY") {
+            if !self.eat_keyword("KEY") {
+                return Err(format!("expected KEY after PRIMARY on line {}", self.line()));
+            }
+            if !self.eat_keyword("CLUSTERED") {
+                self.eat_keyword("NONCLUSTERED");
+            }
+            Some(Constraint::PrimaryKey(self.column_list()?))
+        } else if self.eat_keyword("FOREIGN") {
+            if !self.eat_keyword("KEY") {
+                return Err(format!("expected KEY after FOREIGN on line {}", self.line()));
+            }
+            let columns = self.column_list()?;
+            Some(Constraint::ForeignKey(self.references(name, table, columns)?))
+        } else {
+            None
+        };
+        self.skip_element();
+        Ok(constraint)
+    }
+
+    /// A column definition, such as `[Id] int IDENTITY(1,1) NOT NULL
+    /// PRIMARY KEY`, with the key constraints declared on it.
+    fn column(&mut self, table: &(String, String)) -> Result<(Column, Vec<Constraint>), String> {
+        let name = self.identifier()?;
+        let computed = self.eat_keyword("AS");
+        let data_type = if computed {
+            // A computed column, whose type the script doesn't declare
+            DataType::new("computed")
+        } else {
+            let (_, base) = self.table_name()?;
+            let mut declaration = base;
+            if self.eat_symbol('(') {
+                let mut arguments = Vec::new();
+                while let Some(Token::Word(argument)) = self.peek() {
+                    arguments.push(argument.clone());
+                    self.pos += 1;
+                    if !self.eat_symbol(',') {
+                        break;
+                    }
+                }
+                self.expect_symbol(')')?;
+                declaration = format!("{}({})", declaration, arguments.join(","));
+            }
+            DataType::parse(&declaration)
+        };
+        let mut column = Column {
+            name,
+            data_type,
+            description: None,
+            bound_default: None,
+            bound_rule: None,
+            collation: None,
+            fulltext_catalog: None,
+            computed,
+        };
+
+        let mut constraints = Vec::new();
+        let mut constraint_name = None;
+        while let Some(token) = self.peek().cloned() {
+            match token {
+                Token::Symbol(',' | ')') => break,
+                Token::Symbol('(') => self.skip_parens(),
+                _ if self.eat_keyword("CONSTRAINT") => constraint_name = Some(self.identifier()?),
+                _ if self.eat_keyword("COLLATE") => column.collation = Some(self.identifier()?),
+                _ if self.eat_keyword("PRIMARY") => {
+                    if !self.eat_keyword("KEY") {
+                        return Err(format!("expected KEY after PRIMARY on line {}", self.line()));
+                    }
+                    constraints.push(Constraint::PrimaryKey(vec![column.name.clone()]));
+                }
+                _ if self.is_keyword("FOREIGN") || self.is_keyword("REFERENCES") => {
+                    if self.eat_keyword("FOREIGN") && !self.eat_keyword("KEY") {
+                        return Err(format!("expected KEY after FOREIGN on line {}", self.line()));
+                    }
+                    let foreign_key = self.references(constraint_name.take(), table, vec![column.name.clone()])?;
+                    constraints.push(Constraint::ForeignKey(foreign_key));
+                }
+                _ => self.pos += 1,
+            }
+        }
+        Ok((column, constraints))
+    }
+
+    /// One comma-separated element of a `CREATE TABLE` body or `ALTER TABLE
+    /// ADD` list: a column or a table constraint.
+    fn element(&mut self, table: &(String, String)) -> Result<(Option<Column>, Vec<Constraint>), String> {
+        let is_constraint = ["CONSTRAINT", "PRIMARY", "FOREIGN", "UNIQUE", "CHECK", "INDEX", "PERIOD"]
+            .iter()
+            .any(|keyword| self.is_keyword(keyword));
+        if is_constraint {
+            Ok((None, self.table_constraint(table)?.into_iter().collect()))
+        } else {
+            let (column, constraints) = self.column(table)?;
+            Ok((Some(column), constraints))
+        }
+    }
+
+    fn apply(&mut self, table: &(String, String), constraints: Vec<Constraint>, line: usize) {
+        for constraint in constraints {
+            match constraint {
+                Constraint::PrimaryKey(columns) => match self.find_table(&table.0, &table.1) {
+                    Some(i) => self.tables[i].primary_key = columns,
+                    None => self.warnings.push(format!(
+                        "line {}: ignored a primary key on {}.{}, which the script doesn't create",
+                        line, table.0, table.1
+                    )),
+                },
+                Constraint::ForeignKey(foreign_key) => self.foreign_keys.push(foreign_key),
+            }
+        }
+    }
+
+    fn find_table(&self, schema: &str, name: &str) -> Option<usize> {
+        self.tables
+            .iter()
+            .position(|table| table.schema.eq_ignore_ascii_case(schema) && table.name.eq_ignore_ascii_case(name))
+    }
+
+    /// `CREATE TABLE name (elements) [ON filegroup] ...`, after `CREATE TABLE`.
+    fn create_table(&mut self) -> Result<(), String> {
+        let line = self.line();
+        let name = self.table_name()?;
+        if name.1.starts_with('#') {
+            // Temporary tables aren't part of the schema
+            self.skip_statement();
+            return Ok(());
+        }
+        self.expect_symbol('(')?;
+        let mut table = Table::external_stub(&name.0, &name.1);
+        table.external = false;
+        let mut constraints = Vec::new();
+        loop {
+            let (column, element_constraints) = self.element(&name)?;
+            table.columns.extend(column);
+            constraints.extend(element_constraints);
+            if !self.eat_symbol(',') {
+                break;
+            }
+            // A trailing comma before the closing parenthesis
+            if self.peek() == Some(&Token::Symbol(')')) {
+                break;
+            }
+        }
+        self.expect_symbol(')')?;
+        if self.eat_keyword("ON") {
+            table.filegroup = Some(self.identifier()?);
+        }
+        table.history_table = self.history_table()?;
+        if self.find_table(&name.0, &name.1).is_some() {
+            return Err(format!("{}.{} is created twice", name.0, name.1));
+        }
+        self.tables.push(table);
+        self.apply(&name, constraints, line);
+        self.skip_statement();
+        Ok(())
+    }
+
+    /// The table named by a `HISTORY_TABLE = name` option, such as the one of
+    /// `WITH (SYSTEM_VERSIONING = ON (HISTORY_TABLE = dbo.OrdersHistory))`,
+    /// in the rest of the statement. Stops just past it, or where
+    /// `skip_statement` would otherwise.
+    fn history_table(&mut self) -> Result<Option<(String, String)>, String> {
+        let mut depth = 0;
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Symbol('(') => depth += 1,
+                Token::Symbol(')') => depth -= 1,
+                Token::Symbol(';') if depth == 0 => break,
+                _ if depth == 0 && (self.at_go() || self.is_keyword("CREATE") || self.is_keyword("ALTER")) => break,
+                _ if self.is_keyword("HISTORY_TABLE") => {
+                    self.pos += 1;
+                    self.expect_symbol('=')?;
+                    return self.table_name().map(Some);
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        Ok(None)
+    }
+
+    /// `ALTER TABLE name [WITH CHECK|NOCHECK] ADD ...` and `ALTER TABLE name
+    /// NOCHECK CONSTRAINT ...`, after `ALTER TABLE`. Other alterations are
+    /// skipped.
+    fn alter_table(&mut self) -> Result<(), String> {
+        let line = self.line();
+        let name = self.table_name()?;
+        if self.eat_keyword("WITH") && !self.eat_keyword("CHECK") {
+            self.eat_keyword("NOCHECK");
+        }
+        if self.eat_keyword("ADD") {
+            let mut constraints = Vec::new();
+            loop {
+                let (column, element_constraints) = self.element(&name)?;
+                if let Some(column) = column {
+                    match self.find_table(&name.0, &name.1) {
+                        Some(i) => self.tables[i].columns.push(column),
+                        None => self.warnings.push(format!(
+                            "line {}: ignored column {} added to {}.{}, which the script doesn't create",
+                            line, column.name, name.0, name.1
+                        )),
+                    }
+                }
+                constraints.extend(element_constraints);
+                if !self.eat_symbol(',') {
+                    break;
+                }
+            }
+            self.apply(&name, constraints, line);
+        } else if self.eat_keyword("NOCHECK") && self.eat_keyword("CONSTRAINT") {
+            loop {
+                let constraint = self.identifier()?;
+                let constraint = (!constraint.eq_ignore_ascii_case("ALL")).then_some(constraint);
+                self.disabled.push((name.0.clone(), name.1.clone(), constraint));
+                if !self.eat_symbol(',') {
+                    break;
+                }
+            }
+        }
+        self.skip_statement();
+        Ok(())
+    }
+
+    fn parse(&mut self) {
+        while self.pos < self.tokens.len() {
+            let line = self.line();
+            if self.eat_keyword("CREATE") {
+                if self.eat_keyword("OR") {
+                    self.eat_keyword("ALTER");
+                }
+                let is_module = ["PROC", "PROCEDURE", "FUNCTION", "TRIGGER", "VIEW"]
+                    .iter()
+                    .any(|keyword| self.is_keyword(keyword));
+                if is_module {
+                    self.skip_batch();
+                } else if self.eat_keyword("TABLE") {
+                    if let Err(reason) = self.create_table() {
+                        self.warnings.push(format!("line {}: skipped a CREATE TABLE statement: {}", line, reason));
+                        self.skip_statement();
+                    }
+                }
+            } else if self.eat_keyword("ALTER") {
+                if self.eat_keyword("TABLE") {
+                    if let Err(reason) = self.alter_table() {
+                        self.warnings.push(format!("line {}: skipped an ALTER TABLE statement: {}", line, reason));
+                        self.skip_statement();
+                    }
+                }
+            } else if self.peek() == Some(&Token::Symbol('(')) {
+                self.skip_parens();
+            } else {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Turns the collected foreign keys into one reference per column pair,
+    /// resolving omitted target columns to the referenced primary key and
+    /// naming unnamed constraints `FK_<table>_<referenced table>`.
+    fn references_of(&mut self) -> Vec<Reference> {
+        let mut references: Vec<Reference> = Vec::new();
+        for foreign_key in std::mem::take(&mut self.foreign_keys) {
+            let target = self
+                .find_table(&foreign_key.referenced_schema, &foreign_key.referenced_table)
+                .map(|i| &self.tables[i]);
+            let referenced_columns = if foreign_key.referenced_columns.is_empty() {
+                target.map(|table| table.primary_key.clone()).unwrap_or_default()
+            } else {
+                foreign_key.referenced_columns.clone()
+            };
+            if referenced_columns.len() != foreign_key.columns.len() {
+                self.warnings.push(format!(
+                    "line {}: skipped a foreign key from {}.{} whose columns don't match those of {}.{}",
+                    foreign_key.line,
+                    foreign_key.schema,
+                    foreign_key.table,
+                    foreign_key.referenced_schema,
+                    foreign_key.referenced_table
+                ));
+                continue;
+            }
+            let references_pk = target.is_none_or(|table| {
+                table.primary_key.len() == referenced_columns.len()
+                    && referenced_columns
+                        .iter()
+                        .all(|column| table.primary_key.iter().any(|key| key.eq_ignore_ascii_case(column)))
+            });
+            // Reference the tables by the names they were created with
+            let (schema, table) = match self.find_table(&foreign_key.schema, &foreign_key.table) {
+                Some(i) => (self.tables[i].schema.clone(), self.tables[i].name.clone()),
+                None => (foreign_key.schema.clone(), foreign_key.table.clone()),
+            };
+            let (referenced_schema, referenced_table) = match target {
+                Some(target) => (target.schema.clone(), target.name.clone()),
+                None => (foreign_key.referenced_schema.clone(), foreign_key.referenced_table.clone()),
+            };
+            let constraint_name = foreign_key.name.clone().unwrap_or_else(|| {
+                let base = format!("FK_{}_{}", table, referenced_table);
+                let mut name = base.clone();
+                let mut suffix = 2;
+                while references.iter().any(|reference| reference.constraint_name == name) {
+                    name = format!("{}_{}", base, suffix);
+                    suffix += 1;
+                }
+                name
+            });
+            let is_disabled = self.disabled.iter().any(|(disabled_schema, disabled_table, constraint)| {
+                disabled_schema.eq_ignore_ascii_case(&schema)
+                    && disabled_table.eq_ignore_ascii_case(&table)
+                    && constraint
+                        .as_ref()
+                        .is_none_or(|constraint| constraint.eq_ignore_ascii_case(&constraint_name))
+            });
+            for (column, referenced_column) in foreign_key.columns.iter().zip(referenced_columns) {
+                references.push(Reference {
+                    constraint_name: constraint_name.clone(),
+                    schema: schema.clone(),
+                    table: table.clone(),
+                    column: column.clone(),
+                    referenced_schema: referenced_schema.clone(),
+                    referenced_table: referenced_table.clone(),
+                    referenced_column,
+                    is_disabled,
+                    references_pk,
+                    via_synonym: None,
+                });
+            }
+        }
+        references
+    }
+}
+
+/// Reads the tables, primary keys and foreign keys declared by a T-SQL DDL
+/// script, such as one generated by SSMS, without a server: `CREATE TABLE`
+/// statements with their column and table constraints, `ALTER TABLE ...
+/// ADD` columns and constraints, and `ALTER TABLE ... NOCHECK CONSTRAINT`.
+/// Other statements are skipped, as are whole batches creating procedures,
+/// functions, triggers or views. Returns the schema with a warning for each
+/// table statement that couldn't be parsed.
+pub fn parse_ddl(script: &str) -> (DatabaseSchema, Vec<String>) {
+    let mut parser = Parser {
+        tokens: tokenize(script),
+        pos: 0,
+        tables: Vec::new(),
+        foreign_keys: Vec::new(),
+        disabled: Vec::new(),
+        warnings: Vec::new(),
+    };
+    parser.parse();
+    let references = parser.references_of();
+    let schema = DatabaseSchema {
+        tables: parser.tables,
+        references,
+    };
+    (schema, parser.warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{reference_lines, table_names};
+
+    const SCRIPT: &str = "
+CREATE TABLE [dbo].[Order] (
+    [OrderId] INT NOT NULL IDENTITY(1,1),
+    [Region] NVARCHAR(10) NOT NULL,
+    [Total] DECIMAL(10, 2) NULL DEFAULT 0,
+    CONSTRAINT [PK_Order] PRIMARY KEY CLUSTERED ([OrderId], [Region])
+);
+GO
+CREATE TABLE dbo.OrderLine (
+    LineId int NOT NULL PRIMARY KEY,
+    OrderId int NOT NULL,
+    Region nvarchar(10) NOT NULL,
+    ProductId int NULL,
+    CONSTRAINT FK_OrderLine_Order FOREIGN KEY (OrderId, Region) REFERENCES dbo.[Order] (OrderId, Region)
+);
+GO
+ALTER TABLE dbo.OrderLine ADD CONSTRAINT FK_OrderLine_Product FOREIGN KEY (ProductId) REFERENCES dbo.Product (Id);
+ALTER TABLE dbo.OrderLine NOCHECK CONSTRAINT FK_OrderLine_Product;
+GO
+CREATE PROCEDURE dbo.usp_Orders AS SELECT * FROM dbo.[Order];
+GO
+CREATE TABLE dbo.Broken (Id int NOT NULL,
+";
+
+    #[test]
+    fn parses_composite_keys_and_foreign_keys() {
+        let (schema, warnings) = parse_ddl(SCRIPT);
+        assert_eq!(table_names(&schema), ["dbo.Order", "dbo.OrderLine"]);
+        let order = &schema.tables[0];
+        let columns: Vec<String> =
+            order.columns.iter().map(|column| format!("{} {}", column.name, column.data_type)).collect();
+        assert_eq!(columns, ["OrderId int", "Region nvarchar(10)", "Total decimal(10,2)"]);
+        assert_eq!(order.primary_key, ["OrderId", "Region"]);
+        assert_eq!(schema.tables[1].primary_key, ["LineId"]);
+        assert_eq!(
+            reference_lines(&schema),
+            [
+                "FK_OrderLine_Order: dbo.OrderLine.OrderId -> dbo.Order.OrderId",
+                "FK_OrderLine_Order: dbo.OrderLine.Region -> dbo.Order.Region",
+                "FK_OrderLine_Product: dbo.OrderLine.ProductId -> dbo.Product.Id (disabled)",
+            ]
+        );
+        assert_eq!(warnings, ["line 22: skipped a CREATE TABLE statement: expected a name on line 22"]);
+    }
+
+    #[test]
+    fn names_unnamed_foreign_keys_and_resolves_omitted_columns() {
+        let (schema, warnings) = parse_ddl(
+            "CREATE TABLE Customer (Id int PRIMARY KEY);
+            CREATE TABLE [Order] (
+                Id int PRIMARY KEY,
+                CustomerId int REFERENCES Customer,
+                BillToId int FOREIGN KEY REFERENCES dbo.Customer (Id) ON DELETE NO ACTION
+            );",
+        );
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(table_names(&schema), ["dbo.Customer", "dbo.Order"]);
+        assert_eq!(
+            reference_lines(&schema),
+            [
+                "FK_Order_Customer: dbo.Order.CustomerId -> dbo.Customer.Id",
+                "FK_Order_Customer_2: dbo.Order.BillToId -> dbo.Customer.Id",
+            ]
+        );
+    }
+
+    #[test]
+    fn references_to_a_unique_column_are_not_primary_key_references() {
+        let (schema, _) = parse_ddl(
+            "CREATE TABLE dbo.Customer (Id int NOT NULL PRIMARY KEY, Code char(8) NOT NULL UNIQUE);
+             CREATE TABLE dbo.Orders (Id int NOT NULL PRIMARY KEY, CustomerId int, CustomerCode char(8));
+             ALTER TABLE dbo.Orders ADD CONSTRAINT FK_Orders_Id FOREIGN KEY (CustomerId) REFERENCES dbo.Customer (Id);
+             ALTER TABLE dbo.Orders ADD CONSTRAINT FK_Orders_Code
+                 FOREIGN KEY (CustomerCode) REFERENCES dbo.Customer (Code);",
+        );
+        let targets: Vec<(&str, bool)> = schema
+            .references
+            .iter()
+            .map(|reference| (reference.constraint_name.as_str(), reference.references_pk))
+            .collect();
+        assert_eq!(targets, [("FK_Orders_Id", true), ("FK_Orders_Code", false)]);
+
+        let plantuml = crate::render::generate_plantuml(&schema, &crate::RenderOptions::default());
+        let edges: Vec<&str> = plantuml.lines().filter(|line| line.contains("-->")).collect();
+        assert_eq!(edges.len(), 2, "{}", plantuml);
+        assert!(!edges[0].contains("<<unique>>") && edges[1].contains("<<unique>>"), "{}", plantuml);
+    }
+}
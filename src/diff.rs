@@ -0,0 +1,407 @@
+use crate::error::DiagramError;
+use crate::model::{DatabaseSchema, Reference, Table};
+use serde::Serialize;
+
+/// How the `--compare-to` report is printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiffOutput {
+    #[default]
+    Text,
+    Json,
+    Markdown,
+}
+
+impl DiffOutput {
+    pub fn parse(value: &str) -> Option<DiffOutput> {
+        match value {
+            "text" => Some(DiffOutput::Text),
+            "json" => Some(DiffOutput::Json),
+            "markdown" => Some(DiffOutput::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// A column whose data type changed between the two schemas.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ColumnChange {
+    pub name: String,
+    pub old_type: String,
+    pub new_type: String,
+}
+
+/// Column changes in a table present in both schemas.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TableDiff {
+    /// The table as `schema.name`.
+    pub table: String,
+    pub added_columns: Vec<String>,
+    pub removed_columns: Vec<String>,
+    pub changed_columns: Vec<ColumnChange>,
+}
+
+/// The structural differences between an earlier schema and the current one.
+/// Tables are named `schema.name` and references are described as
+/// `FK_Name (dbo.Orders.CustomerId -> dbo.Customers.Id)`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SchemaDiff {
+    pub added_tables: Vec<String>,
+    pub removed_tables: Vec<String>,
+    pub changed_tables: Vec<TableDiff>,
+    pub added_references: Vec<String>,
+    pub removed_references: Vec<String>,
+}
+
+/// Reads a schema snapshot written earlier with `--format yaml`.
+pub fn parse_snapshot(yaml: &str) -> Result<DatabaseSchema, DiagramError> {
+    serde_yaml::from_str(yaml).map_err(|err| DiagramError::InvalidArgument(format!("invalid schema snapshot: {}", err)))
+}
+
+fn qualified(table: &Table) -> String {
+    format!("{}.{}", table.schema, table.name)
+}
+
+fn describe(reference: &Reference) -> String {
+    format!(
+        "{} ({}.{}.{} -> {}.{}.{})",
+        reference.constraint_name,
+        reference.schema,
+        reference.table,
+        reference.column,
+        reference.referenced_schema,
+        reference.referenced_table,
+        reference.referenced_column
+    )
+}
+
+fn find_table<'a>(schema: &'a DatabaseSchema, table: &Table) -> Option<&'a Table> {
+    schema
+        .tables
+        .iter()
+        .find(|other| !other.external && other.schema == table.schema && other.name == table.name)
+}
+
+fn diff_table(old: &Table, new: &Table) -> Option<TableDiff> {
+    let find = |table: &Table, name: &str| table.columns.iter().position(|column| column.name == name);
+    let diff = TableDiff {
+        table: qualified(new),
+        added_columns: new
+            .columns
+            .iter()
+            .filter(|column| find(old, &column.name).is_none())
+            .map(|column| column.name.clone())
+            .collect(),
+        removed_columns: old
+            .columns
+            .iter()
+            .filter(|column| find(new, &column.name).is_none())
+            .map(|column| column.name.clone())
+            .collect(),
+        changed_columns: new
+            .columns
+            .iter()
+            .filter_map(|column| {
+                let previous = &old.columns[find(old, &column.name)?];
+                (previous.data_type != column.data_type).then(|| ColumnChange {
+                    name: column.name.clone(),
+                    old_type: previous.data_type.to_string(),
+                    new_type: column.data_type.to_string(),
+                })
+            })
+            .collect(),
+    };
+    let unchanged = diff.added_columns.is_empty() && diff.removed_columns.is_empty() && diff.changed_columns.is_empty();
+    (!unchanged).then_some(diff)
+}
+
+/// Compares `old` with `new`. External stubs are left out, since they carry
+/// no columns of their own.
+pub fn diff_schemas(old: &DatabaseSchema, new: &DatabaseSchema) -> SchemaDiff {
+    let old_tables: Vec<&Table> = old.tables.iter().filter(|table| !table.external).collect();
+    let new_tables: Vec<&Table> = new.tables.iter().filter(|table| !table.external).collect();
+    let old_references: Vec<String> = old.references.iter().map(describe).collect();
+    let new_references: Vec<String> = new.references.iter().map(describe).collect();
+
+    SchemaDiff {
+        added_tables: new_tables
+            .iter()
+            .filter(|table| find_table(old, table).is_none())
+            .map(|table| qualified(table))
+            .collect(),
+        removed_tables: old_tables
+            .iter()
+            .filter(|table| find_table(new, table).is_none())
+            .map(|table| qualified(table))
+            .collect(),
+        changed_tables: new_tables
+            .iter()
+            .filter_map(|table| diff_table(find_table(old, table)?, table))
+            .collect(),
+        added_references: new_references
+            .iter()
+            .filter(|reference| !old_references.contains(reference))
+            .cloned()
+            .collect(),
+        removed_references: old_references
+            .iter()
+            .filter(|reference| !new_references.contains(reference))
+            .cloned()
+            .collect(),
+    }
+}
+
+/// Whether `table` in `new` is new or differs from its `old` counterpart in
+/// its columns or the foreign keys it declares.
+fn is_changed(old: &DatabaseSchema, new: &DatabaseSchema, table: &Table) -> bool {
+    let Some(previous) = find_table(old, table) else {
+        return true;
+    };
+    let declared = |schema: &DatabaseSchema| {
+        let mut references: Vec<String> = schema
+            .references
+            .iter()
+            .filter(|reference| reference.schema == table.schema && reference.table == table.name)
+            .map(describe)
+            .collect();
+        references.sort_unstable();
+        references
+    };
+    diff_table(previous, table).is_some() || declared(old) != declared(new)
+}
+
+/// Narrows `new` to the tables added or changed since `old`, for
+/// `--changed-only`. Foreign keys from a kept table into an unchanged one stay
+/// visible through an external stub; the rest of the unchanged tables are
+/// dropped along with their references.
+pub fn changed_only(old: &DatabaseSchema, mut new: DatabaseSchema) -> DatabaseSchema {
+    let keep: Vec<bool> = new
+        .tables
+        .iter()
+        .map(|table| !table.external && is_changed(old, &new, table))
+        .collect();
+    let mut keep = keep.into_iter();
+    new.tables.retain(|_| keep.next().unwrap_or(false));
+
+    let kept = |tables: &[Table], schema: &str, name: &str| {
+        tables.iter().any(|table| table.schema == schema && table.name == name)
+    };
+    new.references.retain(|reference| kept(&new.tables, &reference.schema, &reference.table));
+    for reference in &new.references {
+        if !kept(&new.tables, &reference.referenced_schema, &reference.referenced_table) {
+            new.tables.push(Table::external_stub(&reference.referenced_schema, &reference.referenced_table));
+        }
+    }
+    new
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == SchemaDiff::default()
+    }
+
+    pub fn render(&self, output: DiffOutput) -> String {
+        match output {
+            DiffOutput::Text => self.to_text(),
+            DiffOutput::Json => {
+                let mut json = serde_json::to_string_pretty(self).expect("the schema diff serializes to JSON");
+                json.push('\n');
+                json
+            }
+            DiffOutput::Markdown => self.to_markdown(),
+        }
+    }
+
+    /// One line per change, prefixed `+`, `-` or `~` like a patch.
+    fn to_text(&self) -> String {
+        if self.is_empty() {
+            return "No schema changes\n".to_string();
+        }
+        let mut text = String::new();
+        for table in &self.added_tables {
+            text.push_str(&format!("+ table {}\n", table));
+        }
+        for table in &self.removed_tables {
+            text.push_str(&format!("- table {}\n", table));
+        }
+        for table in &self.changed_tables {
+            text.push_str(&format!("~ table {}\n", table.table));
+            for column in &table.added_columns {
+                text.push_str(&format!("  + column {}\n", column));
+            }
+            for column in &table.removed_columns {
+                text.push_str(&format!("  - column {}\n", column));
+            }
+            for change in &table.changed_columns {
+                text.push_str(&format!("  ~ column {}: {} -> {}\n", change.name, change.old_type, change.new_type));
+            }
+        }
+        for reference in &self.added_references {
+            text.push_str(&format!("+ reference {}\n", reference));
+        }
+        for reference in &self.removed_references {
+            text.push_str(&format!("- reference {}\n", reference));
+        }
+        text
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut markdown = String::from("# Schema changes\n\n");
+        if self.is_empty() {
+            markdown.push_str("No schema changes.\n");
+            return markdown;
+        }
+        markdown_list(&mut markdown, "Added tables", &self.added_tables);
+        markdown_list(&mut markdown, "Removed tables", &self.removed_tables);
+        if !self.changed_tables.is_empty() {
+            markdown.push_str("## Changed tables\n\n");
+            for table in &self.changed_tables {
+                markdown.push_str(&format!("### `{}`\n\n", table.table));
+                for column in &table.added_columns {
+                    markdown.push_str(&format!("- Added column `{}`\n", column));
+                }
+                for column in &table.removed_columns {
+                    markdown.push_str(&format!("- Removed column `{}`\n", column));
+                }
+                for change in &table.changed_columns {
+                    markdown.push_str(&format!(
+                        "- Changed column `{}` from `{}` to `{}`\n",
+                        change.name, change.old_type, change.new_type
+                    ));
+                }
+                markdown.push('\n');
+            }
+        }
+        markdown_list(&mut markdown, "Added references", &self.added_references);
+        markdown_list(&mut markdown, "Removed references", &self.removed_references);
+        markdown
+    }
+}
+
+fn markdown_list(markdown: &mut String, heading: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    markdown.push_str(&format!("## {}\n\n", heading));
+    for item in items {
+        markdown.push_str(&format!("- `{}`\n", item));
+    }
+    markdown.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{column, reference, reference_lines, schema, table, table_names};
+
+    fn before() -> DatabaseSchema {
+        schema(
+            vec![
+                table("dbo.Customer", &["Id", "Name:nvarchar(50)"], &["Id"]),
+                table("dbo.Order", &["Id", "CustomerId", "Notes:text"], &["Id"]),
+                table("dbo.Legacy", &["Id"], &["Id"]),
+            ],
+            vec![reference("FK_Order_Customer", "dbo.Order.CustomerId", "dbo.Customer.Id")],
+        )
+    }
+
+    fn after() -> DatabaseSchema {
+        let mut after = before();
+        after.tables.remove(2);
+        after.tables[1].columns.pop();
+        after.tables[1].columns.push(column("ShipToId"));
+        after.tables[0].columns[1] = column("Name:nvarchar(100)");
+        after.tables.push(table("dbo.Address", &["Id"], &["Id"]));
+        after.references.push(reference("FK_Order_ShipTo", "dbo.Order.ShipToId", "dbo.Address.Id"));
+        after
+    }
+
+    #[test]
+    fn diff_finds_each_kind_of_change() {
+        let diff = diff_schemas(&before(), &after());
+        assert_eq!(diff.added_tables, ["dbo.Address"]);
+        assert_eq!(diff.removed_tables, ["dbo.Legacy"]);
+        assert_eq!(
+            diff.changed_tables,
+            [
+                TableDiff {
+                    table: "dbo.Customer".to_string(),
+                    added_columns: Vec::new(),
+                    removed_columns: Vec::new(),
+                    changed_columns: vec![ColumnChange {
+                        name: "Name".to_string(),
+                        old_type: "nvarchar(50)".to_string(),
+                        new_type: "nvarchar(100)".to_string(),
+                    }],
+                },
+                TableDiff {
+                    table: "dbo.Order".to_string(),
+                    added_columns: vec!["ShipToId".to_string()],
+                    removed_columns: vec!["Notes".to_string()],
+                    changed_columns: Vec::new(),
+                },
+            ]
+        );
+        assert_eq!(diff.added_references, ["FK_Order_ShipTo (dbo.Order.ShipToId -> dbo.Address.Id)"]);
+        assert!(diff.removed_references.is_empty());
+        assert!(diff_schemas(&before(), &before()).is_empty());
+    }
+
+    #[test]
+    fn every_output_reports_the_same_changes() {
+        let diff = diff_schemas(&before(), &after());
+        let items = [
+            "dbo.Address",
+            "dbo.Legacy",
+            "dbo.Customer",
+            "nvarchar(50)",
+            "nvarchar(100)",
+            "ShipToId",
+            "Notes",
+            "FK_Order_ShipTo (dbo.Order.ShipToId -> dbo.Address.Id)",
+        ];
+        for output in [DiffOutput::Text, DiffOutput::Json, DiffOutput::Markdown] {
+            let report = diff.render(output);
+            for item in items {
+                assert!(report.contains(item), "{:?} report lacks {}:\n{}", output, item, report);
+            }
+        }
+        let json: serde_json::Value = serde_json::from_str(&diff.render(DiffOutput::Json)).unwrap();
+        assert_eq!(json["changed_tables"][1]["removed_columns"][0], "Notes");
+        assert_eq!(
+            diff.render(DiffOutput::Text).lines().take(4).collect::<Vec<_>>(),
+            [
+                "+ table dbo.Address",
+                "- table dbo.Legacy",
+                "~ table dbo.Customer",
+                "  ~ column Name: nvarchar(50) -> nvarchar(100)",
+            ]
+        );
+    }
+
+    #[test]
+    fn changed_only_keeps_the_changed_table_and_stubs_its_targets() {
+        let mut new = before();
+        new.tables[1].columns.push(column("Placed:datetime2"));
+        let changed = changed_only(&before(), new);
+
+        assert_eq!(table_names(&changed), ["dbo.Order", "dbo.Customer"]);
+        assert!(!changed.tables[0].external && changed.tables[1].external);
+        assert_eq!(reference_lines(&changed), ["FK_Order_Customer: dbo.Order.CustomerId -> dbo.Customer.Id"]);
+    }
+
+    #[test]
+    fn changed_only_counts_a_new_foreign_key_as_a_change() {
+        let mut new = before();
+        new.references.push(reference("FK_Legacy_Customer", "dbo.Legacy.Id", "dbo.Customer.Id"));
+        assert_eq!(table_names(&changed_only(&before(), new)), ["dbo.Legacy", "dbo.Customer"]);
+        assert!(changed_only(&before(), before()).tables.is_empty());
+    }
+
+    #[test]
+    fn empty_diff_says_so_in_each_output() {
+        let diff = SchemaDiff::default();
+        assert_eq!(diff.render(DiffOutput::Text), "No schema changes\n");
+        assert_eq!(diff.render(DiffOutput::Markdown), "# Schema changes\n\nNo schema changes.\n");
+        assert!(diff.render(DiffOutput::Json).contains("\"added_tables\": []"));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::connection::connect;
+use crate::error::DiagramError;
+use crate::extract::with_timeout;
+use sqlx::mssql::MssqlPool;
+use std::time::Duration;
+
+/// The outcome of one `--doctor` check: its name and, when it failed, why.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub check: String,
+    pub error: Option<String>,
+}
+
+impl Diagnostic {
+    fn new<T>(check: &str, result: Result<T, DiagramError>) -> Diagnostic {
+        Diagnostic {
+            check: check.to_string(),
+            error: result.err().map(|err| err.to_string()),
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// The report line, such as `✓ SELECT 1` or `✗ sys.foreign_keys: ...`.
+    pub fn line(&self) -> String {
+        match &self.error {
+            None => format!("✓ {}", self.check),
+            Some(error) => format!("✗ {}: {}", self.check, error),
+        }
+    }
+}
+
+/// Runs the `SELECT 1` extraction starts with.
+async fn select_one(pool: &MssqlPool, timeout: Option<Duration>) -> Result<(), DiagramError> {
+    with_timeout(timeout, "health check", async {
+        sqlx::query("SELECT 1")
+            .fetch_one(pool)
+            .await
+            .map_err(DiagramError::HealthCheckFailed)
+    })
+    .await?;
+    Ok(())
+}
+
+/// Runs a query against a metadata view, failing with the permission
+/// guidance `from_metadata_query` gives when the login can't read it.
+async fn read_view(pool: &MssqlPool, timeout: Option<Duration>, view: &str) -> Result<(), DiagramError> {
+    let query = format!("SELECT TOP 1 1 AS READABLE FROM {}", view);
+    with_timeout(timeout, view, async {
+        sqlx::query(&query)
+            .fetch_optional(pool)
+            .await
+            .map_err(|err| DiagramError::from_metadata_query(view, err))
+    })
+    .await?;
+    Ok(())
+}
+
+/// Checks, in order, that the server accepts the connection, runs a query
+/// and lets the login read the metadata views extraction depends on. When
+/// the connection fails, the other checks are reported as skipped.
+pub async fn diagnose(connection_string: &str, timeout: Option<Duration>) -> Vec<Diagnostic> {
+    let pool = match connect(connection_string, false).await {
+        Ok(pool) => pool,
+        Err(err) => {
+            let mut diagnostics = vec![Diagnostic::new::<()>("connection", Err(err))];
+            for check in ["SELECT 1", "INFORMATION_SCHEMA", "sys.foreign_keys"] {
+                diagnostics.push(Diagnostic {
+                    check: check.to_string(),
+                    error: Some("skipped, since the connection failed".to_string()),
+                });
+            }
+            return diagnostics;
+        }
+    };
+    vec![
+        Diagnostic::new("connection", Ok(())),
+        Diagnostic::new("SELECT 1", select_one(&pool, timeout).await),
+        Diagnostic::new("INFORMATION_SCHEMA", read_view(&pool, timeout, "INFORMATION_SCHEMA.COLUMNS").await),
+        Diagnostic::new("sys.foreign_keys", read_view(&pool, timeout, "sys.foreign_keys").await),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_mark_passed_and_failed_checks() {
+        let passed = Diagnostic::new("SELECT 1", Ok(()));
+        assert!(passed.passed());
+        assert_eq!(passed.line(), "✓ SELECT 1");
+
+        let denied = DiagramError::MetadataPermissionDenied(
+            "sys.foreign_keys".to_string(),
+            "The SELECT permission was denied".to_string(),
+        );
+        let failed = Diagnostic::new::<()>("sys.foreign_keys", Err(denied));
+        assert!(!failed.passed());
+        let line = failed.line();
+        let expected = "✗ sys.foreign_keys: permission denied reading sys.foreign_keys: The SELECT permission was denied; ";
+        assert!(line.starts_with(expected), "{}", line);
+        assert!(!line.contains('\n'), "{}", line);
+    }
+}
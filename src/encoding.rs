@@ -0,0 +1,100 @@
+use crate::error::DiagramError;
+use encoding_rs::WINDOWS_1252;
+
+/// The byte order mark written at the start of UTF-16LE files.
+const UTF16LE_BOM: [u8; 2] = [0xFF, 0xFE];
+
+/// Character encoding of the generated files, for `--output-encoding`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputEncoding {
+    /// UTF-8 without a byte order mark.
+    #[default]
+    Utf8,
+    /// UTF-16 little-endian with a byte order mark.
+    Utf16Le,
+    Windows1252,
+}
+
+impl OutputEncoding {
+    pub fn parse(value: &str) -> Option<OutputEncoding> {
+        match value {
+            "utf8" => Some(OutputEncoding::Utf8),
+            "utf16le" => Some(OutputEncoding::Utf16Le),
+            "windows1252" => Some(OutputEncoding::Windows1252),
+            _ => None,
+        }
+    }
+
+    /// Transcodes `text`, failing on the first character the encoding can't
+    /// represent rather than substituting it.
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, DiagramError> {
+        match self {
+            OutputEncoding::Utf8 => Ok(text.as_bytes().to_vec()),
+            OutputEncoding::Utf16Le => {
+                let mut bytes = UTF16LE_BOM.to_vec();
+                bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+                Ok(bytes)
+            }
+            OutputEncoding::Windows1252 => {
+                let (bytes, _, had_errors) = WINDOWS_1252.encode(text);
+                if !had_errors {
+                    return Ok(bytes.into_owned());
+                }
+                let unmappable = text
+                    .chars()
+                    .find(|c| WINDOWS_1252.encode(c.encode_utf8(&mut [0; 4])).2)
+                    .unwrap_or(char::REPLACEMENT_CHARACTER);
+                Err(DiagramError::InvalidArgument(format!(
+                    "'{}' (U+{:04X}) can't be written in windows-1252; use --output-encoding utf8 or utf16le",
+                    unmappable, unmappable as u32
+                )))
+            }
+        }
+    }
+
+    /// Reads back text written with `encode`, replacing bytes that aren't
+    /// valid in the encoding, for showing how an existing file differs.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            OutputEncoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            OutputEncoding::Utf16Le => {
+                let bytes = bytes.strip_prefix(&UTF16LE_BOM).unwrap_or(bytes);
+                let units: Vec<u16> =
+                    bytes.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+                String::from_utf16_lossy(&units)
+            }
+            OutputEncoding::Windows1252 => WINDOWS_1252.decode_without_bom_handling(bytes).0.into_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_ascii_names_round_trip_through_utf16le() {
+        let text = "class \"Größe_Été\" as dbo_Größe_Été\n";
+        let bytes = OutputEncoding::Utf16Le.encode(text).unwrap();
+        assert_eq!(&bytes[..4], [0xFF, 0xFE, b'c', 0]);
+        assert_eq!(bytes.len(), 2 + 2 * text.encode_utf16().count());
+        assert_eq!(OutputEncoding::Utf16Le.decode(&bytes), text);
+    }
+
+    #[test]
+    fn windows1252_round_trips_what_it_can_represent() {
+        let bytes = OutputEncoding::Windows1252.encode("Größe €").unwrap();
+        assert_eq!(bytes, [b'G', b'r', 0xF6, 0xDF, b'e', b' ', 0x80]);
+        assert_eq!(OutputEncoding::Windows1252.decode(&bytes), "Größe €");
+        match OutputEncoding::Windows1252.encode("日本") {
+            Err(DiagramError::InvalidArgument(message)) => assert!(message.starts_with("'日' (U+65E5)"), "{}", message),
+            other => panic!("expected an unmappable character, got {:?}", other.map_err(|err| err.to_string())),
+        }
+    }
+
+    #[test]
+    fn utf8_is_written_as_is() {
+        assert_eq!(OutputEncoding::Utf8.encode("Größe").unwrap(), "Größe".as_bytes());
+        assert_eq!(OutputEncoding::parse("utf-16"), None);
+    }
+}
@@ -0,0 +1,352 @@
+use crate::connection::redact_connection_string;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum DiagramError {
+    Connection(sqlx::Error),
+    Database(sqlx::Error),
+    HealthCheckFailed(sqlx::Error),
+    QueryTimeout(String),
+    /// The login may not read a metadata view: `(view, server message)`.
+    MetadataPermissionDenied(String, String),
+    InvalidArgument(String),
+    EmptySchema,
+    Io(std::io::Error),
+    Cancelled,
+    /// `--check` found generated files that differ from the ones on disk.
+    OutOfDate(Vec<String>),
+    /// `--fail-on-warnings` found this many structural warnings.
+    WarningsFound(usize),
+    /// `--batch` failed to diagram `(failed, total)` of its databases.
+    BatchFailed(usize, usize),
+    /// `--doctor` found `(failed, total)` of its checks failing.
+    DoctorFailed(usize, usize),
+    /// `--validate` found this many inconsistencies in the schema.
+    InvalidSchema(usize),
+}
+
+impl DiagramError {
+    /// The process exit code reported for this error:
+    /// 2 connection/auth failure, 3 query/permission failure, 4 bad arguments,
+    /// 5 empty schema, 6 IO/write failure, 7 out-of-date output, 8 warnings
+    /// under `--fail-on-warnings`, 9 failed `--batch` entries, 10 failed
+    /// `--doctor` checks, 11 an inconsistent schema under `--validate` and
+    /// 130 cancellation.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            DiagramError::Connection(_) => 2,
+            DiagramError::Database(_)
+            | DiagramError::HealthCheckFailed(_)
+            | DiagramError::QueryTimeout(_)
+            | DiagramError::MetadataPermissionDenied(_, _) => 3,
+            DiagramError::InvalidArgument(_) => 4,
+            DiagramError::EmptySchema => 5,
+            DiagramError::Io(_) => 6,
+            DiagramError::OutOfDate(_) => 7,
+            DiagramError::WarningsFound(_) => 8,
+            DiagramError::BatchFailed(_, _) => 9,
+            DiagramError::DoctorFailed(_, _) => 10,
+            DiagramError::InvalidSchema(_) => 11,
+            DiagramError::Cancelled => 130,
+        }
+    }
+}
+
+/// How a failure is reported on stderr.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// An `error: ...` line.
+    #[default]
+    Human,
+    /// A JSON object with the error's kind, message and, when it concerns
+    /// one, table, for tools embedding this one.
+    Json,
+}
+
+impl ErrorFormat {
+    pub fn parse(format: &str) -> Option<ErrorFormat> {
+        match format {
+            "human" => Some(ErrorFormat::Human),
+            "json" => Some(ErrorFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// The report of `err` in this format.
+    pub fn report(&self, err: &DiagramError) -> String {
+        match self {
+            ErrorFormat::Human => format!("error: {}", err),
+            ErrorFormat::Json => err.to_json(),
+        }
+    }
+}
+
+impl DiagramError {
+    /// The variant as a stable snake_case name, such as `query_timeout`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DiagramError::Connection(_) => "connection",
+            DiagramError::Database(_) => "database",
+            DiagramError::HealthCheckFailed(_) => "health_check_failed",
+            DiagramError::QueryTimeout(_) => "query_timeout",
+            DiagramError::MetadataPermissionDenied(_, _) => "metadata_permission_denied",
+            DiagramError::InvalidArgument(_) => "invalid_argument",
+            DiagramError::EmptySchema => "empty_schema",
+            DiagramError::Io(_) => "io",
+            DiagramError::Cancelled => "cancelled",
+            DiagramError::OutOfDate(_) => "out_of_date",
+            DiagramError::WarningsFound(_) => "warnings_found",
+            DiagramError::BatchFailed(_, _) => "batch_failed",
+            DiagramError::DoctorFailed(_, _) => "doctor_failed",
+            DiagramError::InvalidSchema(_) => "invalid_schema",
+        }
+    }
+
+    /// The table or metadata view the error is about, when there is one.
+    pub fn table(&self) -> Option<&str> {
+        match self {
+            DiagramError::QueryTimeout(table) | DiagramError::MetadataPermissionDenied(table, _) => Some(table),
+            _ => None,
+        }
+    }
+
+    /// The error as a one-line JSON object: `{"kind": ..., "message": ...,
+    /// "table": ..., "exit_code": ...}`, with `table` null when the error
+    /// isn't about one and `message` the human-readable text.
+    pub fn to_json(&self) -> String {
+        serde_json::json!({
+            "kind": self.kind(),
+            "message": self.to_string(),
+            "table": self.table(),
+            "exit_code": self.exit_code(),
+        })
+        .to_string()
+    }
+}
+
+/// The SQL Server error raised when a snapshot transaction reads a database
+/// that doesn't allow snapshot isolation.
+const SNAPSHOT_NOT_ALLOWED: i32 = 3952;
+
+/// SQL Server error numbers raised when a permission is denied.
+const PERMISSION_DENIED_NUMBERS: &[i32] = &[229, 230, 262, 300];
+
+/// The SQL Server error number of a database error. sqlx doesn't expose it
+/// through its API, so it is read back from the error's debug output.
+fn sql_error_number(err: &sqlx::Error) -> Option<i32> {
+    let err = err.as_database_error()?.try_downcast_ref::<sqlx::mssql::MssqlDatabaseError>()?;
+    // The number follows the quoted message, whose own quotes are escaped
+    let debug = format!("{:?}", err);
+    let marker = "\", number: ";
+    let start = debug
+        .match_indices(marker)
+        .find(|(i, _)| !debug[..*i].ends_with('\\'))
+        .map(|(i, _)| i + marker.len())?;
+    debug[start..].split(|c: char| !c.is_ascii_digit()).next()?.parse().ok()
+}
+
+impl DiagramError {
+    /// Wraps an error from a query against the metadata `view`, singling out
+    /// permission failures so they can carry guidance.
+    pub fn from_metadata_query(view: &str, err: sqlx::Error) -> DiagramError {
+        let number = sql_error_number(&err);
+        DiagramError::from_metadata_error_number(view, number, err)
+    }
+
+    fn from_metadata_error_number(view: &str, number: Option<i32>, err: sqlx::Error) -> DiagramError {
+        match number {
+            Some(number) if PERMISSION_DENIED_NUMBERS.contains(&number) => {
+                DiagramError::MetadataPermissionDenied(view.to_string(), err.to_string())
+            }
+            _ => DiagramError::Database(err),
+        }
+    }
+
+    /// Explains a failure inside the `--consistent-snapshot` transaction when
+    /// the database doesn't allow snapshot isolation.
+    pub fn in_snapshot(self) -> DiagramError {
+        match self {
+            DiagramError::Database(err) if sql_error_number(&err) == Some(SNAPSHOT_NOT_ALLOWED) => {
+                DiagramError::InvalidArgument(format!(
+                    "--consistent-snapshot needs snapshot isolation, which this database doesn't allow ({}); \
+                     enable it with ALTER DATABASE <name> SET ALLOW_SNAPSHOT_ISOLATION ON, or run without \
+                     --consistent-snapshot",
+                    redact_connection_string(&err.to_string())
+                ))
+            }
+            err => err,
+        }
+    }
+}
+
+impl fmt::Display for DiagramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagramError::Connection(err) => write!(
+                f,
+                "could not connect to the SQL server: {}",
+                redact_connection_string(&err.to_string())
+            ),
+            DiagramError::Database(err) => write!(f, "database error: {}", redact_connection_string(&err.to_string())),
+            DiagramError::HealthCheckFailed(err) => write!(
+                f,
+                "connected, but the health check query (SELECT 1) failed: {}; check that the session can run queries",
+                redact_connection_string(&err.to_string())
+            ),
+            DiagramError::QueryTimeout(table) => write!(f, "metadata query for {} timed out", table),
+            DiagramError::MetadataPermissionDenied(view, message) => write!(
+                f,
+                "permission denied reading {}: {}; grant the login VIEW DEFINITION on the database \
+                 (GRANT VIEW DEFINITION TO <user>), or, if only INFORMATION_SCHEMA is blocked, read the \
+                 equivalent sys.* catalog views (sys.tables, sys.columns) instead",
+                view,
+                message
+            ),
+            DiagramError::InvalidArgument(message) => write!(f, "invalid argument: {}", message),
+            DiagramError::EmptySchema => write!(f, "no tables were found to diagram"),
+            DiagramError::Io(err) => write!(f, "io error: {}", err),
+            DiagramError::Cancelled => write!(f, "extraction was cancelled"),
+            DiagramError::OutOfDate(files) => write!(f, "generated output differs from {}", files.join(", ")),
+            DiagramError::WarningsFound(count) => write!(f, "{} warning(s) found with --fail-on-warnings", count),
+            DiagramError::BatchFailed(failed, total) => write!(f, "{} of {} batch databases failed", failed, total),
+            DiagramError::DoctorFailed(failed, total) => write!(f, "{} of {} doctor checks failed", failed, total),
+            DiagramError::InvalidSchema(count) => write!(f, "{} schema inconsistencies found with --validate", count),
+        }
+    }
+}
+
+impl std::error::Error for DiagramError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DiagramError::Connection(err) | DiagramError::Database(err) => Some(err),
+            DiagramError::HealthCheckFailed(err) => Some(err),
+            DiagramError::Io(err) => Some(err),
+            DiagramError::QueryTimeout(_)
+            | DiagramError::MetadataPermissionDenied(_, _)
+            | DiagramError::InvalidArgument(_)
+            | DiagramError::EmptySchema
+            | DiagramError::Cancelled
+            | DiagramError::OutOfDate(_)
+            | DiagramError::WarningsFound(_)
+            | DiagramError::BatchFailed(_, _)
+            | DiagramError::DoctorFailed(_, _)
+            | DiagramError::InvalidSchema(_) => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for DiagramError {
+    fn from(err: sqlx::Error) -> Self {
+        DiagramError::Database(err)
+    }
+}
+
+impl From<std::io::Error> for DiagramError {
+    fn from(err: std::io::Error) -> Self {
+        DiagramError::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_error_exits_with_its_documented_code() {
+        let io = || std::io::Error::new(std::io::ErrorKind::PermissionDenied, "read-only directory");
+        let codes: Vec<(u8, &str)> = [
+            DiagramError::Connection(sqlx::Error::PoolTimedOut),
+            DiagramError::Database(sqlx::Error::RowNotFound),
+            DiagramError::HealthCheckFailed(sqlx::Error::PoolClosed),
+            DiagramError::QueryTimeout("dbo.Orders".to_string()),
+            DiagramError::MetadataPermissionDenied("sys.tables".to_string(), "denied".to_string()),
+            DiagramError::InvalidArgument("--depth".to_string()),
+            DiagramError::EmptySchema,
+            DiagramError::Io(io()),
+            DiagramError::OutOfDate(vec!["schema.puml".to_string()]),
+            DiagramError::WarningsFound(2),
+            DiagramError::BatchFailed(1, 3),
+            DiagramError::DoctorFailed(1, 4),
+            DiagramError::InvalidSchema(5),
+            DiagramError::Cancelled,
+        ]
+        .iter()
+        .map(|err| (err.exit_code(), err.kind()))
+        .collect();
+        assert_eq!(
+            codes,
+            [
+                (2, "connection"),
+                (3, "database"),
+                (3, "health_check_failed"),
+                (3, "query_timeout"),
+                (3, "metadata_permission_denied"),
+                (4, "invalid_argument"),
+                (5, "empty_schema"),
+                (6, "io"),
+                (7, "out_of_date"),
+                (8, "warnings_found"),
+                (9, "batch_failed"),
+                (10, "doctor_failed"),
+                (11, "invalid_schema"),
+                (130, "cancelled"),
+            ]
+        );
+    }
+
+    #[test]
+    fn json_reports_carry_kind_message_table_and_exit_code() {
+        let report: serde_json::Value =
+            serde_json::from_str(&DiagramError::QueryTimeout("dbo.Orders".to_string()).to_json()).unwrap();
+        assert_eq!(
+            report,
+            serde_json::json!({
+                "kind": "query_timeout",
+                "message": "metadata query for dbo.Orders timed out",
+                "table": "dbo.Orders",
+                "exit_code": 3,
+            })
+        );
+
+        let report = ErrorFormat::Json.report(&DiagramError::EmptySchema);
+        assert!(!report.contains('\n'), "{}", report);
+        let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(
+            report,
+            serde_json::json!({
+                "kind": "empty_schema",
+                "message": "no tables were found to diagram",
+                "table": null,
+                "exit_code": 5,
+            })
+        );
+        assert_eq!(ErrorFormat::Human.report(&DiagramError::EmptySchema), "error: no tables were found to diagram");
+    }
+
+    #[test]
+    fn permission_errors_on_metadata_views_carry_guidance() {
+        for number in [229, 230, 262, 300] {
+            let err = sqlx::Error::Protocol("The SELECT permission was denied".to_string());
+            match DiagramError::from_metadata_error_number("sys.foreign_keys", Some(number), err) {
+                err @ DiagramError::MetadataPermissionDenied(_, _) => {
+                    let message = err.to_string();
+                    assert!(message.starts_with("permission denied reading sys.foreign_keys: "), "{}", message);
+                    assert!(message.contains("The SELECT permission was denied"), "{}", message);
+                    assert!(message.contains("GRANT VIEW DEFINITION TO <user>"), "{}", message);
+                }
+                other => panic!("error {} gave {}", number, other),
+            }
+        }
+    }
+
+    #[test]
+    fn other_metadata_errors_stay_database_errors() {
+        for number in [Some(208), None] {
+            let err = sqlx::Error::Protocol("Invalid object name".to_string());
+            match DiagramError::from_metadata_error_number("sys.foreign_keys", number, err) {
+                DiagramError::Database(_) => {}
+                other => panic!("error {:?} gave {}", number, other),
+            }
+        }
+    }
+}
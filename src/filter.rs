@@ -0,0 +1,709 @@
+use crate::analysis::connected_components;
+use crate::model::{DatabaseSchema, Table};
+
+/// A table pattern such as `Orders`, `Order*` or `sales.Order*`.
+///
+/// Patterns without a schema part match tables in any schema; qualified
+/// patterns must match both the schema and the table name.
+#[derive(Clone)]
+pub struct NamePattern {
+    schema: Option<String>,
+    name: String,
+}
+
+impl NamePattern {
+    pub fn parse(pattern: &str) -> NamePattern {
+        match pattern.split_once('.') {
+            Some((schema, name)) => NamePattern {
+                schema: Some(unquote(schema).to_string()),
+                name: unquote(name).to_string(),
+            },
+            None => NamePattern {
+                schema: None,
+                name: unquote(pattern).to_string(),
+            },
+        }
+    }
+
+    pub fn matches(&self, schema: &str, name: &str, case_sensitive: bool) -> bool {
+        let schema_matches = match &self.schema {
+            Some(pattern) => glob_match(pattern, schema, case_sensitive),
+            None => true,
+        };
+        schema_matches && glob_match(&self.name, name, case_sensitive)
+    }
+}
+
+fn unquote(part: &str) -> &str {
+    let part = part.trim();
+    part.strip_prefix('[')
+        .and_then(|part| part.strip_suffix(']'))
+        .unwrap_or(part)
+}
+
+/// Parses a comma-separated list of patterns, ignoring empty entries.
+pub fn parse_patterns(patterns: &str) -> Vec<NamePattern> {
+    patterns
+        .split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(NamePattern::parse)
+        .collect()
+}
+
+/// Matches `text` against a glob supporting `*` and `?`.
+pub fn glob_match(pattern: &str, text: &str, case_sensitive: bool) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) = if case_sensitive {
+        (pattern.chars().collect(), text.chars().collect())
+    } else {
+        (pattern.to_lowercase().chars().collect(), text.to_lowercase().chars().collect())
+    };
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+pub struct TableFilter {
+    include: Vec<NamePattern>,
+    exclude: Vec<NamePattern>,
+    case_sensitive: bool,
+    external_stubs: bool,
+}
+
+impl TableFilter {
+    /// With `external_stubs`, tables that are filtered out but referenced by a
+    /// kept table are replaced by name-only stubs instead of being dropped.
+    pub fn new(include: Option<&str>, exclude: Option<&str>, case_sensitive: bool, external_stubs: bool) -> TableFilter {
+        TableFilter {
+            include: include.map(parse_patterns).unwrap_or_default(),
+            exclude: exclude.map(parse_patterns).unwrap_or_default(),
+            case_sensitive,
+            external_stubs,
+        }
+    }
+
+    pub fn matches(&self, table: &Table) -> bool {
+        let included = self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| pattern.matches(&table.schema, &table.name, self.case_sensitive));
+        let excluded = self
+            .exclude
+            .iter()
+            .any(|pattern| pattern.matches(&table.schema, &table.name, self.case_sensitive));
+        included && !excluded
+    }
+
+    /// Drops tables that don't match, along with any references to or from
+    /// them, except references from kept tables when stubs are enabled.
+    pub fn apply(&self, mut schema: DatabaseSchema) -> DatabaseSchema {
+        schema.tables.retain(|table| self.matches(table));
+        if self.external_stubs {
+            let mut stubs: Vec<Table> = Vec::new();
+            for reference in &schema.references {
+                let source_kept = schema
+                    .tables
+                    .iter()
+                    .any(|table| table.schema == reference.schema && table.name == reference.table);
+                let target_present = schema.tables.iter().chain(&stubs).any(|table| {
+                    table.schema == reference.referenced_schema && table.name == reference.referenced_table
+                });
+                if source_kept && !target_present {
+                    stubs.push(Table::external_stub(&reference.referenced_schema, &reference.referenced_table));
+                }
+            }
+            schema.tables.extend(stubs);
+        }
+        let tables = &schema.tables;
+        let contains = |schema: &str, name: &str, allow_external: bool| {
+            tables
+                .iter()
+                .any(|table| table.schema == schema && table.name == name && (allow_external || !table.external))
+        };
+        schema.references.retain(|reference| {
+            contains(&reference.schema, &reference.table, false)
+                && contains(&reference.referenced_schema, &reference.referenced_table, true)
+        });
+        schema
+    }
+}
+
+/// Removes tables that neither reference nor are referenced by another table
+/// in the schema. Self-references alone don't keep a table, and are dropped
+/// along with it.
+pub fn drop_isolated(mut schema: DatabaseSchema) -> DatabaseSchema {
+    let connected = |table: &Table| {
+        schema.references.iter().any(|reference| {
+            let from = reference.schema == table.schema && reference.table == table.name;
+            let to = reference.referenced_schema == table.schema && reference.referenced_table == table.name;
+            from != to
+        })
+    };
+    let keep: Vec<bool> = schema.tables.iter().map(connected).collect();
+    let mut keep = keep.into_iter();
+    schema.tables.retain(|_| keep.next().unwrap_or(false));
+    let tables = &schema.tables;
+    let contains = |schema: &str, name: &str| tables.iter().any(|table| table.schema == schema && table.name == name);
+    schema.references.retain(|reference| {
+        contains(&reference.schema, &reference.table)
+            && contains(&reference.referenced_schema, &reference.referenced_table)
+    });
+    schema
+}
+
+/// Parses a `--tables-file` list of schema-qualified table names, one per
+/// line, such as `sales.Orders` or `[sales].[Order Lines]`. Blank lines and
+/// lines starting with `#` are skipped.
+pub fn parse_table_list(text: &str) -> Result<Vec<(String, String)>, String> {
+    let mut tables = Vec::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        match line.split_once('.') {
+            Some((schema, name)) if !unquote(schema).is_empty() && !unquote(name).is_empty() => {
+                tables.push((unquote(schema).to_string(), unquote(name).to_string()));
+            }
+            _ => return Err(format!("'{}' should be a schema-qualified name such as dbo.{}", line, unquote(line))),
+        }
+    }
+    Ok(tables)
+}
+
+/// Keeps only the tables in `list`, reordered to follow it, and the
+/// references between them. Also returns the listed names, as
+/// `schema.name`, that match no table; a table listed twice keeps its
+/// first place.
+pub fn in_listed_order(
+    schema: DatabaseSchema,
+    list: &[(String, String)],
+    case_sensitive: bool,
+) -> (DatabaseSchema, Vec<String>) {
+    // Folds case like `glob_match`, so names beyond ASCII such as `Größe`
+    // match the way they do in --include
+    let same = |a: &str, b: &str| if case_sensitive { a == b } else { a.to_lowercase() == b.to_lowercase() };
+    let mut order: Vec<usize> = Vec::new();
+    let mut missing = Vec::new();
+    for (table_schema, name) in list {
+        match schema.tables.iter().position(|table| same(&table.schema, table_schema) && same(&table.name, name)) {
+            Some(position) if !order.contains(&position) => order.push(position),
+            Some(_) => {}
+            None => missing.push(format!("{}.{}", table_schema, name)),
+        }
+    }
+    let mut ends = reference_ends(&schema).into_iter();
+    let DatabaseSchema { tables, mut references } = schema;
+    references.retain(|_| matches!(ends.next(), Some(Some((from, to))) if order.contains(&from) && order.contains(&to)));
+    let mut tables: Vec<Option<Table>> = tables.into_iter().map(Some).collect();
+    let tables = order.iter().filter_map(|&position| tables[position].take()).collect();
+    (DatabaseSchema { tables, references }, missing)
+}
+
+/// A `--focus` anchor such as `Orders:2`: the tables matching `pattern`,
+/// expanded by `depth` foreign key hops, or by `--depth` when `None`.
+#[derive(Clone)]
+pub struct FocusTable {
+    pub pattern: NamePattern,
+    pub depth: Option<usize>,
+}
+
+/// Parses a `--focus` list such as `Orders:2,Customers`, where each table
+/// pattern may carry its own depth.
+pub fn parse_focus(list: &str) -> Result<Vec<FocusTable>, String> {
+    let mut anchors = Vec::new();
+    for entry in list.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let (pattern, depth) = match entry.rsplit_once(':') {
+            Some((pattern, depth)) => {
+                let depth = depth
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("invalid depth '{}' for '{}'; expected a whole number", depth, pattern))?;
+                (pattern, Some(depth))
+            }
+            None => (entry, None),
+        };
+        anchors.push(FocusTable {
+            pattern: NamePattern::parse(pattern),
+            depth,
+        });
+    }
+    if anchors.is_empty() {
+        return Err("expected at least one table".to_string());
+    }
+    Ok(anchors)
+}
+
+/// Keeps the tables within each anchor's depth of foreign key hops, in
+/// either direction, of the tables it matches, and the references between
+/// the tables kept. The neighbourhoods of all anchors are combined.
+pub fn focus(
+    schema: DatabaseSchema,
+    anchors: &[FocusTable],
+    default_depth: usize,
+    case_sensitive: bool,
+) -> DatabaseSchema {
+    let ends = reference_ends(&schema);
+    let mut neighbours: Vec<Vec<usize>> = vec![Vec::new(); schema.tables.len()];
+    for &(from, to) in ends.iter().flatten() {
+        neighbours[from].push(to);
+        neighbours[to].push(from);
+    }
+
+    let mut keep = vec![false; schema.tables.len()];
+    for anchor in anchors {
+        let start = matching(&schema, std::slice::from_ref(&anchor.pattern), case_sensitive);
+        let reached = spread(start, &neighbours, Some(anchor.depth.unwrap_or(default_depth)));
+        for (kept, reached) in keep.iter_mut().zip(reached) {
+            *kept |= reached;
+        }
+    }
+    keep_tables(schema, ends, keep)
+}
+
+/// Keeps the tables matching `depends_on` and every table they reach by
+/// following foreign keys to the tables they reference, together with the
+/// tables matching `depended_on_by` and every table reaching them through
+/// foreign keys, plus the references between the tables kept.
+pub fn reachable(
+    schema: DatabaseSchema,
+    depends_on: &[NamePattern],
+    depended_on_by: &[NamePattern],
+    case_sensitive: bool,
+) -> DatabaseSchema {
+    let ends = reference_ends(&schema);
+    let mut referenced: Vec<Vec<usize>> = vec![Vec::new(); schema.tables.len()];
+    let mut referencing: Vec<Vec<usize>> = vec![Vec::new(); schema.tables.len()];
+    for &(from, to) in ends.iter().flatten() {
+        referenced[from].push(to);
+        referencing[to].push(from);
+    }
+
+    let dependencies = spread(matching(&schema, depends_on, case_sensitive), &referenced, None);
+    let dependents = spread(matching(&schema, depended_on_by, case_sensitive), &referencing, None);
+    let keep = dependencies.into_iter().zip(dependents).map(|(a, b)| a || b).collect();
+    keep_tables(schema, ends, keep)
+}
+
+/// Which tables `--limit-depth-from-roots` counts foreign key hops from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Roots {
+    /// Tables referencing no other table, such as lookup and master tables,
+    /// expanded to the tables referencing them.
+    #[default]
+    NoOutgoing,
+    /// Tables no other table references, expanded to the tables they
+    /// reference.
+    NoIncoming,
+}
+
+impl Roots {
+    pub fn parse(roots: &str) -> Option<Roots> {
+        match roots {
+            "no-outgoing" => Some(Roots::NoOutgoing),
+            "no-incoming" => Some(Roots::NoIncoming),
+            _ => None,
+        }
+    }
+}
+
+/// Keeps the root tables and those within `depth` foreign key hops of them,
+/// followed away from the roots, plus the references between the tables
+/// kept. Isolated tables are roots too, and self-references don't stop a
+/// table from being one.
+pub fn from_roots(schema: DatabaseSchema, roots: Roots, depth: usize) -> DatabaseSchema {
+    let ends = reference_ends(&schema);
+    let mut links: Vec<Vec<usize>> = vec![Vec::new(); schema.tables.len()];
+    let mut is_root = vec![true; schema.tables.len()];
+    for &(from, to) in ends.iter().flatten().filter(|(from, to)| from != to) {
+        match roots {
+            Roots::NoOutgoing => {
+                is_root[from] = false;
+                links[to].push(from);
+            }
+            Roots::NoIncoming => {
+                is_root[to] = false;
+                links[from].push(to);
+            }
+        }
+    }
+    let keep = spread(is_root, &links, Some(depth));
+    keep_tables(schema, ends, keep)
+}
+
+/// The positions in `schema.tables` of each reference's table and
+/// referenced table, when both are in the schema.
+fn reference_ends(schema: &DatabaseSchema) -> Vec<Option<(usize, usize)>> {
+    let position = |table_schema: &str, table: &str| {
+        schema
+            .tables
+            .iter()
+            .position(|candidate| candidate.schema == table_schema && candidate.name == table)
+    };
+    schema
+        .references
+        .iter()
+        .map(|reference| {
+            position(&reference.schema, &reference.table)
+                .zip(position(&reference.referenced_schema, &reference.referenced_table))
+        })
+        .collect()
+}
+
+/// Flags the tables matching any of `patterns`, by position.
+fn matching(schema: &DatabaseSchema, patterns: &[NamePattern], case_sensitive: bool) -> Vec<bool> {
+    schema
+        .tables
+        .iter()
+        .map(|table| patterns.iter().any(|pattern| pattern.matches(&table.schema, &table.name, case_sensitive)))
+        .collect()
+}
+
+/// Extends the `reached` tables along `links`, at most `hops` steps when
+/// given or until nothing new is reached otherwise.
+fn spread(mut reached: Vec<bool>, links: &[Vec<usize>], hops: Option<usize>) -> Vec<bool> {
+    let mut frontier: Vec<usize> = (0..reached.len()).filter(|&i| reached[i]).collect();
+    let mut hop = 0;
+    while !frontier.is_empty() && hops.is_none_or(|hops| hop < hops) {
+        let mut next = Vec::new();
+        for &table in &frontier {
+            for &linked in &links[table] {
+                if !reached[linked] {
+                    reached[linked] = true;
+                    next.push(linked);
+                }
+            }
+        }
+        frontier = next;
+        hop += 1;
+    }
+    reached
+}
+
+/// Keeps the tables flagged in `keep` and the references, located by
+/// `ends`, between two of them.
+fn keep_tables(schema: DatabaseSchema, ends: Vec<Option<(usize, usize)>>, keep: Vec<bool>) -> DatabaseSchema {
+    let DatabaseSchema { mut tables, mut references } = schema;
+    let mut ends = ends.into_iter();
+    references.retain(|_| matches!(ends.next(), Some(Some((from, to))) if keep[from] && keep[to]));
+    let mut kept = keep.into_iter();
+    tables.retain(|_| kept.next().unwrap_or(false));
+    DatabaseSchema { tables, references }
+}
+
+/// Splits a schema into one sub-schema per database schema, keeping only the
+/// references whose endpoints both fall within the same part.
+pub fn split_by_schema(schema: &DatabaseSchema) -> Vec<(String, DatabaseSchema)> {
+    let mut names: Vec<&str> = schema.tables.iter().map(|table| table.schema.as_str()).collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+        .into_iter()
+        .map(|name| {
+            let part = DatabaseSchema {
+                tables: schema.tables.iter().filter(|table| table.schema == name).cloned().collect(),
+                references: schema
+                    .references
+                    .iter()
+                    .filter(|reference| reference.schema == name && reference.referenced_schema == name)
+                    .cloned()
+                    .collect(),
+            };
+            (name.to_string(), part)
+        })
+        .collect()
+}
+
+/// Splits a schema into one `component_N` sub-schema per cluster of tables
+/// linked by foreign keys, numbered in model order, plus a `misc` part
+/// holding every isolated table. Only references within a part are kept.
+pub fn split_by_component(schema: &DatabaseSchema) -> Vec<(String, DatabaseSchema)> {
+    let (clusters, isolated): (Vec<_>, Vec<_>) = connected_components(schema)
+        .into_iter()
+        .partition(|component| component.len() > 1);
+    let mut parts: Vec<(String, Vec<&Table>)> = clusters
+        .into_iter()
+        .enumerate()
+        .map(|(i, members)| (format!("component_{}", i + 1), members))
+        .collect();
+    if !isolated.is_empty() {
+        parts.push(("misc".to_string(), isolated.into_iter().flatten().collect()));
+    }
+    parts
+        .into_iter()
+        .map(|(name, members)| {
+            let contains = |table_schema: &str, table: &str| {
+                members.iter().any(|member| member.schema == table_schema && member.name == table)
+            };
+            let part = DatabaseSchema {
+                tables: members.iter().map(|&table| table.clone()).collect(),
+                references: schema
+                    .references
+                    .iter()
+                    .filter(|reference| {
+                        contains(&reference.schema, &reference.table)
+                            && contains(&reference.referenced_schema, &reference.referenced_table)
+                    })
+                    .cloned()
+                    .collect(),
+            };
+            (name, part)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{reference, reference_lines, schema, table, table_names as names};
+
+    fn sample() -> DatabaseSchema {
+        schema(
+            vec![
+                table("dbo.Customer", &["Id"], &["Id"]),
+                table("dbo.Order", &["Id", "CustomerId"], &["Id"]),
+                table("dbo.Employee", &["Id", "ManagerId"], &["Id"]),
+                table("dbo.Log", &["Id", "ProductId"], &["Id"]),
+                table("dbo.Product", &["Id"], &["Id"]),
+                table("dbo.Setting", &["Id"], &["Id"]),
+            ],
+            vec![
+                reference("FK_Order_Customer", "dbo.Order.CustomerId", "dbo.Customer.Id"),
+                reference("FK_Employee_Manager", "dbo.Employee.ManagerId", "dbo.Employee.Id"),
+                reference("FK_Log_Product", "dbo.Log.ProductId", "dbo.Product.Id"),
+            ],
+        )
+    }
+
+    #[test]
+    fn drop_isolated_drops_self_references_with_their_table() {
+        let connected = drop_isolated(sample());
+
+        assert_eq!(names(&connected), ["dbo.Customer", "dbo.Order", "dbo.Log", "dbo.Product"]);
+        assert_eq!(
+            reference_lines(&connected),
+            [
+                "FK_Order_Customer: dbo.Order.CustomerId -> dbo.Customer.Id",
+                "FK_Log_Product: dbo.Log.ProductId -> dbo.Product.Id",
+            ]
+        );
+    }
+
+    #[test]
+    fn drop_isolated_after_focus_drops_tables_it_cut_off() {
+        let anchors = parse_focus("dbo.Order:0,dbo.Customer:0,dbo.Log:0").unwrap();
+        let focused = focus(sample(), &anchors, 1, false);
+        assert_eq!(names(&focused), ["dbo.Customer", "dbo.Order", "dbo.Log"]);
+
+        assert_eq!(names(&drop_isolated(focused)), ["dbo.Customer", "dbo.Order"]);
+    }
+
+    #[test]
+    fn include_ignores_case_unless_case_sensitive() {
+        let customers = table("dbo.Customers", &["Id"], &["Id"]);
+        assert!(TableFilter::new(Some("customers"), None, false, false).matches(&customers));
+        assert!(!TableFilter::new(Some("customers"), None, true, false).matches(&customers));
+        assert!(TableFilter::new(Some("Customers"), None, true, false).matches(&customers));
+    }
+
+    #[test]
+    fn qualified_patterns_compare_the_case_of_both_parts() {
+        let customers = table("dbo.Customers", &["Id"], &["Id"]);
+        for pattern in ["DBO.Customers", "dbo.CUSTOMERS"] {
+            assert!(TableFilter::new(Some(pattern), None, false, false).matches(&customers), "{}", pattern);
+            assert!(!TableFilter::new(Some(pattern), None, true, false).matches(&customers), "{}", pattern);
+        }
+        assert!(!TableFilter::new(Some("sales.customers"), None, false, false).matches(&customers));
+    }
+
+    #[test]
+    fn listed_tables_follow_the_file_order() {
+        let file = "# curated\ndbo.Product\n\n[dbo].[Log]\ndbo.Order\ndbo.Customer\ndbo.Missing\n";
+        let list = parse_table_list(file).unwrap();
+        let (listed, missing) = in_listed_order(sample(), &list, false);
+
+        assert_eq!(names(&listed), ["dbo.Product", "dbo.Log", "dbo.Order", "dbo.Customer"]);
+        assert_eq!(
+            reference_lines(&listed),
+            [
+                "FK_Order_Customer: dbo.Order.CustomerId -> dbo.Customer.Id",
+                "FK_Log_Product: dbo.Log.ProductId -> dbo.Product.Id",
+            ]
+        );
+        assert_eq!(missing, ["dbo.Missing"]);
+    }
+
+    #[test]
+    fn listed_tables_fold_case_beyond_ascii() {
+        let schema = schema(vec![table("dbo.Größe", &["Id"], &["Id"])], Vec::new());
+        let list = parse_table_list("DBO.GRÖSSE\ndbo.GRÖßE").unwrap();
+        let (listed, missing) = in_listed_order(schema.clone(), &list, false);
+        assert_eq!(names(&listed), ["dbo.Größe"]);
+        assert_eq!(missing, ["DBO.GRÖSSE"]);
+
+        let (listed, missing) = in_listed_order(schema, &list, true);
+        assert!(listed.tables.is_empty());
+        assert_eq!(missing, ["DBO.GRÖSSE", "dbo.GRÖßE"]);
+    }
+
+    #[test]
+    fn references_into_an_excluded_schema_get_one_stub() {
+        let schema = schema(
+            vec![
+                table("dbo.Order", &["Id", "RegionId"], &["Id"]),
+                table("dbo.Customer", &["Id", "RegionId"], &["Id"]),
+                table("ref.Region", &["Id", "Name"], &["Id"]),
+                table("ref.Country", &["Id"], &["Id"]),
+            ],
+            vec![
+                reference("FK_Order_Region", "dbo.Order.RegionId", "ref.Region.Id"),
+                reference("FK_Customer_Region", "dbo.Customer.RegionId", "ref.Region.Id"),
+            ],
+        );
+        let filtered = TableFilter::new(None, Some("ref.*"), false, true).apply(schema);
+        assert_eq!(names(&filtered), ["dbo.Order", "dbo.Customer", "ref.Region"]);
+        assert!(filtered.tables[2].external && filtered.tables[2].columns.is_empty());
+        assert_eq!(
+            reference_lines(&filtered),
+            [
+                "FK_Order_Region: dbo.Order.RegionId -> ref.Region.Id",
+                "FK_Customer_Region: dbo.Customer.RegionId -> ref.Region.Id",
+            ]
+        );
+        let plantuml = crate::render::generate_plantuml(&filtered, &crate::render::RenderOptions::default());
+        let stubs: Vec<&str> = plantuml.lines().filter(|line| line.ends_with("<<external>>")).collect();
+        assert_eq!(stubs.len(), 1, "{}", plantuml);
+    }
+
+    #[test]
+    fn split_by_component_gives_one_part_per_cluster() {
+        let mut schema = schema(
+            vec![
+                table("dbo.Customer", &["Id"], &["Id"]),
+                table("hr.Department", &["Id"], &["Id"]),
+                table("dbo.Orders", &["Id", "CustomerId"], &["Id"]),
+                table("hr.Employee", &["Id", "DepartmentId"], &["Id"]),
+            ],
+            vec![
+                reference("FK_Orders_Customer", "dbo.Orders.CustomerId", "dbo.Customer.Id"),
+                reference("FK_Employee_Department", "hr.Employee.DepartmentId", "hr.Department.Id"),
+            ],
+        );
+        let parts = |schema: &DatabaseSchema| -> Vec<(String, Vec<String>, Vec<String>)> {
+            split_by_component(schema)
+                .into_iter()
+                .map(|(name, part)| (name, names(&part), reference_lines(&part)))
+                .collect()
+        };
+        let component = |name: &str, tables: [&str; 2], reference: &str| {
+            (name.to_string(), tables.map(str::to_string).to_vec(), vec![reference.to_string()])
+        };
+        let expected = vec![
+            component(
+                "component_1",
+                ["dbo.Customer", "dbo.Orders"],
+                "FK_Orders_Customer: dbo.Orders.CustomerId -> dbo.Customer.Id",
+            ),
+            component(
+                "component_2",
+                ["hr.Department", "hr.Employee"],
+                "FK_Employee_Department: hr.Employee.DepartmentId -> hr.Department.Id",
+            ),
+        ];
+        assert_eq!(parts(&schema), expected);
+
+        schema.tables.push(table("dbo.Setting", &["Id"], &["Id"]));
+        let with_misc = parts(&schema);
+        assert_eq!(with_misc[..2], expected);
+        assert_eq!(with_misc[2], ("misc".to_string(), vec!["dbo.Setting".to_string()], Vec::new()));
+    }
+
+    #[test]
+    fn per_table_focus_depths_combine_their_neighbourhoods() {
+        // A chain a → b → c → d → e → f
+        let names_in_chain = ["a", "b", "c", "d", "e", "f"];
+        let chain = schema(
+            names_in_chain.iter().map(|name| table(&format!("dbo.{}", name), &["Id", "NextId"], &["Id"])).collect(),
+            names_in_chain
+                .windows(2)
+                .map(|pair| {
+                    let constraint = format!("FK_{}_{}", pair[0], pair[1]);
+                    reference(&constraint, &format!("dbo.{}.NextId", pair[0]), &format!("dbo.{}.Id", pair[1]))
+                })
+                .collect(),
+        );
+        let anchors = parse_focus("dbo.a:2, dbo.f").unwrap();
+        assert_eq!((anchors[0].depth, anchors[1].depth), (Some(2), None));
+        let focused = focus(chain.clone(), &anchors, 1, false);
+        assert_eq!(names(&focused), ["dbo.a", "dbo.b", "dbo.c", "dbo.e", "dbo.f"]);
+        assert_eq!(reference_lines(&focused).len(), 3);
+        // Without its own depth, f follows --depth
+        assert_eq!(names(&focus(chain, &anchors, 0, false)), ["dbo.a", "dbo.b", "dbo.c", "dbo.f"]);
+        assert!(parse_focus("dbo.a:two").is_err());
+    }
+
+    #[test]
+    fn reachable_follows_foreign_keys_in_one_direction() {
+        // b → a, c → a, c → b, e → d, f → d, f → e and c → d
+        let graph = crate::testing::two_triangles;
+        let patterns = |name: &str| vec![NamePattern::parse(name)];
+        let dependencies = reachable(graph(), &patterns("dbo.c"), &[], false);
+        assert_eq!(names(&dependencies), ["dbo.a", "dbo.d", "dbo.b", "dbo.c"]);
+        assert_eq!(reference_lines(&dependencies).len(), 4);
+        let dependents = reachable(graph(), &[], &patterns("dbo.d"), false);
+        assert_eq!(names(&dependents), ["dbo.d", "dbo.e", "dbo.c", "dbo.f"]);
+        assert_eq!(names(&reachable(graph(), &patterns("dbo.a"), &[], false)), ["dbo.a"]);
+        // Both directions together keep the union
+        let both = reachable(graph(), &patterns("dbo.b"), &patterns("dbo.b"), false);
+        assert_eq!(names(&both), ["dbo.a", "dbo.b", "dbo.c"]);
+    }
+
+    #[test]
+    fn from_roots_keeps_the_tables_near_the_roots() {
+        // Street → City → Country → Region, with the self-referencing
+        // Employee also pointing at City
+        let geography = || {
+            schema(
+                vec![
+                    table("dbo.Region", &["Id"], &["Id"]),
+                    table("dbo.Country", &["Id", "RegionId"], &["Id"]),
+                    table("dbo.City", &["Id", "CountryId"], &["Id"]),
+                    table("dbo.Street", &["Id", "CityId"], &["Id"]),
+                    table("dbo.Employee", &["Id", "CityId", "ManagerId"], &["Id"]),
+                    table("dbo.Setting", &["Id"], &["Id"]),
+                ],
+                vec![
+                    reference("FK_Country_Region", "dbo.Country.RegionId", "dbo.Region.Id"),
+                    reference("FK_City_Country", "dbo.City.CountryId", "dbo.Country.Id"),
+                    reference("FK_Street_City", "dbo.Street.CityId", "dbo.City.Id"),
+                    reference("FK_Employee_City", "dbo.Employee.CityId", "dbo.City.Id"),
+                    reference("FK_Employee_Manager", "dbo.Employee.ManagerId", "dbo.Employee.Id"),
+                ],
+            )
+        };
+        let backbone = from_roots(geography(), Roots::NoOutgoing, 1);
+        assert_eq!(names(&backbone), ["dbo.Region", "dbo.Country", "dbo.Setting"]);
+        assert_eq!(reference_lines(&backbone), ["FK_Country_Region: dbo.Country.RegionId -> dbo.Region.Id"]);
+        assert_eq!(
+            names(&from_roots(geography(), Roots::NoOutgoing, 2)),
+            ["dbo.Region", "dbo.Country", "dbo.City", "dbo.Setting"]
+        );
+        assert_eq!(
+            names(&from_roots(geography(), Roots::NoIncoming, 1)),
+            ["dbo.City", "dbo.Street", "dbo.Employee", "dbo.Setting"]
+        );
+    }
+}
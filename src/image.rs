@@ -0,0 +1,194 @@
+use crate::error::DiagramError;
+use std::io;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// An image format a PlantUML server renders diagrams to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImageFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+impl ImageFormat {
+    pub fn parse(format: &str) -> Option<ImageFormat> {
+        match format {
+            "svg" => Some(ImageFormat::Svg),
+            "png" => Some(ImageFormat::Png),
+            _ => None,
+        }
+    }
+
+    /// The file extension, which is also the server's endpoint for the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Svg => "svg",
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
+/// The parts of a `http://host[:port][/path]` PlantUML server URL.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ServerUrl {
+    pub host: String,
+    pub port: u16,
+    /// The path the server is mounted under, without a trailing slash.
+    pub path: String,
+}
+
+impl ServerUrl {
+    /// Parses a server URL. Only plain HTTP is spoken, so `https://` URLs are
+    /// rejected.
+    pub fn parse(url: &str) -> Result<ServerUrl, DiagramError> {
+        let invalid = |reason: &str| DiagramError::InvalidArgument(format!("PlantUML server '{}' {}", url, reason));
+        let rest = match url.trim().strip_prefix("http://") {
+            Some(rest) => rest,
+            None if url.trim().starts_with("https://") => {
+                return Err(invalid("uses https, which isn't supported; run a local server over http"))
+            }
+            None => return Err(invalid("should start with http://")),
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(slash) => rest.split_at(slash),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| invalid("has an invalid port"))?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(invalid("has no host"));
+        }
+        Ok(ServerUrl {
+            host: host.to_string(),
+            port,
+            path: path.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+/// Splits an HTTP response into its status code and body.
+fn parse_response(response: &[u8]) -> Option<(u16, &[u8])> {
+    let end = response.windows(4).position(|window| window == b"\r\n\r\n")?;
+    let head = std::str::from_utf8(&response[..end]).ok()?;
+    let status = head.lines().next()?.split_whitespace().nth(1)?.parse().ok()?;
+    Some((status, &response[end + 4..]))
+}
+
+/// Renders a PlantUML source to an image by posting it to the server's
+/// `/svg` or `/png` endpoint.
+pub async fn render_image(server: &str, source: &str, format: ImageFormat) -> Result<Vec<u8>, DiagramError> {
+    let url = ServerUrl::parse(server)?;
+    let failed = |reason: String| {
+        DiagramError::Io(io::Error::other(format!("rendering through {} failed: {}", server, reason)))
+    };
+
+    // HTTP/1.0 keeps the body unchunked and ends it by closing the connection
+    let request = format!(
+        "POST {}/{} HTTP/1.0\r\nHost: {}:{}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\n\r\n",
+        url.path,
+        format.extension(),
+        url.host,
+        url.port,
+        source.len()
+    );
+    let response = async {
+        let mut stream = TcpStream::connect((url.host.as_str(), url.port)).await?;
+        stream.write_all(request.as_bytes()).await?;
+        stream.write_all(source.as_bytes()).await?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+        Ok::<_, io::Error>(response)
+    }
+    .await
+    .map_err(|err| failed(err.to_string()))?;
+
+    match parse_response(&response) {
+        Some((200, body)) => Ok(body.to_vec()),
+        Some((status, _)) => Err(failed(format!("the server answered with status {}", status))),
+        None => Err(failed("the server's response isn't HTTP".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Serves one request with `response`, returning the request received.
+    async fn serve_once(response: &'static [u8]) -> (String, tokio::task::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = format!("http://{}/plantuml/", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            // The request is complete once the whole Content-Length body is in
+            loop {
+                let read = stream.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length: usize = head
+                        .lines()
+                        .find_map(|line| line.strip_prefix("Content-Length: "))
+                        .and_then(|length| length.parse().ok())
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break;
+                    }
+                }
+                if read == 0 {
+                    break;
+                }
+            }
+            stream.write_all(response).await.unwrap();
+            request
+        });
+        (server, handle)
+    }
+
+    #[tokio::test]
+    async fn posts_the_source_and_returns_the_image() {
+        let (server, handle) = serve_once(b"HTTP/1.1 200 OK\r\nContent-Type: image/svg+xml\r\n\r\n<svg/>").await;
+        let source = "@startuml\nclass Order\n@enduml\n";
+        let image = render_image(&server, source, ImageFormat::Svg).await.unwrap();
+        assert_eq!(image, b"<svg/>");
+
+        let request = String::from_utf8(handle.await.unwrap()).unwrap();
+        assert!(request.starts_with("POST /plantuml/svg HTTP/1.0\r\n"), "{}", request);
+        assert!(request.contains(&format!("Content-Length: {}\r\n", source.len())), "{}", request);
+        assert!(request.ends_with(&format!("\r\n\r\n{}", source)), "{}", request);
+    }
+
+    #[tokio::test]
+    async fn reports_a_failed_status() {
+        let (server, handle) = serve_once(b"HTTP/1.1 400 Bad Request\r\n\r\nSyntax error").await;
+        match render_image(&server, "@startuml\n", ImageFormat::Png).await {
+            Err(err) => assert_eq!(
+                err.to_string(),
+                format!("io error: rendering through {} failed: the server answered with status 400", server)
+            ),
+            Ok(image) => panic!("expected the render to fail, got {} bytes", image.len()),
+        }
+        assert!(String::from_utf8(handle.await.unwrap()).unwrap().starts_with("POST /plantuml/png "));
+    }
+
+    #[test]
+    fn server_urls_need_plain_http() {
+        assert_eq!(
+            ServerUrl::parse("http://localhost:8080/plantuml/").unwrap(),
+            ServerUrl {
+                host: "localhost".to_string(),
+                port: 8080,
+                path: "/plantuml".to_string(),
+            }
+        );
+        assert_eq!(ServerUrl::parse("http://plantuml").unwrap().port, 80);
+        for url in ["https://plantuml.com", "plantuml:8080", "http://:8080", "http://host:port"] {
+            assert!(matches!(ServerUrl::parse(url), Err(DiagramError::InvalidArgument(_))), "{}", url);
+        }
+    }
+}
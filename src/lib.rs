@@ -0,0 +1,28 @@
+pub mod analysis;
+pub mod anonymize;
+pub mod atomic;
+pub mod batch;
+pub mod check;
+pub mod connection;
+pub mod ddl;
+pub mod diff;
+pub mod doctor;
+pub mod encoding;
+pub mod error;
+pub mod extract;
+pub mod filter;
+pub mod image;
+pub mod manifest;
+pub mod merge;
+pub mod model;
+pub mod render;
+pub mod rng;
+pub mod validate;
+
+#[cfg(test)]
+mod testing;
+
+pub use error::DiagramError;
+pub use extract::{extract_schema, ExtractOptions};
+pub use model::{Column, DataType, DatabaseSchema, Index, Reference, Table, Trigger};
+pub use render::RenderOptions;
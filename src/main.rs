@@ -1,133 +1,63 @@
-use clap::{Arg, Command};
-use serde::Serialize;
-use sqlx::{MssqlPool, Row};
-use std::fs::File;
-use std::io::Write;
+use clap::builder::PossibleValuesParser;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use sqlx::MssqlPool;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tsql_diagram_generator::anonymize::anonymize;
+use tsql_diagram_generator::analysis::{connected_components, design_warnings, layers, procedure_dependencies};
+use tsql_diagram_generator::atomic::{write_atomically, AtomicFile};
+use tsql_diagram_generator::batch::{directory_names, parse_batch, BatchEntry};
+use tsql_diagram_generator::check::diff_output;
+use tsql_diagram_generator::connection::{
+    check_tcp_host, connect, connection_string, expand_env, parse_endpoint, resolve_endpoint, Encrypt, Endpoint,
+    SessionOptions,
+};
+use tsql_diagram_generator::ddl::parse_ddl;
+use tsql_diagram_generator::diff::{changed_only, diff_schemas, parse_snapshot, DiffOutput};
+use tsql_diagram_generator::doctor::diagnose;
+use tsql_diagram_generator::encoding::OutputEncoding;
+use tsql_diagram_generator::error::ErrorFormat;
+use tsql_diagram_generator::extract;
+use tsql_diagram_generator::filter::{
+    drop_isolated, focus, from_roots, in_listed_order, parse_focus, parse_patterns, parse_table_list, reachable,
+    split_by_component, split_by_schema, FocusTable, NamePattern, Roots, TableFilter,
+};
+use tsql_diagram_generator::image::{render_image, ImageFormat, ServerUrl};
+use tsql_diagram_generator::manifest::{Manifest, ObjectCounts};
+use tsql_diagram_generator::merge::{
+    combine_databases, merge_catalogs, parse_cross_references, resolve_synonyms, Synonym, DATABASE_PROPERTY,
+};
+use tsql_diagram_generator::render::{
+    format_names, header_banner, lookup_format, parse_json_fields, parse_rename_map, ArrowStyle, AttributeIcons,
+    ColorBy, IdentifierCase, Layout, OutputFormat, PlantUmlFormat, PlantUmlStream, QuoteStyle, ReferenceStyle,
+    COLUMN_HINTS_PROPERTY, DEFAULT_PII_PATTERNS,
+};
+use tsql_diagram_generator::validate::validate;
+use tsql_diagram_generator::{extract_schema, DatabaseSchema, DiagramError, ExtractOptions, RenderOptions, Table};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
 
-#[derive(Serialize)]
-struct Column {
-    name: String,
-    data_type: String,
-}
-
-#[derive(Serialize)]
-struct Table {
-    name: String,
-    columns: Vec<Column>,
-}
-
-#[derive(Serialize)]
-struct Reference {
-    table: String,
-    column: String,
-    referenced_table: String,
-    referenced_column: String,
-}
-
-#[derive(Serialize)]
-struct DatabaseSchema {
-    tables: Vec<Table>,
-    references: Vec<Reference>,
-}
-
-async fn get_tables(pool: &MssqlPool) -> Result<Vec<Table>, Box<dyn std::error::Error>> {
-    let mut tables = Vec::new();
-    let rows = sqlx::query("SELECT TABLE_NAME FROM INFORMATION_SCHEMA.TABLES WHERE TABLE_TYPE = 'BASE TABLE'")
-        .fetch_all(pool)
-        .await?;
+/// Exit code used when the command line can't be parsed.
+const EXIT_BAD_ARGUMENTS: u8 = 4;
 
-    for row in rows {
-        let table_name: String = row.try_get("TABLE_NAME")?;
-        let columns = get_columns(pool, &table_name).await?;
-        tables.push(Table {
-            name: table_name,
-            columns,
-        });
-    }
-    Ok(tables)
-}
+/// File `--stream` writes the PlantUML diagram to.
+const STREAM_FILE: &str = "schema.puml";
 
-async fn get_columns(pool: &MssqlPool, table_name: &str) -> Result<Vec<Column>, Box<dyn std::error::Error>> {
-    let mut columns = Vec::new();
-    let query = format!("SELECT COLUMN_NAME, DATA_TYPE FROM INFORMATION_SCHEMA.COLUMNS WHERE TABLE_NAME = '{}'", table_name);
-    let rows = sqlx::query(&query)
-        .fetch_all(pool)
-        .await?;
+/// File the `--manifest` inventory is written to.
+const MANIFEST_FILE: &str = "schema.manifest.json";
 
-    for row in rows {
-        let column_name: String = row.try_get("COLUMN_NAME")?;
-        let data_type: String = row.try_get("DATA_TYPE")?;
-        columns.push(Column {
-            name: column_name,
-            data_type: data_type,
-        });
-    }
-    Ok(columns)
-}
-
-async fn get_references(pool: &MssqlPool) -> Result<Vec<Reference>, Box<dyn std::error::Error>> {
-    let mut references = Vec::new();
-    let query = "
-        SELECT 
-            tp.name AS TABLE_NAME,
-            cp.name AS COLUMN_NAME,
-            tr.name AS REFERENCED_TABLE_NAME,
-            cr.name AS REFERENCED_COLUMN_NAME
-        FROM 
-            sys.foreign_keys AS fk
-        INNER JOIN 
-            sys.foreign_key_columns AS fkc ON fk.object_id = fkc.constraint_object_id
-        INNER JOIN 
-            sys.tables AS tp ON fkc.parent_object_id = tp.object_id
-        INNER JOIN 
-            sys.columns AS cp ON fkc.parent_object_id = cp.object_id AND fkc.parent_column_id = cp.column_id
-        INNER JOIN 
-            sys.tables AS tr ON fkc.referenced_object_id = tr.object_id
-        INNER JOIN 
-            sys.columns AS cr ON fkc.referenced_object_id = cr.object_id AND fkc.referenced_column_id = cr.column_id";
-
-    let rows = sqlx::query(query)
-        .fetch_all(pool)
-        .await?;
-
-    for row in rows {
-        let table: String = row.try_get("TABLE_NAME")?;
-        let column: String = row.try_get("COLUMN_NAME")?;
-        let referenced_table: String = row.try_get("REFERENCED_TABLE_NAME")?;
-        let referenced_column: String = row.try_get("REFERENCED_COLUMN_NAME")?;
-        references.push(Reference {
-            table,
-            column,
-            referenced_table,
-            referenced_column,
-        });
-    }
-    Ok(references)
-}
-
-fn generate_plantuml(schema: &DatabaseSchema) -> String {
-    let mut plantuml = String::new();
-    plantuml.push_str("@startuml\n");
-    for table in &schema.tables {
-        plantuml.push_str(&format!("class {} {{\n", table.name));
-        for column in &table.columns {
-            plantuml.push_str(&format!("  {} : {}\n", column.name, column.data_type));
-        }
-        plantuml.push_str("}\n");
-    }
-    for reference in &schema.references {
-        plantuml.push_str(&format!(
-            "{}::{} --> {}::{} : {}\n",
-            reference.table, reference.column, reference.referenced_table, reference.referenced_column, reference.column
-        ));
-    }
-    plantuml.push_str("@enduml\n");
-    plantuml
-}
-
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let matches = Command::new("TSQLDiagramGenerator")
+fn command() -> Command {
+    Command::new("TSQLDiagramGenerator")
         .version("1.0")
         .author("Tyler Maginnis <maginnist@gmail.com>")
         .about("Generates a TSQL database diagram")
@@ -135,57 +65,2218 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             Arg::new("ip_address")
                 .short('i')
                 .long("ip_address")
-                .help("Sets the IP address of the SQL server")
-                .required(true)
+                .help("Sets the IP address of the SQL server, optionally as HOST,PORT or as HOST\\INSTANCE for a named instance")
+                .required_unless_present_any(["batch", "from_sql"])
+        )
+        .arg(
+            Arg::new("port")
+                .long("port")
+                .value_name("PORT")
+                .help("Sets the TCP port of the SQL server. Resolution order: --port, then a HOST,PORT suffix, which must agree with it, then the port the SQL Server Browser gives for a HOST\\INSTANCE name, looked up only when no port is given (with a warning when --port overrides the instance), then 1433")
+                .value_parser(clap::value_parser!(u16).range(1..))
         )
         .arg(
             Arg::new("username")
                 .short('u')
                 .long("username")
                 .help("Sets the username for the SQL server")
-                .required(true)
+                .required_unless_present_any(["batch", "from_sql"])
         )
         .arg(
             Arg::new("password")
                 .short('p')
                 .long("password")
                 .help("Sets the password for the SQL server")
-                .required(true)
+                .required_unless_present_any(["batch", "from_sql", "password_stdin"])
+        )
+        .arg(
+            Arg::new("password_stdin")
+                .long("password-stdin")
+                .help("Reads the password for the SQL server from the first line of standard input, trimmed, instead of --password; --batch entries with a password of their own still use it")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["password", "from_sql"])
         )
         .arg(
             Arg::new("initial_catalog")
                 .short('c')
                 .long("initial_catalog")
-                .help("Sets the initial catalog for the SQL server")
-                .required(true)
+                .help("Sets the initial catalog for the SQL server; comma-separate to diagram several catalogs")
+                .required_unless_present_any(["batch", "from_sql"])
+        )
+        .arg(
+            Arg::new("read_only")
+                .long("read-only")
+                .help("Not yet supported by the SQL Server driver, so always rejected: would connect with ApplicationIntent=ReadOnly; connect to the readable secondary's own address instead")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("encrypt")
+                .long("encrypt")
+                .value_name("MODE")
+                .help("Sets the connection encryption mode. Encryption is not yet supported by the SQL Server driver, so true and strict are always rejected and only false connects")
+                .value_parser(["true", "false", "strict"])
+        )
+        .arg(
+            Arg::new("application_name")
+                .long("application-name")
+                .value_name("NAME")
+                .help("Not yet supported by the SQL Server driver, so always rejected: would set the application name reported in sys.dm_exec_sessions")
+        )
+        .arg(
+            Arg::new("cross_db_references")
+                .long("cross-db-references")
+                .value_name("FILE")
+                .help("Reads cross-database references (Catalog.Schema.Table.Column -> Catalog.Schema.Table.Column per line)")
+        )
+        .arg(
+            Arg::new("include_synonyms")
+                .long("include-synonyms")
+                .help("Resolves cross-database references that target a synonym to the synonym's base table, labelled via synonym")
+                .action(ArgAction::SetTrue)
+                .requires("cross_db_references")
+        )
+        .arg(
+            Arg::new("format")
+                .short('f')
+                .long("format")
+                .help("Sets the output format; repeat or comma-separate to emit several formats")
+                .value_parser(PossibleValuesParser::new(format_names()))
+                .value_delimiter(',')
+                .action(ArgAction::Append)
+                .default_value("plantuml")
+        )
+        .arg(
+            Arg::new("json_fields")
+                .long("json-fields")
+                .value_name("FIELDS")
+                .help("Limits the json format to these comma-separated table and column fields (e.g. name,data_type)")
+                .value_parser(parse_json_fields)
+        )
+        .arg(
+            Arg::new("header_comment")
+                .long("header-comment")
+                .value_name("TEXT")
+                .help("Adds a comment line at the top of every generated file, in each format's comment syntax; repeat for several lines")
+                .action(ArgAction::Append)
+        )
+        .arg(
+            Arg::new("group_by_property")
+                .long("group-by-property")
+                .value_name("NAME")
+                .help("Groups tables into packages by the value of this extended property (e.g. Domain)")
+        )
+        .arg(
+            Arg::new("highlight_cycles")
+                .long("highlight-cycles")
+                .help("Marks relationships that form circular foreign-key chains with <<cycle>>")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("purpose_legend")
+                .long("purpose-legend")
+                .help("Adds a floating note listing each described table's MS_Description")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("server_info_note")
+                .long("server-info-note")
+                .help("Adds a floating note naming the server, SQL Server version and database the diagram was generated from")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("anonymize")
+        )
+        .arg(
+            Arg::new("embed_provenance")
+                .long("embed-provenance")
+                .help("Adds a floating note listing the tool version and the options given on the command line, with the password redacted")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("anonymize")
+        )
+        .arg(
+            Arg::new("svg_tooltips")
+                .long("svg-tooltips")
+                .help("Adds table and column descriptions to the PlantUML diagram as tooltips shown on hover when rendered to SVG")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("columns_summary")
+                .long("columns-summary")
+                .help("Replaces PlantUML and DOT column lists with a summary line of column, primary key, foreign key and index counts")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("type_legend")
+                .long("type-legend")
+                .help("Adds a floating note counting columns by base data type")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("highlight_type_mismatches")
+                .long("highlight-type-mismatches")
+                .help("Marks relationships whose two columns differ in data type with <<type mismatch>>")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("include_disabled_fks")
+                .long("include-disabled-fks")
+                .help("Includes disabled foreign keys, rendered as dashed relationships")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("include_legacy_bindings")
+                .long("include-legacy-bindings")
+                .help("Annotates columns with defaults and rules bound via sp_bindefault/sp_bindrule")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("include_fulltext")
+                .long("include-fulltext")
+                .help("Marks columns covered by a full-text index with <<FTS: catalog>>")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("include")
+                .long("include")
+                .value_name("PATTERNS")
+                .help("Only includes tables matching these comma-separated patterns (e.g. dbo.Order*,Customers)")
+        )
+        .arg(
+            Arg::new("exclude")
+                .long("exclude")
+                .value_name("PATTERNS")
+                .help("Excludes tables matching these comma-separated patterns")
+        )
+        .arg(
+            Arg::new("external_stubs")
+                .long("external-stubs")
+                .help("Draws filtered-out tables referenced by included ones as name-only <<external>> stubs")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("only_referenced")
+                .long("only-referenced")
+                .help("Drops tables that have no relationships with the other included tables")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("tables_file")
+                .long("tables-file")
+                .value_name("PATH")
+                .help("Limits the diagram to the schema-qualified tables listed one per line in this file, drawn in the file's order with the references among them; listed tables missing from the database are warned about")
+        )
+        .arg(
+            Arg::new("focus")
+                .long("focus")
+                .value_name("TABLES")
+                .help("Limits the diagram to these comma-separated tables and their neighbours, each optionally with its own depth (e.g. Orders:2,Customers)")
+                .value_parser(parse_focus)
+        )
+        .arg(
+            Arg::new("depth")
+                .long("depth")
+                .value_name("N")
+                .help("Sets how many foreign key hops --focus expands tables without a depth of their own")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("1")
+                .requires("focus")
+        )
+        .arg(
+            Arg::new("depends_on")
+                .long("depends-on")
+                .value_name("TABLES")
+                .help("Limits the diagram to these comma-separated tables and every table they reach through their foreign keys")
+        )
+        .arg(
+            Arg::new("depended_on_by")
+                .long("depended-on-by")
+                .value_name("TABLES")
+                .help("Limits the diagram to these comma-separated tables and every table whose foreign keys lead to them")
+        )
+        .arg(
+            Arg::new("limit_depth_from_roots")
+                .long("limit-depth-from-roots")
+                .value_name("N")
+                .help("Limits the diagram to the root tables and those within N foreign key hops of them, for a backbone overview")
+                .value_parser(clap::value_parser!(u64))
+        )
+        .arg(
+            Arg::new("roots")
+                .long("roots")
+                .value_name("KIND")
+                .help("Sets which tables --limit-depth-from-roots starts from: those referencing no other table, expanded to the tables referencing them, or those no table references, expanded to the tables they reference")
+                .value_parser(["no-outgoing", "no-incoming"])
+                .default_value("no-outgoing")
+                .requires("limit_depth_from_roots")
+        )
+        .arg(
+            Arg::new("case_sensitive")
+                .long("case-sensitive")
+                .help("Matches table patterns case-sensitively, for databases with case-sensitive collations")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("consistent_snapshot")
+                .long("consistent-snapshot")
+                .help("Reads the schema in a single snapshot transaction for a consistent view; the database must allow snapshot isolation")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("query_timeout")
+                .long("query-timeout")
+                .value_name("SECONDS")
+                .help("Sets a timeout in seconds for each metadata query")
+                .value_parser(clap::value_parser!(u64))
+        )
+        .arg(
+            Arg::new("watch")
+                .long("watch")
+                .value_name("SECONDS")
+                .help("Regenerates the output every SECONDS seconds until interrupted with Ctrl-C")
+                .value_parser(clap::value_parser!(u64).range(1..))
+        )
+        .arg(
+            Arg::new("skip_errors")
+                .long("skip-errors")
+                .help("Skips tables whose metadata queries fail instead of aborting")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("fail_on_warnings")
+                .long("fail-on-warnings")
+                .help("Exits with code 8 after writing the output if any structural warnings were found")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("validate")
+                .long("validate")
+                .help("Checks the extracted schema for foreign keys naming missing tables or columns, duplicate tables and empty names, exiting with code 11 if any are found")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("stream")
+        )
+        .arg(
+            Arg::new("fail_on_empty")
+                .long("fail-on-empty")
+                .help("Exits with code 5 instead of writing an empty diagram when no tables are found")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .help("Suppresses warnings written to stderr")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("error_format")
+                .long("error-format")
+                .value_name("FORMAT")
+                .help("Reports a failure on stderr as an error: line, or as a JSON object with its kind, message and table for tools embedding this one")
+                .value_parser(["human", "json"])
+                .default_value("human")
+        )
+        .arg(
+            Arg::new("quote_style")
+                .long("quote-style")
+                .help("Sets how identifiers are quoted in DBML output")
+                .value_parser(["brackets", "ansi", "backtick", "none"])
+                .default_value("ansi")
+        )
+        .arg(
+            Arg::new("arrow")
+                .long("arrow")
+                .help("Sets the PlantUML connector drawn for foreign keys")
+                .value_parser(["solid", "dashed", "composition", "aggregation"])
+                .default_value("solid")
+        )
+        .arg(
+            Arg::new("reference_style")
+                .long("reference-style")
+                .help("Sets whether PlantUML foreign key edges connect columns or table headers")
+                .value_parser(["columns", "tables"])
+                .default_value("columns")
+        )
+        .arg(
+            Arg::new("max_edges_per_table")
+                .long("max-edges-per-table")
+                .value_name("N")
+                .help("Replaces the PlantUML arrows of tables with more than N relationships with a note listing the related tables")
+                .value_parser(clap::value_parser!(u64).range(1..))
+        )
+        .arg(
+            Arg::new("color_by")
+                .long("color-by")
+                .help("Colours PlantUML classes by their schema, each schema always getting the same colour")
+                .value_parser(["none", "schema"])
+                .default_value("none")
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .help("Seeds the schema colours of --color-by schema and the tables --layered breaks cycles at; the same seed always gives the same output")
+                .value_parser(clap::value_parser!(u64))
+        )
+        .arg(
+            Arg::new("merge_parallel_edges")
+                .long("merge-parallel-edges")
+                .help("Draws a single edge between two tables linked by several foreign keys, listing every column pair")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("verbose_labels")
+                .long("verbose-labels")
+                .help("Labels relationships with both columns (child → parent), listing every pair of a composite foreign key on one edge")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("ef_hints")
+                .long("ef-hints")
+                .help("Labels relationships with the Entity Framework navigation properties they scaffold to, such as Order.Customer ↔ Customer.Orders")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("fk_cardinality")
+                .long("fk-cardinality")
+                .help("Marks relationships one-to-one (0..1 to 1) when the primary key or an unfiltered unique index covers the foreign key's referencing columns taken together, and one-to-many (* to 1) otherwise")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("layout")
+                .long("layout")
+                .help("Sets the PlantUML layout direction")
+                .value_parser(["top-down", "left-right"])
+                .default_value("top-down")
+        )
+        .arg(
+            Arg::new("pivot_on")
+                .long("pivot-on")
+                .value_name("TABLE")
+                .help("Centers this table in PlantUML output, spreading its related tables around it")
+        )
+        .arg(
+            Arg::new("together")
+                .long("together")
+                .help("Keeps tables linked by foreign keys near each other with PlantUML together blocks")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("cluster_layout")
+                .long("cluster-layout")
+                .help("Keeps tightly linked communities of tables together in PlantUML output, splitting large clusters to reduce edge crossings")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("together")
+        )
+        .arg(
+            Arg::new("layered")
+                .long("layered")
+                .help("Ranks PlantUML classes in layers so foreign keys point one way, tables referencing nothing at the top; cycles are broken with a warning")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["together", "cluster_layout", "pivot_on", "group_by_property"])
+        )
+        .arg(
+            Arg::new("case")
+                .long("case")
+                .help("Sets the case of table and column names in the diagrams; JSON and YAML exports keep the originals")
+                .value_parser(["preserve", "lower", "upper"])
+                .default_value("preserve")
+        )
+        .arg(
+            Arg::new("split_by_schema")
+                .long("split-by-schema")
+                .help("Writes a separate output file for each database schema")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("split_by_component")
+                .long("split-by-component")
+                .help("Writes a separate output file for each cluster of tables linked by foreign keys, with isolated tables in misc")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("split_by_schema")
+        )
+        .arg(
+            Arg::new("indent")
+                .long("indent")
+                .value_name("N")
+                .help("Indents nested lines of the PlantUML, Mermaid, DOT, DBML and PlantUML JSON output by N spaces per level")
+                .value_parser(clap::value_parser!(u64))
+        )
+        .arg(
+            Arg::new("compact_output")
+                .long("compact-output")
+                .help("Leaves out optional indentation and blank lines to minimize file sizes")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("indent")
+        )
+        .arg(
+            Arg::new("output_encoding")
+                .long("output-encoding")
+                .help("Sets the character encoding of the generated files; utf16le files start with a byte order mark")
+                .value_parser(["utf8", "utf16le", "windows1252"])
+                .default_value("utf8")
+                .conflicts_with("stream")
+        )
+        .arg(
+            Arg::new("output_dir")
+                .long("output-dir")
+                .value_name("DIR")
+                .help("Writes the generated files to this directory instead of the current one")
+        )
+        .arg(
+            Arg::new("batch")
+                .long("batch")
+                .value_name("FILE")
+                .help("Diagrams every database listed in FILE, one JSON object per line, into its own directory under --output-dir")
+                .conflicts_with_all(["watch", "zip", "anonymize_map"])
+        )
+        .arg(
+            Arg::new("from_sql")
+                .long("from-sql")
+                .value_name("PATH")
+                .help("Diagrams the tables and keys a T-SQL DDL script creates instead of connecting to a server")
+                .conflicts_with_all(["initial_catalog", "batch", "doctor", "stream", "server_info_note", "include_synonyms"])
+        )
+        .arg(
+            Arg::new("batch_concurrency")
+                .long("batch-concurrency")
+                .value_name("N")
+                .help("Sets how many databases of a --batch run are diagrammed at once")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .default_value("4")
+                .requires("batch")
+        )
+        .arg(
+            Arg::new("combined")
+                .long("combined")
+                .value_name("PATH")
+                .help("Also draws every database of a --batch run into this one PlantUML file, each in its own package, with the --cross-db-references between them; those are then left out of the per-database output")
+                .requires("batch")
+                .conflicts_with_all(["anonymize", "group_by_property"])
+        )
+        .arg(
+            Arg::new("zip")
+                .long("zip")
+                .value_name("PATH")
+                .help("Bundles all generated output files into a single zip archive")
+        )
+        .arg(
+            Arg::new("also_render")
+                .long("also-render")
+                .value_name("FORMAT")
+                .help("Also writes each PlantUML file rendered to an image by the --plantuml-server, keeping the source")
+                .value_parser(["svg", "png"])
+                .conflicts_with_all(["check", "zip"])
+        )
+        .arg(
+            Arg::new("plantuml_server")
+                .long("plantuml-server")
+                .value_name("URL")
+                .help("Sets the http:// URL of the PlantUML server --also-render uses")
+                .default_value("http://localhost:8080")
+        )
+        .arg(
+            Arg::new("doctor")
+                .long("doctor")
+                .help("Checks the connection and the metadata permissions extraction needs instead of writing a diagram, exiting with code 10 if any check fails")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["batch", "watch"])
+        )
+        .arg(
+            Arg::new("count_only")
+                .long("count-only")
+                .help("Prints the numbers of tables, views, columns, foreign keys, indexes and procedures in each catalog instead of writing a diagram, counted by the server without extracting the schema, so filters don't apply")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["batch", "watch", "doctor", "from_sql"])
+        )
+        .arg(
+            Arg::new("count_format")
+                .long("count-format")
+                .help("Prints the --count-only tallies as one line per catalog or as one JSON object per catalog")
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .requires("count_only")
+        )
+        .arg(
+            Arg::new("components")
+                .long("components")
+                .help("Prints the clusters of tables linked by foreign keys and the isolated tables instead of writing a diagram")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("fingerprint")
+                .long("fingerprint")
+                .help("Prints a SHA-256 of the tables, columns, types, primary keys and foreign keys instead of writing a diagram; it ignores descriptions, statistics and table order, so environments with the same structure print the same hash")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["anonymize", "components"])
+        )
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .help("Compares the generated output with the existing files instead of writing them, printing a diff and exiting with code 7 if they differ")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["zip", "watch"])
+        )
+        .arg(
+            Arg::new("compare_to")
+                .long("compare-to")
+                .value_name("FILE")
+                .help("Prints the changes since an earlier schema snapshot written with --format yaml")
+        )
+        .arg(
+            Arg::new("diff_output")
+                .long("diff-output")
+                .help("Sets the format of the --compare-to report, independently of --format")
+                .value_parser(["text", "json", "markdown"])
+                .default_value("text")
+                .requires("compare_to")
+        )
+        .arg(
+            Arg::new("changed_only")
+                .long("changed-only")
+                .help("Only diagrams the tables added or changed since the --compare-to snapshot")
+                .action(ArgAction::SetTrue)
+                .requires("compare_to")
+        )
+        .arg(
+            Arg::new("stream")
+                .long("stream")
+                .help("Writes each PlantUML class to schema.puml as its table is read, adding the relationships at the end")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([
+                    "zip",
+                    "check",
+                    "watch",
+                    "manifest",
+                    "components",
+                    "fingerprint",
+                    "split_by_schema",
+                    "split_by_component",
+                    "overview",
+                    "only_referenced",
+                    "focus",
+                    "tables_file",
+                    "depends_on",
+                    "depended_on_by",
+                    "limit_depth_from_roots",
+                    "also_render",
+                    "cross_db_references",
+                    "group_by_property",
+                    "combined",
+                    "together",
+                    "cluster_layout",
+                    "layered",
+                    "purpose_legend",
+                    "svg_tooltips",
+                    "respect_column_hints",
+                    "include_filegroups",
+                    "include_triggers",
+                    "include_proc_dependencies",
+                    "resolve_indirection",
+                    "history_links",
+                    "include_indexes",
+                    "columns_summary",
+                    "include_row_counts",
+                    "include_sizes",
+                    "include_table_types",
+                    "since",
+                    "include_usage",
+                    "include_legacy_bindings",
+                    "include_fulltext",
+                    "case",
+                    "auto_overview_threshold",
+                    "anonymize",
+                    "compare_to",
+                    "annotation_query",
+                    "consistent_snapshot",
+                ])
+        )
+        .arg(
+            Arg::new("anonymize")
+                .long("anonymize")
+                .help("Replaces table and column names with Table1, Col1, ... placeholders, keeping types and relationships")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("anonymize_map")
+                .long("anonymize-map")
+                .value_name("PATH")
+                .help("Writes the placeholder-to-name mapping of --anonymize to this JSON file")
+                .requires("anonymize")
+        )
+        .arg(
+            Arg::new("rename_map")
+                .long("rename-map")
+                .value_name("PATH")
+                .help("Labels PlantUML classes and DOT nodes with display names from a JSON object or CSV file of physical,display table names, keeping relationships and JSON output on the physical names")
+                .conflicts_with("anonymize")
+        )
+        .arg(
+            Arg::new("manifest")
+                .long("manifest")
+                .help("Also writes schema.manifest.json with object counts and the generation time")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("relationships_only")
+                .long("relationships-only")
+                .help("Renders tables without columns, showing only their relationships")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("auto_overview_threshold")
+                .long("auto-overview-threshold")
+                .value_name("N")
+                .help("Renders relationships only when more than N tables are diagrammed")
+                .value_parser(clap::value_parser!(usize))
+        )
+        .arg(
+            Arg::new("force_detailed")
+                .long("force-detailed")
+                .help("Keeps the detailed diagram even above --auto-overview-threshold")
+                .action(ArgAction::SetTrue)
+                .requires("auto_overview_threshold")
+        )
+        .arg(
+            Arg::new("topology_only")
+                .long("topology-only")
+                .help("Skips reading columns and draws only the tables and their relationships, which is much faster on wide schemas")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("overview")
+                .long("overview")
+                .help("Also writes a <base>.overview.puml map showing only tables and relationships")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("no_columns_for")
+                .long("no-columns-for")
+                .value_name("PATTERNS")
+                .help("Renders tables matching these comma-separated patterns without their columns")
+        )
+        .arg(
+            Arg::new("columns_match")
+                .long("columns-match")
+                .value_name("PATTERNS")
+                .help("Only shows columns matching these comma-separated patterns (e.g. Created*,Modified*), plus key columns")
+        )
+        .arg(
+            Arg::new("hide_columns")
+                .long("hide-columns")
+                .value_name("PATTERNS")
+                .help("Hides columns matching these comma-separated patterns (e.g. RowVersion,CreatedBy,Modified*) in every table, except primary and foreign key columns; JSON and YAML output keep them")
+        )
+        .arg(
+            Arg::new("max_columns_per_table")
+                .long("max-columns-per-table")
+                .value_name("N")
+                .help("Shows at most N columns per table, plus key columns, and counts the rest")
+                .value_parser(clap::value_parser!(usize))
+        )
+        .arg(
+            Arg::new("first_columns")
+                .long("first-columns")
+                .value_name("N")
+                .help("Shows only each table's first N columns by ordinal position, plus key columns, and counts the rest")
+                .value_parser(clap::value_parser!(usize))
+        )
+        .arg(
+            Arg::new("max_table_name_length")
+                .long("max-table-name-length")
+                .value_name("N")
+                .help("Truncates PlantUML class labels longer than N characters, keeping edges connected through short aliases")
+                .value_parser(clap::value_parser!(u64).range(2..))
+        )
+        .arg(
+            Arg::new("wrap_columns")
+                .long("wrap-columns")
+                .value_name("N")
+                .help("Splits PlantUML classes of more than 20 columns into N side-by-side lists, key columns first")
+                .value_parser(clap::value_parser!(u64).range(2..))
+        )
+        .arg(
+            Arg::new("respect_column_hints")
+                .long("respect-column-hints")
+                .help("Shows only the columns listed in a table's DiagramColumns extended property")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("compact")
+                .long("compact")
+                .help("Tightens the PlantUML layout by hiding empty members and class circles")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("show_types")
+                .long("show-types")
+                .value_name("BOOL")
+                .help("Shows column data types; pass false for a cleaner attribute list")
+                .value_parser(clap::value_parser!(bool))
+                .default_value("true")
+        )
+        .arg(
+            Arg::new("sample_data")
+                .long("sample-data")
+                .value_name("ROWS")
+                .help("Attaches up to ROWS example rows to each table as a note. This reads actual table data, so only use it where the data may be shared")
+                .value_parser(clap::value_parser!(u32))
+        )
+        .arg(
+            Arg::new("annotation_query")
+                .long("annotation-query")
+                .value_name("SQL")
+                .help("Runs a query returning (table_name, annotation) text rows and attaches each annotation to its table as a note")
+        )
+        .arg(
+            Arg::new("include_filegroups")
+                .long("include-filegroups")
+                .help("Shows the filegroup each table is stored on in its class header")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("hide_primary_filegroup")
+                .long("hide-primary-filegroup")
+                .help("With --include-filegroups, leaves out tables stored on PRIMARY")
+                .action(ArgAction::SetTrue)
+                .requires("include_filegroups")
+        )
+        .arg(
+            Arg::new("include_triggers")
+                .long("include-triggers")
+                .help("Marks tables that have triggers with a <<triggers: N>> stereotype")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("history_links")
+                .long("history-links")
+                .help("Links each system-versioned temporal table to its history table with a dotted <<history>> edge, kept apart from foreign keys (SQL Server 2016+)")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("include_proc_dependencies")
+                .long("include-proc-dependencies")
+                .help("Draws stored procedures as PlantUML <<proc>> nodes with dashed edges to the tables they reference by name")
+                .action(ArgAction::SetTrue)
         )
-        .get_matches();
+        .arg(
+            Arg::new("resolve_indirection")
+                .long("resolve-indirection")
+                .help("Draws views as PlantUML <<view>> nodes with dashed depends-on edges to the tables they select from, and synonyms for tables of the database as <<synonym>> nodes with dashed alias edges to their base tables")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("max_procs")
+                .long("max-procs")
+                .value_name("N")
+                .help("Sets how many procedures, in name order, --include-proc-dependencies draws")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .default_value("20")
+                .requires("include_proc_dependencies")
+        )
+        .arg(
+            Arg::new("include_indexes")
+                .long("include-indexes")
+                .help("Lists each table's indexes, with descending key columns marked DESC, included columns as INCLUDE (...) and the WHERE predicate of filtered indexes")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("include_table_types")
+                .long("include-table-types")
+                .help("Draws user-defined table types as <<table type>> classes with their columns")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("include_row_counts")
+                .long("include-row-counts")
+                .help("Marks each table with its row count from partition statistics as <<rows: N>>")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("include_sizes")
+                .long("include-sizes")
+                .help("Marks each table with the space it and its indexes reserve as <<size: N MB>> (needs VIEW DATABASE STATE)")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("cardinality_hints")
+                .long("cardinality-hints")
+                .help("Appends the approximate child-to-parent row ratio (e.g. ~100:1) to relationship labels")
+                .action(ArgAction::SetTrue)
+                .requires("include_row_counts")
+        )
+        .arg(
+            Arg::new("detect_inheritance")
+                .long("detect-inheritance")
+                .help("Draws foreign keys whose columns are the child's whole primary key as generalizations (<|--)")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("highlight_collations")
+                .long("highlight-collations")
+                .help("Annotates columns whose collation differs from the database default with {collate <name>}")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("pii_patterns")
+                .long("pii-patterns")
+                .value_name("PATTERNS")
+                .help("Marks columns whose names match these comma-separated globs as <<PII>> and tallies them; without a list, uses a built-in one of names such as *_ssn, email and *_dob")
+                .num_args(0..=1)
+                .default_missing_value(DEFAULT_PII_PATTERNS)
+        )
+        .arg(
+            Arg::new("since")
+                .long("since")
+                .value_name("TIMESTAMP")
+                .help("Marks tables whose definition changed after this server-time ISO date (YYYY-MM-DD[THH:MM:SS]) with <<modified>>")
+                .value_parser(parse_since)
+        )
+        .arg(
+            Arg::new("include_usage")
+                .long("include-usage")
+                .help("Shows when each table was last used according to index usage statistics (needs VIEW SERVER STATE), marking tables without statistics since the last restart with <<no usage stats>>")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("cold_since")
+                .long("cold-since")
+                .value_name("TIMESTAMP")
+                .help("Marks tables last used before this server-time ISO date (YYYY-MM-DD[THH:MM:SS]) with <<cold>>")
+                .value_parser(parse_since)
+                .requires("include_usage")
+        )
+        .arg(
+            Arg::new("key_spots")
+                .long("key-spots")
+                .help("Tags primary key, foreign key and referenced unique key columns with PK/FK/U stereotype spots")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("pfk_marker")
+                .long("pfk-marker")
+                .help("Marks columns in both the primary key and a foreign key, as in junction tables, with a single PFK spot instead of separate PK and FK spots")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("group_composite_keys")
+                .long("group-composite-keys")
+                .help("Groups the columns of multi-column primary keys and unique indexes in PlantUML with a shared <<PK1>> or <<UK1>> tag and a note such as composite key (OrderId, LineNo)")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("attribute_icons")
+                .long("attribute-icons")
+                .help("Prefixes PlantUML members with an icon for their role: 🔑 primary key, 🔗 foreign key and ⚙ computed columns, leaving plain columns unadorned")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("attribute_icon_set")
+                .long("attribute-icon-set")
+                .value_name("LIST")
+                .help("Overrides --attribute-icons icons with role=icon pairs, such as pk=PK,fk=FK; the roles are pk, fk and computed")
+                .value_parser(|list: &str| AttributeIcons::parse(list))
+                .requires("attribute_icons")
+        )
+        .arg(
+            Arg::new("highlight_no_pk")
+                .long("highlight-no-pk")
+                .help("Marks tables without a primary key with a <<no-pk>> stereotype")
+                .action(ArgAction::SetTrue)
+        )
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let matches = match command().try_get_matches() {
+        Ok(matches) => matches,
+        Err(err) => {
+            let _ = err.print();
+            return if err.use_stderr() {
+                ExitCode::from(EXIT_BAD_ARGUMENTS)
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+    };
+
+    match run(&matches).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            let format = ErrorFormat::parse(matches.get_one::<String>("error_format").unwrap()).unwrap_or_default();
+            eprintln!("{}", format.report(&err));
+            ExitCode::from(err.exit_code())
+        }
+    }
+}
+
+async fn run(matches: &ArgMatches) -> Result<(), DiagramError> {
+    session_options(matches).check_supported()?;
+    let interval = match matches.get_one::<u64>("watch") {
+        Some(seconds) => Duration::from_secs(*seconds),
+        None => {
+            // The first Ctrl-C stops extraction between tables; a second one
+            // exits immediately
+            let cancel = Arc::new(AtomicBool::new(false));
+            let signalled = Arc::clone(&cancel);
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    signalled.store(true, Ordering::Relaxed);
+                }
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    std::process::exit(i32::from(DiagramError::Cancelled.exit_code()));
+                }
+            });
+            if matches.get_flag("doctor") {
+                return doctor(matches, &Target::from_matches(matches)?).await;
+            }
+            if matches.get_flag("count_only") {
+                return count_only(matches, &Target::from_matches(matches)?).await;
+            }
+            if let Some(path) = matches.get_one::<String>("batch") {
+                return run_batch(matches, path, cancel).await;
+            }
+            let output_dir = matches.get_one::<String>("output_dir").map(Path::new);
+            let target = Target::from_matches(matches)?;
+            return generate(matches, &target, output_dir, Some(cancel), None, None).await.map(|_| ());
+        }
+    };
+
+    // Regenerate on every interval until interrupted, reporting whether the
+    // schema changed since the previous pass
+    let target = Target::from_matches(matches)?;
+    let output_dir = matches.get_one::<String>("output_dir").map(Path::new);
+    let shutdown = tokio::signal::ctrl_c();
+    tokio::pin!(shutdown);
+    let mut previous = None;
+    let mut written = WrittenHashes::new();
+    loop {
+        let result = tokio::select! {
+            result = generate(matches, &target, output_dir, None, Some(&mut written), None) => result,
+            _ = &mut shutdown => break,
+        };
+        match (result, previous) {
+            (Ok(fingerprint), Some(last)) if fingerprint == last => println!("Schema unchanged"),
+            (Ok(fingerprint), Some(_)) => {
+                println!("Schema changed");
+                previous = Some(fingerprint);
+            }
+            (Ok(fingerprint), None) => previous = Some(fingerprint),
+            // Later passes keep watching through transient failures
+            (Err(err), Some(_)) => eprintln!("error: {}", err),
+            (Err(err), None) => return Err(err),
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = &mut shutdown => break,
+        }
+    }
+    Ok(())
+}
+
+/// Runs the `--doctor` checks against every catalog of `target`, printing a
+/// line per check.
+async fn doctor(matches: &ArgMatches, target: &Target) -> Result<(), DiagramError> {
+    let (host, port) = server_address(matches, &target.ip_address).await?;
+    let timeout = matches.get_one::<u64>("query_timeout").map(|seconds| Duration::from_secs(*seconds));
+    let catalogs: Vec<&str> = target
+        .initial_catalog
+        .split(',')
+        .map(str::trim)
+        .filter(|catalog| !catalog.is_empty())
+        .collect();
+    let mut diagnostics = Vec::new();
+    for catalog in &catalogs {
+        if catalogs.len() > 1 {
+            println!("{}:", catalog);
+        }
+        let connection_string = connection_string(&host, port, &target.username, &target.password, catalog);
+        for diagnostic in diagnose(&connection_string, timeout).await {
+            println!("{}", diagnostic.line());
+            diagnostics.push(diagnostic);
+        }
+    }
+    match diagnostics.iter().filter(|diagnostic| !diagnostic.passed()).count() {
+        0 => Ok(()),
+        failed => Err(DiagramError::DoctorFailed(failed, diagnostics.len())),
+    }
+}
 
-    let ip_address = matches.get_one::<String>("ip_address").unwrap();
-    let username = matches.get_one::<String>("username").unwrap();
-    let password = matches.get_one::<String>("password").unwrap();
-    let initial_catalog = matches.get_one::<String>("initial_catalog").unwrap();
+/// Prints the `--count-only` tallies of every catalog of `target`, as a
+/// line or a JSON object per catalog, without extracting the schema.
+async fn count_only(matches: &ArgMatches, target: &Target) -> Result<(), DiagramError> {
+    let (host, port) = server_address(matches, &target.ip_address).await?;
+    let timeout = matches.get_one::<u64>("query_timeout").map(|seconds| Duration::from_secs(*seconds));
+    let json = matches.get_one::<String>("count_format").is_some_and(|format| format == "json");
+    let catalogs: Vec<&str> = target
+        .initial_catalog
+        .split(',')
+        .map(str::trim)
+        .filter(|catalog| !catalog.is_empty())
+        .collect();
+    for catalog in &catalogs {
+        let connection_string = connection_string(&host, port, &target.username, &target.password, catalog);
+        let pool = connect(&connection_string, false).await?;
+        let counts = extract::catalog_counts(&pool, timeout).await?;
+        if json {
+            println!("{}", counts.to_json(catalog));
+        } else if catalogs.len() > 1 {
+            println!("{}: {}", catalog, counts.line());
+        } else {
+            println!("{}", counts.line());
+        }
+    }
+    Ok(())
+}
+
+/// Options whose values are never written into `--embed-provenance` notes.
+const SECRET_ARGS: &[&str] = &["password"];
+
+/// The `--embed-provenance` note: the tool version, then each option given
+/// on the command line in declaration order, with secrets redacted.
+fn provenance(matches: &ArgMatches) -> Vec<String> {
+    let command = command();
+    let mut lines = vec![format!(
+        "Generated by {} {}",
+        command.get_name(),
+        command.get_version().unwrap_or_default()
+    )];
+    for arg in command.get_arguments() {
+        let id = arg.get_id().as_str();
+        if matches.value_source(id) != Some(ValueSource::CommandLine) {
+            continue;
+        }
+        let flag = format!("--{}", arg.get_long().unwrap_or(id));
+        if !arg.get_action().takes_values() {
+            lines.push(flag);
+        } else if SECRET_ARGS.contains(&id) {
+            lines.push(format!("{} <redacted>", flag));
+        } else {
+            let values: Vec<String> = matches
+                .get_raw(id)
+                .into_iter()
+                .flatten()
+                .map(|value| value.to_string_lossy().into_owned())
+                .collect();
+            lines.push(format!("{} {}", flag, values.join(",")));
+        }
+    }
+    lines
+}
+
+/// The host and port `--ip_address` and `--port` resolve to.
+async fn server_address(matches: &ArgMatches, host: &str) -> Result<(String, u16), DiagramError> {
+    resolve_endpoint(&server_endpoint(matches, host)?).await
+}
+
+/// The endpoint `--ip_address` and `--port` name, before any instance port is
+/// looked up, warning unless quiet when `--port` overrides an instance name.
+fn server_endpoint(matches: &ArgMatches, host: &str) -> Result<Endpoint, DiagramError> {
+    check_tcp_host(host)?;
+    let (endpoint, warning) = parse_endpoint(host, matches.get_one::<u16>("port").copied())?;
+    if let Some(warning) = warning.filter(|_| !matches.get_flag("quiet")) {
+        eprintln!("warning: {}", warning);
+    }
+    Ok(endpoint)
+}
+
+/// The password given on the command line: `--password`, with `${VAR}`
+/// placeholders expanded, or with `--password-stdin` the first line of
+/// standard input, trimmed and taken as it is.
+fn command_line_password(matches: &ArgMatches) -> Result<Option<String>, DiagramError> {
+    if !matches.get_flag("password_stdin") {
+        return matches.get_one::<String>("password").map(|password| expand_env(password, "password")).transpose();
+    }
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    match line.trim() {
+        "" => Err(DiagramError::InvalidArgument(
+            "--password-stdin read no password from standard input".to_string(),
+        )),
+        password => Ok(Some(password.to_string())),
+    }
+}
+
+/// The session settings given by `--read-only`, `--application-name` and
+/// `--encrypt`.
+fn session_options(matches: &ArgMatches) -> SessionOptions<'_> {
+    SessionOptions {
+        read_only: matches.get_flag("read_only"),
+        application_name: matches.get_one::<String>("application_name").map(String::as_str),
+        encrypt: matches.get_one::<String>("encrypt").and_then(|encrypt| Encrypt::parse(encrypt)),
+    }
+}
+
+/// The server, login and catalogs that one `generate` run diagrams.
+struct Target {
+    ip_address: String,
+    username: String,
+    password: String,
+    initial_catalog: String,
+}
+
+impl Target {
+    /// The target given by the connection flags, with `${VAR}` placeholders
+    /// expanded from the environment.
+    fn from_matches(matches: &ArgMatches) -> Result<Target, DiagramError> {
+        let arg = |name: &str| expand_env(matches.get_one::<String>(name).map_or("", String::as_str), name);
+        Ok(Target {
+            ip_address: arg("ip_address")?,
+            username: arg("username")?,
+            password: command_line_password(matches)?.unwrap_or_default(),
+            initial_catalog: arg("initial_catalog")?,
+        })
+    }
+
+    /// The target of a `--batch` entry, taking the connection settings it
+    /// leaves out from the command line, where the password was read once as
+    /// `password`.
+    fn for_entry(matches: &ArgMatches, entry: &BatchEntry, password: Option<&str>) -> Result<Target, DiagramError> {
+        let setting = |value: &Option<String>, name: &str| {
+            value
+                .clone()
+                .or_else(|| matches.get_one::<String>(name).cloned())
+                .ok_or_else(|| {
+                    DiagramError::InvalidArgument(format!(
+                        "batch entry for {} has no {} and none was given with --{}",
+                        entry.initial_catalog, name, name
+                    ))
+                })
+                .and_then(|value| expand_env(&value, name))
+        };
+        Ok(Target {
+            ip_address: setting(&entry.ip_address, "ip_address")?,
+            username: setting(&entry.username, "username")?,
+            password: match (&entry.password, password) {
+                (None, Some(password)) => password.to_string(),
+                _ => setting(&entry.password, "password")?,
+            },
+            initial_catalog: expand_env(&entry.initial_catalog, "initial_catalog")?,
+        })
+    }
+}
+
+/// Diagrams every database of the `--batch` file into its own directory
+/// under `--output-dir`, `--batch-concurrency` at a time, carrying on past
+/// failures and finishing with a tally.
+async fn run_batch(matches: &ArgMatches, path: &str, cancel: Arc<AtomicBool>) -> Result<(), DiagramError> {
+    let entries = parse_batch(&std::fs::read_to_string(path)?)?;
+    let default_server = matches.get_one::<String>("ip_address").map(String::as_str).unwrap_or_default();
+    let names = directory_names(&entries, default_server);
+    let output_dir = Path::new(matches.get_one::<String>("output_dir").map(String::as_str).unwrap_or("."));
+    let concurrency = *matches.get_one::<u64>("batch_concurrency").unwrap() as usize;
+    let password = command_line_password(matches)?;
+    let password = password.as_deref();
+    let combined = matches.get_one::<String>("combined");
 
-    // Configure the connection with a timeout
-    let connection_string = format!(
-        "mssql://{}:{}@{}:1433/{}?trustservercertificate=true&connect_timeout=30",
-        username, password, ip_address, initial_catalog
+    let mut results: Vec<_> = stream::iter(entries.iter().zip(&names))
+        .map(|(entry, name)| {
+            let cancel = Arc::clone(&cancel);
+            async move {
+                let directory = output_dir.join(name);
+                let mut catalogs = Vec::new();
+                let mut collected = None;
+                let result = async {
+                    let target = Target::for_entry(matches, entry, password)?;
+                    catalogs =
+                        target.initial_catalog.split(',').map(|catalog| catalog.trim().to_string()).collect();
+                    std::fs::create_dir_all(&directory)?;
+                    let collected = combined.is_some().then_some(&mut collected);
+                    generate(matches, &target, Some(&directory), Some(cancel), None, collected).await
+                }
+                .await;
+                if let Err(err) = &result {
+                    eprintln!("error: {}: {}", name, err);
+                    // Only removes the directory if the run left it empty
+                    let _ = std::fs::remove_dir(&directory);
+                }
+                (name, result, catalogs, collected)
+            }
+        })
+        .buffered(concurrency)
+        .collect()
+        .await;
+
+    if let Some(path) = combined {
+        let diagrammed = results
+            .iter_mut()
+            .filter_map(|(name, _, catalogs, collected)| {
+                Some((name.to_string(), std::mem::take(catalogs), collected.take()?))
+            })
+            .collect();
+        write_combined(matches, path, diagrammed)?;
+    }
+
+    let failed: Vec<&str> = results
+        .iter()
+        .filter(|(_, result, _, _)| result.is_err())
+        .map(|(name, _, _, _)| name.as_str())
+        .collect();
+    if failed.is_empty() {
+        println!("Batch finished: {} succeeded, 0 failed", results.len());
+        return Ok(());
+    }
+    println!(
+        "Batch finished: {} succeeded, {} failed ({})",
+        results.len() - failed.len(),
+        failed.len(),
+        failed.join(", ")
     );
-    let pool = MssqlPool::connect(&connection_string).await?;
+    if cancel.load(Ordering::Relaxed) {
+        return Err(DiagramError::Cancelled);
+    }
+    Err(DiagramError::BatchFailed(failed.len(), results.len()))
+}
+
+/// Places a generated file in `--output-dir`, when one is given.
+fn output_path(output_dir: Option<&Path>, name: &str) -> PathBuf {
+    match output_dir {
+        Some(dir) => dir.join(name),
+        None => PathBuf::from(name),
+    }
+}
+
+/// Validates a `--since` date, widening `YYYY-MM-DD` to midnight so it
+/// compares as a string against `Table::modified_at`.
+fn parse_since(value: &str) -> Result<String, String> {
+    let timestamp = if value.len() == 10 {
+        format!("{}T00:00:00", value)
+    } else {
+        value.to_string()
+    };
+    let valid = timestamp.len() == 19
+        && timestamp.char_indices().all(|(i, c)| match i {
+            4 | 7 => c == '-',
+            10 => c == 'T',
+            13 | 16 => c == ':',
+            _ => c.is_ascii_digit(),
+        });
+    if valid {
+        Ok(timestamp)
+    } else {
+        Err("expected YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS".to_string())
+    }
+}
+
+/// Prints the structural warnings about `schema` unless `quiet`, returning
+/// how many there were. `--fail-on-warnings` prints them even when quiet,
+/// since they explain the failure.
+fn print_warnings(matches: &ArgMatches, schema: &DatabaseSchema, quiet: bool) -> usize {
+    let warnings = design_warnings(schema);
+    if !quiet || matches.get_flag("fail_on_warnings") {
+        for warning in &warnings {
+            eprintln!("warning: {}", warning);
+        }
+    }
+    warnings.len()
+}
+
+/// Fails under `--fail-on-warnings` if any warnings were printed.
+fn check_warnings(matches: &ArgMatches, warnings: usize) -> Result<(), DiagramError> {
+    if matches.get_flag("fail_on_warnings") && warnings > 0 {
+        return Err(DiagramError::WarningsFound(warnings));
+    }
+    Ok(())
+}
+
+/// Lists each cluster of tables linked by foreign keys, followed by the
+/// tables that are linked to nothing else.
+fn components_report(schema: &DatabaseSchema) -> Vec<String> {
+    let qualified = |table: &&Table| format!("{}.{}", table.schema, table.name);
+    let (clusters, isolated): (Vec<_>, Vec<_>) = connected_components(schema)
+        .into_iter()
+        .partition(|component| component.len() > 1);
+    let mut lines = Vec::new();
+    for (i, cluster) in clusters.iter().enumerate() {
+        let members: Vec<String> = cluster.iter().map(qualified).collect();
+        lines.push(format!("Component {} ({} tables): {}", i + 1, cluster.len(), members.join(", ")));
+    }
+    let isolated: Vec<String> = isolated.iter().flatten().map(qualified).collect();
+    if isolated.is_empty() {
+        lines.push("Isolated tables: 0".to_string());
+    } else {
+        lines.push(format!("Isolated tables: {} ({})", isolated.len(), isolated.join(", ")));
+    }
+    lines
+}
+
+/// Streams the PlantUML classes of the tables `filter` keeps to `path` as they
+/// are read, then writes the relationships, returning the diagrammed schema.
+async fn stream_plantuml(
+    pool: &MssqlPool,
+    extract_options: &ExtractOptions,
+    filter: &TableFilter,
+    render_options: &RenderOptions,
+    path: &Path,
+) -> Result<DatabaseSchema, DiagramError> {
+    extract::health_check(pool, extract_options).await?;
+    let mut references = extract::get_references(pool, extract_options).await?;
+    // Classes are drawn before the filter sees the whole schema, so drop the
+    // references whose source table it would remove
+    references.retain(|reference| filter.matches(&Table::external_stub(&reference.schema, &reference.table)));
+
+    let mut file = AtomicFile::create(path)?;
+    file.write_all(header_banner(&PlantUmlFormat, render_options).as_bytes())?;
+    let mut stream = PlantUmlStream::start(file, references.clone(), render_options)?;
+    let mut tables = Vec::new();
+    let mut fetched = pin!(extract::stream_tables(pool, extract_options));
+    while let Some(table) = fetched.try_next().await? {
+        if filter.matches(&table) {
+            stream.write_table(&table)?;
+            tables.push(table);
+        }
+    }
+    let schema = filter.apply(DatabaseSchema { tables, references });
+    stream.finish(&schema)?.commit()?;
+    Ok(schema)
+}
 
-    // Get the database schema
-    let tables = get_tables(&pool).await?;
-    let references = get_references(&pool).await?;
-    let schema = DatabaseSchema { tables, references };
+/// Writes one generated file: the `--header-comment` banner, then the
+/// output, transcoded in full when `--output-encoding` isn't UTF-8.
+fn write_output(
+    writer: &mut dyn Write,
+    format: &dyn OutputFormat,
+    schema: &DatabaseSchema,
+    options: &RenderOptions,
+    encoding: OutputEncoding,
+) -> Result<(), DiagramError> {
+    let banner = header_banner(format, options);
+    if encoding == OutputEncoding::Utf8 {
+        writer.write_all(banner.as_bytes())?;
+        return format.write(schema, writer, options);
+    }
+    writer.write_all(&encoding.encode(&(banner + &format.render(schema, options)?))?)?;
+    Ok(())
+}
 
-    // Generate PlantUML script
-    let plantuml_script = generate_plantuml(&schema);
+/// The schema `--from-sql` reads from the DDL `script`, with the parser's
+/// warnings. Like extraction, it leaves out the foreign keys the script
+/// disables unless `include_disabled_fks`.
+fn ddl_schema(script: &str, include_disabled_fks: bool) -> (DatabaseSchema, Vec<String>) {
+    let (mut schema, warnings) = parse_ddl(script);
+    if !include_disabled_fks {
+        schema.references.retain(|reference| !reference.is_disabled);
+    }
+    (schema, warnings)
+}
 
-    // Save PlantUML script to a file
-    let mut file = File::create("schema.puml")?;
-    file.write_all(plantuml_script.as_bytes())?;
+/// The number of tables diagrammed, leaving out external stubs and table
+/// types, when it exceeds `--auto-overview-threshold` and the overview should
+/// be rendered instead of the detailed diagram.
+fn over_overview_threshold(schema: &DatabaseSchema, threshold: Option<usize>) -> Option<usize> {
+    let table_count = schema
+        .tables
+        .iter()
+        .filter(|table| !table.external && !table.table_type)
+        .count();
+    threshold.filter(|&threshold| table_count > threshold).map(|_| table_count)
+}
 
-    println!("PlantUML script generated and saved to schema.puml");
+/// Hashes of the files a `--watch` run last wrote, keyed by path.
+type WrittenHashes = HashMap<PathBuf, u64>;
 
+/// Records `contents` as written to `path`, returning whether the last
+/// `--watch` cycle already wrote exactly them there, so the file can be left
+/// alone.
+fn unchanged_since_written(written: &mut WrittenHashes, path: &Path, contents: &[u8]) -> bool {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    let hash = hasher.finish();
+    written.insert(path.to_path_buf(), hash) == Some(hash)
+}
+
+/// The schema a `--batch` entry diagrammed and the options it was rendered
+/// with, kept for `--combined`.
+type Collected = (DatabaseSchema, RenderOptions);
+
+/// Draws the databases of a `--batch` run, named with their output
+/// directory names, into one PlantUML file with a package per database and
+/// the `--cross-db-references` between them drawn across packages.
+fn write_combined(
+    matches: &ArgMatches,
+    path: &str,
+    databases: Vec<(String, Vec<String>, Collected)>,
+) -> Result<(), DiagramError> {
+    let Some(options) = databases.first().map(|(_, _, (_, options))| options.clone()) else {
+        return Ok(());
+    };
+    let references = match matches.get_one::<String>("cross_db_references") {
+        Some(path) => parse_cross_references(&std::fs::read_to_string(path)?)?,
+        None => Vec::new(),
+    };
+    let databases = databases.into_iter().map(|(name, catalogs, (schema, _))| (name, catalogs, schema)).collect();
+    let schema = combine_databases(databases, references);
+    let options = RenderOptions {
+        group_by_property: Some(DATABASE_PROPERTY.to_string()),
+        database_packages: true,
+        // A note per database server doesn't fit one combined diagram
+        server_info: None,
+        ..options
+    };
+    let encoding = OutputEncoding::parse(matches.get_one::<String>("output_encoding").unwrap()).unwrap_or_default();
+    let mut contents = Vec::new();
+    write_output(&mut contents, &PlantUmlFormat, &schema, &options, encoding)?;
+    write_atomically(path, contents)?;
+    println!("Combined output generated and saved to {}", path);
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Extracts, renders and writes the diagram once, returning the fingerprint
+/// of the diagrammed schema. With `written`, files whose contents match
+/// the hash recorded on the previous pass are left untouched. With
+/// `collected`, the schema and options are handed back for `--combined`,
+/// and the cross-database references are left to the combined file.
+async fn generate(
+    matches: &ArgMatches,
+    target: &Target,
+    output_dir: Option<&Path>,
+    cancel: Option<Arc<AtomicBool>>,
+    mut written: Option<&mut WrittenHashes>,
+    collected: Option<&mut Option<Collected>>,
+) -> Result<u64, DiagramError> {
+    let (ip_address, port) = server_address(matches, &target.ip_address).await?;
+    let username = &target.username;
+    let password = &target.password;
+    let initial_catalog = &target.initial_catalog;
+    let formats: Vec<_> = matches
+        .get_many::<String>("format")
+        .unwrap()
+        .filter_map(|name| lookup_format(name))
+        .collect();
+    let also_render = matches.get_one::<String>("also_render").and_then(|format| ImageFormat::parse(format));
+    let plantuml_server = matches.get_one::<String>("plantuml_server").unwrap();
+    if also_render.is_some() {
+        ServerUrl::parse(plantuml_server)?;
+        if !formats.iter().any(|format| format.name() == "plantuml") && !matches.get_flag("overview") {
+            return Err(DiagramError::InvalidArgument(
+                "--also-render renders PlantUML files, but no plantuml output was requested".to_string(),
+            ));
+        }
+    }
+    let quiet = matches.get_flag("quiet");
+    let server_info_note = matches.get_flag("server_info_note");
+    let mut render_options = RenderOptions {
+        relationships_only: matches.get_flag("relationships_only"),
+        columns_match: matches
+            .get_one::<String>("columns_match")
+            .map(|patterns| {
+                patterns
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|pattern| !pattern.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        hide_columns: matches
+            .get_one::<String>("hide_columns")
+            .map(|patterns| {
+                patterns
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|pattern| !pattern.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        max_columns_per_table: matches.get_one::<usize>("max_columns_per_table").copied(),
+        first_columns: matches.get_one::<usize>("first_columns").copied(),
+        max_table_name_length: matches.get_one::<u64>("max_table_name_length").map(|&length| length as usize),
+        wrap_columns: matches.get_one::<u64>("wrap_columns").map(|&groups| groups as usize),
+        no_columns_for: matches
+            .get_one::<String>("no_columns_for")
+            .map(|patterns| parse_patterns(patterns))
+            .unwrap_or_default(),
+        display_names: match matches.get_one::<String>("rename_map") {
+            Some(path) => parse_rename_map(&std::fs::read_to_string(path)?)?,
+            None => Vec::new(),
+        },
+        case_sensitive: matches.get_flag("case_sensitive"),
+        respect_column_hints: matches.get_flag("respect_column_hints"),
+        compact: matches.get_flag("compact"),
+        columns_summary: matches.get_flag("columns_summary"),
+        hide_types: !matches.get_one::<bool>("show_types").unwrap(),
+        highlight_no_pk: matches.get_flag("highlight_no_pk"),
+        highlight_cycles: matches.get_flag("highlight_cycles"),
+        purpose_legend: matches.get_flag("purpose_legend"),
+        type_legend: matches.get_flag("type_legend"),
+        server_info: None,
+        provenance: if matches.get_flag("embed_provenance") { provenance(matches) } else { Vec::new() },
+        svg_tooltips: matches.get_flag("svg_tooltips"),
+        show_filegroups: matches.get_flag("include_filegroups"),
+        hide_primary_filegroup: matches.get_flag("hide_primary_filegroup"),
+        show_triggers: matches.get_flag("include_triggers"),
+        history_links: matches.get_flag("history_links"),
+        proc_dependencies: matches
+            .get_flag("include_proc_dependencies")
+            .then(|| *matches.get_one::<u64>("max_procs").unwrap() as usize),
+        resolve_indirection: matches.get_flag("resolve_indirection"),
+        show_indexes: matches.get_flag("include_indexes"),
+        show_row_counts: matches.get_flag("include_row_counts"),
+        show_sizes: matches.get_flag("include_sizes"),
+        cardinality_hints: matches.get_flag("cardinality_hints"),
+        detect_inheritance: matches.get_flag("detect_inheritance"),
+        highlight_collations: matches.get_flag("highlight_collations"),
+        pii_patterns: matches
+            .get_one::<String>("pii_patterns")
+            .map(|patterns| {
+                patterns
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|pattern| !pattern.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        modified_since: matches.get_one::<String>("since").cloned(),
+        show_usage: matches.get_flag("include_usage"),
+        cold_since: matches.get_one::<String>("cold_since").cloned(),
+        highlight_type_mismatches: matches.get_flag("highlight_type_mismatches"),
+        key_spots: matches.get_flag("key_spots"),
+        pfk_marker: matches.get_flag("pfk_marker"),
+        group_composite_keys: matches.get_flag("group_composite_keys"),
+        attribute_icons: matches.get_flag("attribute_icons").then(|| {
+            matches.get_one::<AttributeIcons>("attribute_icon_set").cloned().unwrap_or_default()
+        }),
+        quote_style: QuoteStyle::parse(matches.get_one::<String>("quote_style").unwrap()).unwrap_or_default(),
+        arrow: ArrowStyle::parse(matches.get_one::<String>("arrow").unwrap()).unwrap_or_default(),
+        reference_style: ReferenceStyle::parse(matches.get_one::<String>("reference_style").unwrap()).unwrap_or_default(),
+        max_edges_per_table: matches.get_one::<u64>("max_edges_per_table").map(|&max| max as usize),
+        color_by: ColorBy::parse(matches.get_one::<String>("color_by").unwrap()).unwrap_or_default(),
+        seed: matches.get_one::<u64>("seed").copied(),
+        merge_parallel_edges: matches.get_flag("merge_parallel_edges"),
+        verbose_labels: matches.get_flag("verbose_labels"),
+        ef_hints: matches.get_flag("ef_hints"),
+        fk_cardinality: matches.get_flag("fk_cardinality"),
+        layout: Layout::parse(matches.get_one::<String>("layout").unwrap()).unwrap_or_default(),
+        pivot: matches.get_one::<String>("pivot_on").map(|table| NamePattern::parse(table)),
+        together: matches.get_flag("together"),
+        cluster_layout: matches.get_flag("cluster_layout"),
+        layered: matches.get_flag("layered"),
+        group_by_property: matches.get_one::<String>("group_by_property").cloned(),
+        database_packages: false,
+        text_width: std::env::var("COLUMNS").ok().and_then(|columns| columns.parse().ok()),
+        header_comment: matches
+            .get_many::<String>("header_comment")
+            .map(|lines| lines.cloned().collect())
+            .unwrap_or_default(),
+        json_fields: matches.get_one::<Vec<String>>("json_fields").cloned().unwrap_or_default(),
+        indent: matches.get_one::<u64>("indent").map(|&width| width as usize),
+        compact_output: matches.get_flag("compact_output"),
+    };
+    let table_filter = TableFilter::new(
+        matches.get_one::<String>("include").map(String::as_str),
+        matches.get_one::<String>("exclude").map(String::as_str),
+        matches.get_flag("case_sensitive"),
+        matches.get_flag("external_stubs"),
+    );
+    let extract_options = ExtractOptions {
+        query_timeout: matches.get_one::<u64>("query_timeout").map(|seconds| Duration::from_secs(*seconds)),
+        skip_errors: matches.get_flag("skip_errors"),
+        quiet,
+        descriptions: render_options.purpose_legend || render_options.svg_tooltips,
+        include_disabled_fks: matches.get_flag("include_disabled_fks"),
+        properties: render_options
+            .group_by_property
+            .iter()
+            .cloned()
+            .chain(render_options.respect_column_hints.then(|| COLUMN_HINTS_PROPERTY.to_string()))
+            .collect(),
+        legacy_bindings: matches.get_flag("include_legacy_bindings"),
+        fulltext: matches.get_flag("include_fulltext"),
+        filegroups: render_options.show_filegroups,
+        triggers: render_options.show_triggers,
+        history_tables: render_options.history_links,
+        proc_dependencies: render_options.proc_dependencies.is_some(),
+        indirection: render_options.resolve_indirection,
+        indexes: render_options.show_indexes
+            || render_options.columns_summary
+            || render_options.fk_cardinality
+            || render_options.group_composite_keys,
+        row_counts: render_options.show_row_counts,
+        sizes: render_options.show_sizes,
+        table_types: matches.get_flag("include_table_types"),
+        modify_dates: render_options.modified_since.is_some(),
+        usage: render_options.show_usage,
+        topology_only: matches.get_flag("topology_only"),
+        sample_rows: matches.get_one::<u32>("sample_data").copied(),
+        annotation_query: matches.get_one::<String>("annotation_query").cloned(),
+        consistent_snapshot: matches.get_flag("consistent_snapshot"),
+        cancel,
+        progress: None,
+    };
+
+    let catalogs: Vec<&str> = initial_catalog
+        .split(',')
+        .map(str::trim)
+        .filter(|catalog| !catalog.is_empty())
+        .collect();
+    let mut cross_references = match matches.get_one::<String>("cross_db_references").filter(|_| collected.is_none()) {
+        Some(path) => parse_cross_references(&std::fs::read_to_string(path)?)?,
+        None => Vec::new(),
+    };
+
+    if matches.get_flag("stream") {
+        let [catalog] = catalogs[..] else {
+            return Err(DiagramError::InvalidArgument("--stream diagrams a single catalog".to_string()));
+        };
+        if formats.iter().any(|format| format.name() != "plantuml") {
+            return Err(DiagramError::InvalidArgument("--stream only writes the plantuml format".to_string()));
+        }
+        let connection_string = connection_string(&ip_address, port, username, password, catalog);
+        let pool = connect(&connection_string, false).await?;
+        if server_info_note {
+            render_options.server_info = Some(extract::server_info(&pool, &extract_options).await?);
+        }
+        let path = output_path(output_dir, STREAM_FILE);
+        let schema = stream_plantuml(&pool, &extract_options, &table_filter, &render_options, &path).await?;
+        println!("Output generated and saved to {}", path.display());
+        if schema.tables.is_empty() && matches.get_flag("fail_on_empty") {
+            return Err(DiagramError::EmptySchema);
+        }
+        check_warnings(matches, print_warnings(matches, &schema, quiet))?;
+        return Ok(schema.fingerprint());
+    }
+
+    // Get the database schema of each catalog
+    let write_manifest = matches.get_flag("manifest");
+    let mut object_counts = ObjectCounts::default();
+    let mut extracted = Vec::new();
+    let mut synonyms = Vec::new();
+    if let Some(path) = matches.get_one::<String>("from_sql") {
+        let (schema, warnings) = ddl_schema(&std::fs::read_to_string(path)?, extract_options.include_disabled_fks);
+        if !quiet {
+            for warning in warnings {
+                eprintln!("warning: {}", warning);
+            }
+        }
+        let name = Path::new(path).file_stem().map_or(path.clone(), |stem| stem.to_string_lossy().into_owned());
+        extracted.push((name, table_filter.apply(schema)));
+    }
+    for catalog in &catalogs {
+        // Configure the connection with a timeout
+        let connection_string = connection_string(&ip_address, port, username, password, catalog);
+        let pool = connect(&connection_string, extract_options.consistent_snapshot).await?;
+        let schema = table_filter.apply(extract_schema(&pool, &extract_options).await?);
+        if write_manifest {
+            let counts = extract::object_counts(&pool, &extract_options).await?;
+            object_counts.views += counts.views;
+            object_counts.indexes += counts.indexes;
+        }
+        if server_info_note {
+            let info = extract::server_info(&pool, &extract_options).await?;
+            match &mut render_options.server_info {
+                Some(existing) => existing.databases.extend(info.databases),
+                None => render_options.server_info = Some(info),
+            }
+        }
+        if matches.get_flag("include_synonyms") {
+            for (schema, name, base_object) in extract::get_synonyms(&pool, &extract_options).await? {
+                synonyms.push(Synonym {
+                    catalog: catalog.to_string(),
+                    schema,
+                    name,
+                    base_object,
+                });
+            }
+        }
+        extracted.push((catalog.to_string(), schema));
+    }
+    let databases: Vec<String> = extracted.iter().map(|(database, _)| database.clone()).collect();
+    resolve_synonyms(&mut cross_references, &synonyms);
+    let mut schema = if extracted.len() == 1 && cross_references.is_empty() {
+        extracted.remove(0).1
+    } else {
+        let mut schema = merge_catalogs(extracted);
+        schema.references.extend(cross_references);
+        schema
+    };
+    if matches.get_flag("validate") {
+        if let Err(issues) = validate(&schema) {
+            for issue in &issues {
+                eprintln!("error: {}", issue);
+            }
+            return Err(DiagramError::InvalidSchema(issues.len()));
+        }
+    }
+    if let Some(path) = matches.get_one::<String>("tables_file") {
+        let list = parse_table_list(&std::fs::read_to_string(path)?)
+            .map_err(|reason| DiagramError::InvalidArgument(format!("invalid tables file {}: {}", path, reason)))?;
+        let (listed, missing) = in_listed_order(schema, &list, matches.get_flag("case_sensitive"));
+        schema = listed;
+        if !missing.is_empty() && !quiet {
+            eprintln!("warning: --tables-file lists {}, which the database doesn't have", missing.join(", "));
+        }
+    }
+    if let Some(anchors) = matches.get_one::<Vec<FocusTable>>("focus") {
+        let depth = *matches.get_one::<u64>("depth").unwrap() as usize;
+        schema = focus(schema, anchors, depth, matches.get_flag("case_sensitive"));
+    }
+    let depends_on = matches.get_one::<String>("depends_on").map(|tables| parse_patterns(tables));
+    let depended_on_by = matches.get_one::<String>("depended_on_by").map(|tables| parse_patterns(tables));
+    if depends_on.is_some() || depended_on_by.is_some() {
+        schema = reachable(
+            schema,
+            &depends_on.unwrap_or_default(),
+            &depended_on_by.unwrap_or_default(),
+            matches.get_flag("case_sensitive"),
+        );
+    }
+    if let Some(depth) = matches.get_one::<u64>("limit_depth_from_roots") {
+        let roots = Roots::parse(matches.get_one::<String>("roots").unwrap()).unwrap_or_default();
+        schema = from_roots(schema, roots, *depth as usize);
+    }
+    // Last, so tables the filters above cut off from their neighbours go too
+    if matches.get_flag("only_referenced") {
+        schema = drop_isolated(schema);
+    }
+
+    if schema.tables.is_empty() && matches.get_flag("fail_on_empty") {
+        return Err(DiagramError::EmptySchema);
+    }
+
+    let warnings = print_warnings(matches, &schema, quiet);
+    if render_options.layered && !quiet {
+        let (_, broken) = layers(&schema, render_options.seed);
+        if !broken.is_empty() {
+            let mut constraints: Vec<&str> = broken.iter().map(|reference| reference.constraint_name.as_str()).collect();
+            constraints.dedup();
+            eprintln!(
+                "warning: --layered broke circular foreign key chains at {}, which keep their default direction",
+                constraints.join(", ")
+            );
+        }
+    }
+    if let Some(limit) = render_options.proc_dependencies.filter(|_| !quiet) {
+        let procedures = procedure_dependencies(&schema).len();
+        if procedures > limit {
+            eprintln!(
+                "warning: drew {} of {} procedures referencing the diagrammed tables; raise --max-procs to draw more",
+                limit, procedures
+            );
+        }
+    }
+
+    // Swap names for placeholders before anything is written
+    let anonymized = matches.get_flag("anonymize");
+    if anonymized {
+        let (anonymous, map) = anonymize(schema);
+        schema = anonymous;
+        if let Some(path) = matches.get_one::<String>("anonymize_map").filter(|_| !matches.get_flag("check")) {
+            write_atomically(path, map.to_json())?;
+            println!("Anonymization map saved to {}", path);
+        }
+    }
+
+    // Report what changed since the snapshot before writing the diagram
+    if let Some(path) = matches.get_one::<String>("compare_to") {
+        let snapshot = parse_snapshot(&std::fs::read_to_string(path)?)?;
+        let output = DiffOutput::parse(matches.get_one::<String>("diff_output").unwrap()).unwrap_or_default();
+        print!("{}", diff_schemas(&snapshot, &schema).render(output));
+        if matches.get_flag("changed_only") {
+            schema = changed_only(&snapshot, schema);
+        }
+    }
+
+    let fingerprint = schema.fingerprint();
+    if let Some(collected) = collected {
+        *collected = Some((schema.clone(), render_options.clone()));
+    }
+
+    // Print the structural hash instead of drawing the tables
+    if matches.get_flag("fingerprint") {
+        println!("{}", schema.structural_fingerprint());
+        check_warnings(matches, warnings)?;
+        return Ok(fingerprint);
+    }
+
+    // Report the independent clusters of tables instead of drawing them
+    if matches.get_flag("components") {
+        for line in components_report(&schema) {
+            println!("{}", line);
+        }
+        check_warnings(matches, warnings)?;
+        return Ok(fingerprint);
+    }
+
+    // Fall back to the overview when a detailed diagram would be unreadable
+    let threshold = matches.get_one::<usize>("auto_overview_threshold").copied();
+    let threshold = threshold.filter(|_| !matches.get_flag("force_detailed"));
+    let render_options = match over_overview_threshold(&schema, threshold) {
+        Some(table_count) => {
+            if !quiet {
+                eprintln!(
+                    "warning: {} tables exceed --auto-overview-threshold {}; rendering relationships only (use --force-detailed to keep columns)",
+                    table_count,
+                    threshold.unwrap_or_default()
+                );
+            }
+            render_options.overview()
+        }
+        None => render_options,
+    };
+
+    let database = if anonymized { "Database1".to_string() } else { databases.join(",") };
+    let manifest = write_manifest.then(|| Manifest::new(&database, &schema, object_counts).to_json());
+
+    // Tally the columns flagged as personal data, for the closing summary
+    let pii_columns: Vec<usize> = schema
+        .tables
+        .iter()
+        .map(|table| table.columns.iter().filter(|column| render_options.is_pii(&column.name)).count())
+        .filter(|&count| count > 0)
+        .collect();
+
+    // Work out which files to generate
+    let identifier_case = IdentifierCase::parse(matches.get_one::<String>("case").unwrap()).unwrap_or_default();
+    let parts = if matches.get_flag("split_by_schema") {
+        split_by_schema(&schema)
+            .into_iter()
+            .map(|(name, part)| (format!("schema.{}", name), part))
+            .collect()
+    } else if matches.get_flag("split_by_component") {
+        split_by_component(&schema)
+    } else {
+        vec![("schema".to_string(), schema)]
+    };
+    // Model exports keep the extracted names, so only diagrams get a recased copy
+    let recased: Vec<Option<DatabaseSchema>> = parts
+        .iter()
+        .map(|(_, part)| {
+            (identifier_case != IdentifierCase::Preserve).then(|| identifier_case.apply_to_schema(part.clone()))
+        })
+        .collect();
+    let overview_options = render_options.overview();
+    let mut outputs = Vec::new();
+    for ((base, part), recased) in parts.iter().zip(&recased) {
+        let diagram = recased.as_ref().unwrap_or(part);
+        for format in &formats {
+            let part = if format.exports_model() { part } else { diagram };
+            outputs.push((format!("{}.{}", base, format.extension()), *format, part, &render_options));
+        }
+        if matches.get_flag("overview") {
+            outputs.push((format!("{}.overview.puml", base), &PlantUmlFormat, diagram, &overview_options));
+        }
+    }
+
+    // Compare against the existing files, or generate the requested output and
+    // save it to files or a zip archive
+    let encoding = OutputEncoding::parse(matches.get_one::<String>("output_encoding").unwrap()).unwrap_or_default();
+    if matches.get_flag("check") {
+        let mut stale = Vec::new();
+        for (name, format, part, options) in &outputs {
+            let generated = header_banner(*format, options) + &format.render(part, options)?;
+            let path = output_path(output_dir, name);
+            // Compare the bytes that would be written, so a BOM or a legacy
+            // encoding doesn't make an up-to-date file look stale
+            let existing = match std::fs::read(&path) {
+                Ok(existing) => Some(existing),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+                Err(err) => return Err(err.into()),
+            };
+            if existing.as_deref() == Some(encoding.encode(&generated)?.as_slice()) {
+                continue;
+            }
+            let name = path.display().to_string();
+            let existing = existing.map(|bytes| encoding.decode(&bytes));
+            match diff_output(&name, existing.as_deref(), &generated) {
+                Some(diff) => print!("{}", diff),
+                None => println!("{} has the generated text in another encoding", name),
+            }
+            stale.push(name);
+        }
+        if !stale.is_empty() {
+            return Err(DiagramError::OutOfDate(stale));
+        }
+        println!("Output is up to date");
+    } else if let Some(zip_path) = matches.get_one::<String>("zip") {
+        let mut archive = ZipWriter::new(AtomicFile::create(zip_path)?);
+        for (name, format, part, options) in &outputs {
+            archive
+                .start_file(name.as_str(), SimpleFileOptions::default())
+                .map_err(io::Error::from)?;
+            write_output(&mut archive, *format, part, options, encoding)?;
+        }
+        if let Some(manifest) = &manifest {
+            archive
+                .start_file(MANIFEST_FILE, SimpleFileOptions::default())
+                .map_err(io::Error::from)?;
+            archive.write_all(manifest.as_bytes())?;
+        }
+        archive.finish().map_err(io::Error::from)?.commit()?;
+        println!("Output generated and saved to {}", zip_path);
+    } else {
+        for (name, format, part, options) in &outputs {
+            // The text format is meant for a quick look in the terminal
+            if format.name() == "text" {
+                format.write(part, &mut io::stdout().lock(), options)?;
+                continue;
+            }
+            let path = output_path(output_dir, name);
+            let mut contents = Vec::new();
+            write_output(&mut contents, *format, part, options, encoding)?;
+            if written.as_deref_mut().is_some_and(|written| unchanged_since_written(written, &path, &contents)) {
+                println!("No changes to {}", path.display());
+                continue;
+            }
+            write_atomically(&path, contents)?;
+            println!("Output generated and saved to {}", path.display());
+            if let Some(image_format) = also_render.filter(|_| format.name() == "plantuml") {
+                let source = header_banner(*format, options) + &format.render(part, options)?;
+                let image = render_image(plantuml_server, &source, image_format).await?;
+                let image_path = path.with_extension(image_format.extension());
+                write_atomically(&image_path, image)?;
+                println!("Image rendered and saved to {}", image_path.display());
+            }
+        }
+        if let Some(manifest) = &manifest {
+            let path = output_path(output_dir, MANIFEST_FILE);
+            write_atomically(&path, manifest)?;
+            println!("Manifest saved to {}", path.display());
+        }
+    }
+    if !render_options.pii_patterns.is_empty() {
+        println!(
+            "PII columns flagged: {} in {} table(s)",
+            pii_columns.iter().sum::<usize>(),
+            pii_columns.len()
+        );
+    }
+
+    check_warnings(matches, warnings)?;
+    Ok(fingerprint)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> ArgMatches {
+        let args = ["tsql-diagram-generator"].iter().chain(args);
+        command().try_get_matches_from(args).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// The matches for `args` after a login to a placeholder server.
+    fn matches(args: &[&str]) -> ArgMatches {
+        parse(&[["-u", "sa", "-p", "secret", "-c", "Sales"].as_slice(), args].concat())
+    }
+
+    /// An empty directory for the test `name` under the system temp directory.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tsql-diagram-main-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes a DDL script of `dbo.Customer` referenced by `sales.Orders`
+    /// into `dir`, returning its path.
+    fn sales_script(dir: &Path) -> PathBuf {
+        let script = dir.join("sales.sql");
+        std::fs::write(
+            &script,
+            "CREATE TABLE dbo.Customer (Id int NOT NULL PRIMARY KEY);
+             CREATE TABLE sales.Orders (Id int NOT NULL PRIMARY KEY, CustomerId int);
+             ALTER TABLE sales.Orders ADD CONSTRAINT FK_Orders_Customer
+                 FOREIGN KEY (CustomerId) REFERENCES dbo.Customer (Id);",
+        )
+        .unwrap();
+        script
+    }
+
+    #[tokio::test]
+    async fn overview_is_written_beside_the_detailed_diagram() {
+        let dir = scratch_dir("overview");
+        let script = sales_script(&dir);
+        let args = ["--from-sql", script.to_str().unwrap(), "--overview", "--output-dir", dir.to_str().unwrap()];
+        run(&parse(&args)).await.unwrap();
+
+        let detailed = std::fs::read_to_string(dir.join("schema.puml")).unwrap();
+        assert!(detailed.contains("  CustomerId : int\n"), "{}", detailed);
+        let overview = std::fs::read_to_string(dir.join("schema.overview.puml")).unwrap();
+        assert!(overview.contains("Orders") && overview.contains("Customer"), "{}", overview);
+        assert!(overview.contains("-->"), "{}", overview);
+        assert!(!overview.contains(" : int"), "{}", overview);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn zip_holds_an_entry_per_schema_and_format() {
+        let dir = scratch_dir("zip");
+        let script = sales_script(&dir);
+        let archive = dir.join("diagrams.zip");
+        let (script, archive_path) = (script.to_str().unwrap(), archive.to_str().unwrap());
+        let args = ["--from-sql", script, "--split-by-schema", "--format", "plantuml,mermaid", "--zip", archive_path];
+        run(&parse(&args)).await.unwrap();
+
+        let archive = zip::ZipArchive::new(std::fs::File::open(&archive).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(|name| name.unwrap().into_owned()).collect();
+        names.sort();
+        assert_eq!(names, ["schema.dbo.mmd", "schema.dbo.puml", "schema.sales.mmd", "schema.sales.puml"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// A schema of `count` tables, plus an external stub and a table type
+    /// that don't count towards the auto-overview threshold.
+    fn tables(count: usize) -> DatabaseSchema {
+        let mut tables: Vec<Table> = (0..count)
+            .map(|i| Table {
+                external: false,
+                ..Table::external_stub("dbo", &format!("t{}", i))
+            })
+            .collect();
+        tables.push(Table::external_stub("other", "Stub"));
+        tables.push(Table {
+            external: false,
+            table_type: true,
+            ..Table::external_stub("dbo", "IdList")
+        });
+        DatabaseSchema {
+            tables,
+            references: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn disabled_foreign_keys_are_left_out_unless_included() {
+        let script = "CREATE TABLE dbo.Customer (Id int NOT NULL PRIMARY KEY);
+            CREATE TABLE dbo.Orders (Id int NOT NULL PRIMARY KEY, CustomerId int);
+            ALTER TABLE dbo.Orders ADD CONSTRAINT FK_Orders_Customer
+                FOREIGN KEY (CustomerId) REFERENCES dbo.Customer (Id);
+            ALTER TABLE dbo.Orders NOCHECK CONSTRAINT FK_Orders_Customer;";
+        let (schema, _) = ddl_schema(script, false);
+        assert!(schema.references.is_empty());
+
+        let (schema, _) = ddl_schema(script, true);
+        assert_eq!(schema.references.len(), 1);
+        let plantuml = lookup_format("plantuml").unwrap().render(&schema, &RenderOptions::default()).unwrap();
+        assert!(plantuml.contains("\nOrders::CustomerId ..> Customer::Id : CustomerId\n"), "{}", plantuml);
+        assert!(!plantuml.contains("-->"), "{}", plantuml);
+    }
+
+    #[test]
+    fn auto_overview_switches_only_above_the_threshold() {
+        assert_eq!(over_overview_threshold(&tables(11), Some(10)), Some(11));
+        assert_eq!(over_overview_threshold(&tables(10), Some(10)), None);
+        assert_eq!(over_overview_threshold(&tables(11), None), None);
+    }
+
+    #[test]
+    fn provenance_lists_given_options_with_the_password_redacted() {
+        let lines = provenance(&matches(&["-i", "db01", "--include", "dbo.Order*,Customers", "--quiet"]));
+        assert_eq!(
+            lines,
+            [
+                "Generated by TSQLDiagramGenerator 1.0",
+                "--ip_address db01",
+                "--username sa",
+                "--password <redacted>",
+                "--initial_catalog Sales",
+                "--include dbo.Order*,Customers",
+                "--quiet",
+            ]
+        );
+        assert!(lines.iter().all(|line| !line.contains("secret")));
+    }
+
+    #[test]
+    fn server_endpoint_resolves_port_and_instance() {
+        let endpoint = |args: &[&str]| {
+            let matches = matches(args);
+            server_endpoint(&matches, matches.get_one::<String>("ip_address").unwrap()).unwrap()
+        };
+        assert_eq!(endpoint(&["-i", "db01", "--port", "50100"]), Endpoint::Port("db01".to_string(), 50100));
+        assert_eq!(endpoint(&["-i", "db01"]), Endpoint::Port("db01".to_string(), 1433));
+        assert_eq!(
+            endpoint(&["-i", "db01\\SQLEXPRESS"]),
+            Endpoint::Instance("db01".to_string(), "SQLEXPRESS".to_string())
+        );
+        assert_eq!(
+            endpoint(&["-i", "db01\\SQLEXPRESS", "--port", "50100", "--quiet"]),
+            Endpoint::Port("db01".to_string(), 50100)
+        );
+        assert_eq!(endpoint(&["-i", "db01\\SQLEXPRESS,50200"]), Endpoint::Port("db01".to_string(), 50200));
+    }
+
+    #[test]
+    fn identical_watch_cycles_write_once() {
+        let mut written = WrittenHashes::new();
+        let (path, other) = (Path::new("out/schema.puml"), Path::new("out/schema.mmd"));
+        let writes = [
+            (path, "@startuml\n@enduml\n"),
+            (path, "@startuml\n@enduml\n"),
+            (other, "@startuml\n@enduml\n"),
+            (path, "@startuml\nclass a\n@enduml\n"),
+            (path, "@startuml\nclass a\n@enduml\n"),
+        ]
+        .iter()
+        .map(|(path, contents)| !unchanged_since_written(&mut written, path, contents.as_bytes()))
+        .collect::<Vec<bool>>();
+        assert_eq!(writes, [true, false, true, true, false]);
+    }
+
+    #[tokio::test]
+    async fn case_recases_diagrams_but_not_the_json_export() {
+        let dir = scratch_dir("case");
+        let script = sales_script(&dir);
+        for (case, customer) in [("preserve", "Customer"), ("lower", "customer"), ("upper", "CUSTOMER")] {
+            let (script, output_dir) = (script.to_str().unwrap(), dir.to_str().unwrap());
+            let args = ["--from-sql", script, "--case", case, "--format", "plantuml,json", "--output-dir", output_dir];
+            run(&parse(&args)).await.unwrap();
+
+            let plantuml = std::fs::read_to_string(dir.join("schema.puml")).unwrap();
+            assert!(plantuml.contains(&format!("\nclass {} {{\n", customer)), "--case {}: {}", case, plantuml);
+            let json: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(dir.join("schema.json")).unwrap()).unwrap();
+            let names: Vec<&str> = json["tables"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|table| table["name"].as_str().unwrap())
+                .collect();
+            assert_eq!(names, ["Customer", "Orders"], "--case {}", case);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn components_report_lists_clusters_then_isolated_tables() {
+        let (schema, _) = ddl_schema(
+            "CREATE TABLE dbo.Customer (Id int NOT NULL PRIMARY KEY);
+             CREATE TABLE dbo.Orders (Id int NOT NULL PRIMARY KEY, CustomerId int REFERENCES dbo.Customer (Id));
+             CREATE TABLE hr.Department (Id int NOT NULL PRIMARY KEY);
+             CREATE TABLE hr.Employee (Id int NOT NULL PRIMARY KEY, DepartmentId int REFERENCES hr.Department (Id));
+             CREATE TABLE dbo.Setting (Name nvarchar(50) NOT NULL PRIMARY KEY);",
+            false,
+        );
+        assert_eq!(
+            components_report(&schema),
+            [
+                "Component 1 (2 tables): dbo.Customer, dbo.Orders",
+                "Component 2 (2 tables): hr.Department, hr.Employee",
+                "Isolated tables: 1 (dbo.Setting)",
+            ]
+        );
+    }
+
+    #[test]
+    fn since_accepts_a_date_or_a_timestamp() {
+        assert_eq!(parse_since("2026-10-01").unwrap(), "2026-10-01T00:00:00");
+        assert_eq!(parse_since("2026-10-01T08:15:00").unwrap(), "2026-10-01T08:15:00");
+        for invalid in ["", "2026-10", "2026/10/01", "2026-10-01 08:15:00", "2026-10-01T08:15", "2026-1a-01"] {
+            assert!(parse_since(invalid).is_err(), "{:?} was accepted", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn fail_on_warnings_fails_for_a_table_without_a_primary_key() {
+        let dir = scratch_dir("fail_on_warnings");
+        let script = dir.join("log.sql");
+        std::fs::write(&script, "CREATE TABLE dbo.Log (Message nvarchar(200));").unwrap();
+        let (script, output_dir) = (script.to_str().unwrap(), dir.to_str().unwrap());
+        let args = ["--from-sql", script, "--output-dir", output_dir, "--quiet"];
+        run(&parse(&args)).await.unwrap();
+
+        let strict = [&args[..], &["--fail-on-warnings"]].concat();
+        match run(&parse(&strict)).await {
+            Err(DiagramError::WarningsFound(count)) => assert_eq!(count, 1),
+            other => panic!("{:?}", other.map(|_| ()).map_err(|err| err.to_string())),
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
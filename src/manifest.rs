@@ -0,0 +1,187 @@
+use crate::model::{DatabaseSchema, Table};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Database objects counted by catalog queries rather than read into the model.
+#[derive(Clone, Copy, Default)]
+pub struct ObjectCounts {
+    pub views: usize,
+    pub indexes: usize,
+}
+
+/// The tallies `--count-only` reports for a catalog, read with COUNT queries
+/// against the catalog views instead of extracting the schema.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CatalogCounts {
+    pub tables: usize,
+    pub views: usize,
+    /// Columns of the tables.
+    pub columns: usize,
+    /// Foreign key constraints, with composite keys counted once.
+    pub foreign_keys: usize,
+    pub indexes: usize,
+    pub procedures: usize,
+}
+
+impl CatalogCounts {
+    /// The counts as one line, such as `12 tables, 3 views, 140 columns, 15
+    /// foreign keys, 20 indexes, 8 procedures`.
+    pub fn line(&self) -> String {
+        let plural = |count: usize, one: &str, many: &str| format!("{} {}", count, if count == 1 { one } else { many });
+        [
+            plural(self.tables, "table", "tables"),
+            plural(self.views, "view", "views"),
+            plural(self.columns, "column", "columns"),
+            plural(self.foreign_keys, "foreign key", "foreign keys"),
+            plural(self.indexes, "index", "indexes"),
+            plural(self.procedures, "procedure", "procedures"),
+        ]
+        .join(", ")
+    }
+
+    /// The counts of `database` as a one-line JSON object, such as
+    /// `{"database":"Sales","tables":12,...}`.
+    pub fn to_json(&self, database: &str) -> String {
+        let mut object = serde_json::to_value(self).expect("the counts serialize to JSON");
+        object["database"] = serde_json::Value::from(database);
+        object.to_string()
+    }
+}
+
+/// The machine-readable inventory written next to the diagram with `--manifest`.
+#[derive(Serialize)]
+pub struct Manifest {
+    pub database: String,
+    /// UTC generation time in RFC 3339 form, e.g. `2024-05-01T12:30:00Z`.
+    pub generated_at: String,
+    /// Tables, not counting external stubs or table types.
+    pub tables: usize,
+    pub views: usize,
+    /// Columns of the counted tables.
+    pub columns: usize,
+    /// Foreign key constraints, with composite keys counted once.
+    pub references: usize,
+    pub indexes: usize,
+}
+
+impl Manifest {
+    pub fn new(database: &str, schema: &DatabaseSchema, counts: ObjectCounts) -> Manifest {
+        let constraints: HashSet<(&str, &str, &str)> = schema
+            .references
+            .iter()
+            .map(|reference| {
+                (
+                    reference.schema.as_str(),
+                    reference.table.as_str(),
+                    reference.constraint_name.as_str(),
+                )
+            })
+            .collect();
+        let tables: Vec<&Table> = schema.tables.iter().filter(|table| !table.external && !table.table_type).collect();
+        Manifest {
+            database: database.to_string(),
+            generated_at: utc_timestamp(SystemTime::now()),
+            tables: tables.len(),
+            views: counts.views,
+            columns: tables.iter().map(|table| table.columns.len()).sum(),
+            references: constraints.len(),
+            indexes: counts.indexes,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("the manifest serializes to JSON")
+    }
+}
+
+/// Formats `time` as an RFC 3339 UTC timestamp with second precision.
+fn utc_timestamp(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+    let (days, second_of_day) = ((seconds / 86_400) as i64, seconds % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        second_of_day / 3_600,
+        second_of_day % 3_600 / 60,
+        second_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{reference, schema, table};
+    use std::time::Duration;
+
+    #[test]
+    fn manifest_counts_match_the_schema() {
+        let mut table_type = table("dbo.OrderList", &["Id"], &[]);
+        table_type.table_type = true;
+        let schema = schema(
+            vec![
+                table("dbo.Order", &["Id", "RegionId", "RegionCode"], &["Id"]),
+                table("dbo.Region", &["Id", "Code"], &["Id", "Code"]),
+                table_type,
+                Table::external_stub("crm", "Customer"),
+            ],
+            vec![
+                reference("FK_Order_Region", "dbo.Order.RegionId", "dbo.Region.Id"),
+                reference("FK_Order_Region", "dbo.Order.RegionCode", "dbo.Region.Code"),
+                reference("FK_Order_Customer", "dbo.Order.Id", "crm.Customer.Id"),
+            ],
+        );
+        let manifest = Manifest::new("Sales", &schema, ObjectCounts { views: 2, indexes: 4 });
+
+        let json: serde_json::Value = serde_json::from_str(&manifest.to_json()).unwrap();
+        assert_eq!(json["database"], "Sales");
+        assert_eq!(json["tables"], 2);
+        assert_eq!(json["views"], 2);
+        assert_eq!(json["columns"], 5);
+        assert_eq!(json["references"], 2);
+        assert_eq!(json["indexes"], 4);
+        let generated_at = json["generated_at"].as_str().unwrap();
+        assert_eq!((generated_at.len(), &generated_at[10..11], &generated_at[19..]), (20, "T", "Z"));
+    }
+
+    #[test]
+    fn timestamps_are_rfc_3339_utc() {
+        assert_eq!(utc_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(utc_timestamp(UNIX_EPOCH + Duration::from_secs(951_782_400)), "2000-02-29T00:00:00Z");
+        assert_eq!(utc_timestamp(UNIX_EPOCH + Duration::from_secs(1_714_566_600)), "2024-05-01T12:30:00Z");
+    }
+
+    #[test]
+    fn catalog_counts_are_reported_as_a_line_or_json() {
+        let counts = CatalogCounts { tables: 12, views: 1, columns: 140, foreign_keys: 15, indexes: 1, procedures: 0 };
+        assert_eq!(counts.line(), "12 tables, 1 view, 140 columns, 15 foreign keys, 1 index, 0 procedures");
+        let json: serde_json::Value = serde_json::from_str(&counts.to_json("Sales")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "database": "Sales",
+                "tables": 12,
+                "views": 1,
+                "columns": 140,
+                "foreign_keys": 15,
+                "indexes": 1,
+                "procedures": 0,
+            })
+        );
+        assert!(!counts.to_json("Sales").contains('\n'));
+    }
+}
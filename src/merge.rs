@@ -0,0 +1,321 @@
+use crate::error::DiagramError;
+use crate::model::{DatabaseSchema, Reference};
+
+/// Merges schemas extracted from several catalogs into one, prefixing every
+/// table name with its catalog (`Catalog.Table`) so names stay unique.
+pub fn merge_catalogs(catalogs: Vec<(String, DatabaseSchema)>) -> DatabaseSchema {
+    let mut merged = DatabaseSchema {
+        tables: Vec::new(),
+        references: Vec::new(),
+    };
+    for (catalog, schema) in catalogs {
+        for mut table in schema.tables {
+            table.name = format!("{}.{}", catalog, table.name);
+            merged.tables.push(table);
+        }
+        for mut reference in schema.references {
+            reference.table = format!("{}.{}", catalog, reference.table);
+            reference.referenced_table = format!("{}.{}", catalog, reference.referenced_table);
+            merged.references.push(reference);
+        }
+    }
+    merged
+}
+
+/// The table property `combine_databases` records each table's database in,
+/// which `--combined` groups packages by.
+pub const DATABASE_PROPERTY: &str = "Database";
+
+/// Merges the schemas of `--batch` databases into one for `--combined`. Each
+/// database comes with its name and the catalogs it diagrammed. Table names
+/// are prefixed with the database name, as `merge_catalogs` does, and the
+/// name is recorded in `DATABASE_PROPERTY`. The `Catalog.Table` ends of the
+/// cross-database `references` are pointed at the database diagramming that
+/// catalog; references to tables outside the combined schema are dropped.
+pub fn combine_databases(
+    databases: Vec<(String, Vec<String>, DatabaseSchema)>,
+    references: Vec<Reference>,
+) -> DatabaseSchema {
+    let resolve = |table: &str| {
+        let (catalog, name) = table.split_once('.')?;
+        let (database, catalogs, _) = databases.iter().find(|(_, catalogs, _)| catalogs.iter().any(|c| c == catalog))?;
+        // A database of several catalogs already prefixes its tables with them
+        Some(match catalogs.len() {
+            1 => format!("{}.{}", database, name),
+            _ => format!("{}.{}", database, table),
+        })
+    };
+    let references: Vec<Reference> = references
+        .into_iter()
+        .filter_map(|mut reference| {
+            reference.table = resolve(&reference.table)?;
+            reference.referenced_table = resolve(&reference.referenced_table)?;
+            Some(reference)
+        })
+        .collect();
+    let mut merged = merge_catalogs(
+        databases
+            .into_iter()
+            .map(|(database, _, mut schema)| {
+                for table in &mut schema.tables {
+                    table.properties.insert(DATABASE_PROPERTY.to_string(), database.clone());
+                }
+                (database, schema)
+            })
+            .collect(),
+    );
+    let exists = |table_schema: &str, name: &str| {
+        merged.tables.iter().any(|table| table.schema == table_schema && table.name == name)
+    };
+    let references: Vec<Reference> = references
+        .into_iter()
+        .filter(|reference| {
+            exists(&reference.schema, &reference.table)
+                && exists(&reference.referenced_schema, &reference.referenced_table)
+        })
+        .collect();
+    merged.references.extend(references);
+    merged
+}
+
+/// A synonym read from `sys.synonyms` of one of the merged catalogs.
+pub struct Synonym {
+    pub catalog: String,
+    pub schema: String,
+    pub name: String,
+    /// The base object as written in `CREATE SYNONYM`, with one to four
+    /// optionally bracketed parts, e.g. `[CrmDb].[dbo].[Customers]`.
+    pub base_object: String,
+}
+
+/// Splits a multi-part name such as `[Crm.Db].dbo.[Customers]` at the dots
+/// outside brackets, unquoting each part.
+fn name_parts(name: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = name.trim().chars().peekable();
+    let mut bracketed = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '[' if !bracketed => bracketed = true,
+            // `]]` is an escaped bracket inside a bracketed part
+            ']' if bracketed && chars.peek() == Some(&']') => {
+                chars.next();
+                parts.last_mut().unwrap().push(']');
+            }
+            ']' if bracketed => bracketed = false,
+            '.' if !bracketed => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+/// Points the cross-database references that target a synonym at the table
+/// it stands for, recording the synonym in `Reference::via_synonym`. Parts
+/// the base object leaves out default to the synonym's own catalog and to
+/// `dbo`; a linked-server part is ignored.
+pub fn resolve_synonyms(references: &mut [Reference], synonyms: &[Synonym]) {
+    for reference in references {
+        let synonym = synonyms.iter().find(|synonym| {
+            synonym.schema == reference.referenced_schema
+                && format!("{}.{}", synonym.catalog, synonym.name) == reference.referenced_table
+        });
+        let Some(synonym) = synonym else {
+            continue;
+        };
+        let parts = name_parts(&synonym.base_object);
+        let (catalog, schema, table) = match parts.as_slice() {
+            [.., catalog, schema, table] => (catalog.as_str(), schema.as_str(), table.as_str()),
+            [schema, table] => (synonym.catalog.as_str(), schema.as_str(), table.as_str()),
+            [table] => (synonym.catalog.as_str(), "", table.as_str()),
+            [] => continue,
+        };
+        let catalog = if catalog.is_empty() { synonym.catalog.as_str() } else { catalog };
+        let schema = if schema.is_empty() { "dbo" } else { schema };
+        reference.via_synonym = Some(format!("{}.{}.{}", synonym.catalog, synonym.schema, synonym.name));
+        reference.referenced_schema = schema.to_string();
+        reference.referenced_table = format!("{}.{}", catalog, table);
+    }
+}
+
+fn parse_endpoint(endpoint: &str, line: &str) -> Result<(String, String, String), DiagramError> {
+    let parts: Vec<&str> = endpoint.trim().split('.').map(str::trim).collect();
+    match parts.as_slice() {
+        [catalog, schema, table, column] if parts.iter().all(|part| !part.is_empty()) => Ok((
+            schema.to_string(),
+            format!("{}.{}", catalog, table),
+            column.to_string(),
+        )),
+        _ => Err(DiagramError::InvalidArgument(format!(
+            "expected Catalog.Schema.Table.Column in cross-database reference '{}'",
+            line
+        ))),
+    }
+}
+
+/// Parses cross-database references, one per line in the form
+/// `SalesDb.dbo.Orders.CustomerId -> CrmDb.dbo.Customers.Id`.
+///
+/// SQL Server can't declare foreign keys across databases, so these links
+/// have to be supplied by the user. Blank lines and `#` comments are ignored.
+pub fn parse_cross_references(contents: &str) -> Result<Vec<Reference>, DiagramError> {
+    let mut references = Vec::new();
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (from, to) = line.split_once("->").ok_or_else(|| {
+            DiagramError::InvalidArgument(format!("expected 'from -> to' in cross-database reference '{}'", line))
+        })?;
+        let (schema, table, column) = parse_endpoint(from, line)?;
+        let (referenced_schema, referenced_table, referenced_column) = parse_endpoint(to, line)?;
+        references.push(Reference {
+            constraint_name: format!("cross_db_{}", references.len() + 1),
+            schema,
+            table,
+            column,
+            referenced_schema,
+            referenced_table,
+            referenced_column,
+            is_disabled: false,
+            references_pk: true,
+            via_synonym: None,
+        });
+    }
+    Ok(references)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{generate_plantuml, RenderOptions};
+    use crate::testing::{reference, reference_lines, schema, table, table_names};
+
+    fn sales() -> DatabaseSchema {
+        schema(
+            vec![
+                table("dbo.Order", &["Id", "CustomerId"], &["Id"]),
+                table("dbo.OrderLine", &["Id", "OrderId"], &["Id"]),
+            ],
+            vec![reference("FK_OrderLine_Order", "dbo.OrderLine.OrderId", "dbo.Order.Id")],
+        )
+    }
+
+    fn crm() -> DatabaseSchema {
+        schema(vec![table("dbo.Customer", &["Id"], &["Id"])], Vec::new())
+    }
+
+    #[test]
+    fn merged_catalogs_keep_their_references_and_gain_cross_database_ones() {
+        let mut merged = merge_catalogs(vec![("SalesDb".to_string(), sales()), ("CrmDb".to_string(), crm())]);
+        let cross = parse_cross_references(
+            "# orders belong to CRM customers\n\nSalesDb.dbo.Order.CustomerId -> CrmDb.dbo.Customer.Id\n",
+        )
+        .unwrap();
+        merged.references.extend(cross);
+
+        assert_eq!(table_names(&merged), ["dbo.SalesDb.Order", "dbo.SalesDb.OrderLine", "dbo.CrmDb.Customer"]);
+        assert_eq!(
+            reference_lines(&merged),
+            [
+                "FK_OrderLine_Order: dbo.SalesDb.OrderLine.OrderId -> dbo.SalesDb.Order.Id",
+                "cross_db_1: dbo.SalesDb.Order.CustomerId -> dbo.CrmDb.Customer.Id",
+            ]
+        );
+    }
+
+    #[test]
+    fn combined_databases_render_a_package_each() {
+        let references = parse_cross_references(
+            "SalesDb.dbo.Order.CustomerId -> CrmDb.dbo.Customer.Id\n\
+             SalesDb.dbo.Order.Id -> BillingDb.dbo.Invoice.OrderId",
+        )
+        .unwrap();
+        let combined = combine_databases(
+            vec![
+                ("sales".to_string(), vec!["SalesDb".to_string()], sales()),
+                ("crm".to_string(), vec!["CrmDb".to_string()], crm()),
+            ],
+            references,
+        );
+        assert_eq!(table_names(&combined), ["dbo.sales.Order", "dbo.sales.OrderLine", "dbo.crm.Customer"]);
+        assert_eq!(combined.tables[2].properties[DATABASE_PROPERTY], "crm");
+        assert_eq!(
+            reference_lines(&combined),
+            [
+                "FK_OrderLine_Order: dbo.sales.OrderLine.OrderId -> dbo.sales.Order.Id",
+                "cross_db_1: dbo.sales.Order.CustomerId -> dbo.crm.Customer.Id",
+            ]
+        );
+
+        let options = RenderOptions {
+            group_by_property: Some(DATABASE_PROPERTY.to_string()),
+            database_packages: true,
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&combined, &options);
+        let packages: Vec<&str> = plantuml.lines().filter(|line| line.starts_with("package ")).collect();
+        assert_eq!(packages, ["package \"sales\" {", "package \"crm\" {"]);
+        assert!(plantuml.contains("class \"Customer\" as crm_Customer {"), "{}", plantuml);
+        assert!(plantuml.contains("sales_Order::CustomerId --> crm_Customer::Id : CustomerId\n"), "{}", plantuml);
+    }
+
+    fn synonym(name: &str, base_object: &str) -> Synonym {
+        Synonym {
+            catalog: "SalesDb".to_string(),
+            schema: "dbo".to_string(),
+            name: name.to_string(),
+            base_object: base_object.to_string(),
+        }
+    }
+
+    #[test]
+    fn references_to_synonyms_point_at_the_base_table() {
+        // Merged table names carry their catalog, which `reference` would split off
+        let merged = |constraint: &str, from: &str, to: &str| {
+            let mut reference = reference(constraint, from, to);
+            reference.table = format!("SalesDb.{}", reference.table);
+            reference.referenced_table = format!("SalesDb.{}", reference.referenced_table);
+            reference
+        };
+        let mut references = vec![
+            merged("FK_Order_Customer", "dbo.Order.CustomerId", "dbo.Customers.Id"),
+            merged("FK_Order_Region", "dbo.Order.RegionId", "dbo.Regions.Id"),
+            merged("FK_Order_Rep", "dbo.Order.RepId", "dbo.Reps.Id"),
+            merged("FK_OrderLine_Order", "dbo.OrderLine.OrderId", "dbo.Order.Id"),
+        ];
+        resolve_synonyms(
+            &mut references,
+            &[
+                synonym("Customers", "[Crm.Db].[crm].[Customer]"),
+                synonym("Regions", "ref.Region"),
+                synonym("Reps", "[LinkedServer].HrDb..[Sales]]Rep]"),
+            ],
+        );
+
+        assert_eq!(
+            reference_lines(&schema(Vec::new(), references.clone())),
+            [
+                "FK_Order_Customer: dbo.SalesDb.Order.CustomerId -> crm.Crm.Db.Customer.Id",
+                "FK_Order_Region: dbo.SalesDb.Order.RegionId -> ref.SalesDb.Region.Id",
+                "FK_Order_Rep: dbo.SalesDb.Order.RepId -> dbo.HrDb.Sales]Rep.Id",
+                "FK_OrderLine_Order: dbo.SalesDb.OrderLine.OrderId -> dbo.SalesDb.Order.Id",
+            ]
+        );
+        assert_eq!(references[0].via_synonym.as_deref(), Some("SalesDb.dbo.Customers"));
+        assert_eq!(references[3].via_synonym, None);
+    }
+
+    #[test]
+    fn malformed_cross_references_are_rejected() {
+        for (contents, expected) in [
+            ("SalesDb.dbo.Order.CustomerId", "expected 'from -> to' in cross-database reference"),
+            ("dbo.Order.CustomerId -> CrmDb.dbo.Customer.Id", "expected Catalog.Schema.Table.Column"),
+        ] {
+            match parse_cross_references(contents) {
+                Err(DiagramError::InvalidArgument(message)) => assert!(message.starts_with(expected), "{}", message),
+                other => panic!("expected {} to be rejected, got {:?}", contents, other.map(|refs| refs.len())),
+            }
+        }
+    }
+}
@@ -0,0 +1,473 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Types whose declaration carries a length, such as `nvarchar(50)`.
+const LENGTH_TYPES: &[&str] = &["char", "varchar", "nchar", "nvarchar", "binary", "varbinary"];
+
+/// Types whose declaration carries a precision and scale, such as `decimal(18,2)`.
+const PRECISION_TYPES: &[&str] = &["decimal", "numeric"];
+
+/// Types with a fixed precision that is never declared, such as `money`.
+const IMPLICIT_PRECISION_TYPES: &[&str] = &["money", "smallmoney"];
+
+/// A column's data type split into its base name and declared parameters.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DataType {
+    pub base: String,
+    pub length: Option<i64>,
+    pub precision: Option<u8>,
+    pub scale: Option<u8>,
+    pub is_max: bool,
+}
+
+impl DataType {
+    pub fn new(base: &str) -> DataType {
+        DataType {
+            base: base.to_lowercase(),
+            length: None,
+            precision: None,
+            scale: None,
+            is_max: false,
+        }
+    }
+
+    /// Builds a type from the INFORMATION_SCHEMA.COLUMNS fields, keeping only
+    /// the parameters that appear in the type's declaration.
+    pub fn from_information_schema(
+        data_type: &str,
+        max_length: Option<i32>,
+        precision: Option<i32>,
+        scale: Option<i32>,
+    ) -> DataType {
+        let mut parsed = DataType::new(data_type);
+        if LENGTH_TYPES.contains(&parsed.base.as_str()) {
+            match max_length {
+                Some(-1) => parsed.is_max = true,
+                Some(length) => parsed.length = Some(i64::from(length)),
+                None => {}
+            }
+        } else if PRECISION_TYPES.contains(&parsed.base.as_str()) {
+            parsed.precision = precision
+                .and_then(|precision| u8::try_from(precision).ok())
+                .filter(|precision| *precision > 0);
+            parsed.scale = scale.and_then(|scale| u8::try_from(scale).ok());
+        }
+        parsed
+    }
+
+    /// Parses a declaration such as `int`, `nvarchar(max)` or `decimal(18,2)`.
+    pub fn parse(declaration: &str) -> DataType {
+        let declaration = declaration.trim();
+        let (base, arguments) = match declaration.split_once('(') {
+            Some((base, rest)) => (base.trim(), rest.trim_end_matches(')').trim()),
+            None => (declaration, ""),
+        };
+        let mut parsed = DataType::new(base);
+        if IMPLICIT_PRECISION_TYPES.contains(&parsed.base.as_str()) {
+            return parsed;
+        }
+        let arguments: Vec<&str> = arguments.split(',').map(str::trim).filter(|arg| !arg.is_empty()).collect();
+        match arguments.as_slice() {
+            [max] if max.eq_ignore_ascii_case("max") => parsed.is_max = true,
+            [length] if LENGTH_TYPES.contains(&parsed.base.as_str()) => parsed.length = length.parse().ok(),
+            [precision] => parsed.precision = precision.parse().ok(),
+            [precision, scale] => {
+                parsed.precision = precision.parse().ok();
+                parsed.scale = scale.parse().ok();
+            }
+            _ => {}
+        }
+        parsed
+    }
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.base)?;
+        if self.is_max {
+            write!(f, "(max)")
+        } else if let Some(length) = self.length {
+            write!(f, "({})", length)
+        } else if IMPLICIT_PRECISION_TYPES.contains(&self.base.as_str()) {
+            Ok(())
+        } else if let (Some(precision @ 1..), Some(scale)) = (self.precision, self.scale) {
+            write!(f, "({},{})", precision, scale)
+        } else if let Some(precision @ 1..) = self.precision {
+            write!(f, "({})", precision)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Clone, Hash, Serialize, Deserialize)]
+pub struct Column {
+    pub name: String,
+    pub data_type: DataType,
+    /// The column's `MS_Description` extended property.
+    pub description: Option<String>,
+    /// The legacy `CREATE DEFAULT` object bound to this column via `sp_bindefault`.
+    pub bound_default: Option<String>,
+    /// The legacy `CREATE RULE` object bound to this column via `sp_bindrule`.
+    pub bound_rule: Option<String>,
+    /// The column's collation, only when it differs from the database default.
+    pub collation: Option<String>,
+    /// The full-text catalog of the full-text index covering this column.
+    pub fulltext_catalog: Option<String>,
+    /// Marks a computed column, whose value is derived from an expression.
+    #[serde(default)]
+    pub computed: bool,
+}
+
+#[derive(Clone, Hash, Serialize, Deserialize)]
+pub struct Table {
+    pub schema: String,
+    pub name: String,
+    pub columns: Vec<Column>,
+    pub primary_key: Vec<String>,
+    pub description: Option<String>,
+    /// Requested table-level extended properties, keyed by property name.
+    pub properties: BTreeMap<String, String>,
+    /// Marks a name-only stub for a table outside the diagrammed set, kept so
+    /// that references into it stay visible.
+    pub external: bool,
+    /// Marks a user-defined table type, used for table-valued parameters,
+    /// rather than a table. Table types take no part in foreign keys.
+    pub table_type: bool,
+    /// The filegroup (or partition scheme) holding the table's data.
+    pub filegroup: Option<String>,
+    /// The `(schema, table)` of the history table of a system-versioned
+    /// temporal table, read with `--history-links`.
+    pub history_table: Option<(String, String)>,
+    /// DML triggers defined on the table.
+    pub triggers: Vec<Trigger>,
+    /// Stored procedures referencing the table by name, as `schema.name`,
+    /// read from `sys.sql_expression_dependencies` with
+    /// `--include-proc-dependencies`.
+    pub procedures: Vec<String>,
+    /// Views selecting from the table, as `schema.name`, read from
+    /// `sys.sql_expression_dependencies` with `--resolve-indirection`.
+    #[serde(default)]
+    pub views: Vec<String>,
+    /// Synonyms whose base object is the table, as `schema.name`, read with
+    /// `--resolve-indirection`.
+    #[serde(default)]
+    pub synonyms: Vec<String>,
+    /// Nonclustered and clustered indexes other than the primary key.
+    pub indexes: Vec<Index>,
+    /// The row count from partition statistics, read with `--include-row-counts`.
+    pub row_count: Option<u64>,
+    /// Space reserved for the table and its indexes in kilobytes, read with
+    /// `--include-sizes`. Kept whole so the model stays hashable; see
+    /// `Table::size_mb`.
+    pub size_kb: Option<u64>,
+    /// When the table's definition last changed (`sys.tables.modify_date`),
+    /// as `YYYY-MM-DDTHH:MM:SS` in server time.
+    pub modified_at: Option<String>,
+    /// The latest user seek, scan, lookup or update recorded in
+    /// `sys.dm_db_index_usage_stats`, as `YYYY-MM-DDTHH:MM:SS` in server
+    /// time. `None` when the table hasn't been used since the statistics
+    /// were last reset, usually at server restart.
+    pub last_used: Option<String>,
+    /// Free text attached by `--annotation-query`, drawn as a note.
+    pub annotation: Option<String>,
+    /// Example rows read with `--sample-data`, one value per column in
+    /// `columns` order, with `None` for NULL.
+    pub sample_rows: Vec<Vec<Option<String>>>,
+}
+
+#[derive(Clone, Hash, Serialize, Deserialize)]
+pub struct Trigger {
+    pub name: String,
+    /// `INSTEAD OF` rather than `AFTER`.
+    pub instead_of: bool,
+}
+
+#[derive(Clone, Hash, Serialize, Deserialize)]
+pub struct Index {
+    pub name: String,
+    /// Key columns in key order; included columns are left out.
+    pub columns: Vec<String>,
+    /// The key columns sorted descending rather than ascending.
+    #[serde(default)]
+    pub descending: Vec<String>,
+    /// Non-key columns stored with the index through `INCLUDE`.
+    #[serde(default)]
+    pub included: Vec<String>,
+    pub unique: bool,
+    /// The `WHERE` predicate of a filtered index, as SQL Server stores it
+    /// (e.g. `([IsActive]=(1))`).
+    pub filter: Option<String>,
+}
+
+impl fmt::Display for Index {
+    /// Formats the index as `IX_Active (Status) WHERE ([IsActive]=(1))`,
+    /// with descending key columns marked as in `IX_Date (OrderDate DESC)`,
+    /// then any `INCLUDE (...)` columns and `UNIQUE` before any predicate.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| if self.descending.contains(column) { format!("{} DESC", column) } else { column.clone() })
+            .collect();
+        write!(f, "{} ({})", self.name, columns.join(", "))?;
+        if !self.included.is_empty() {
+            write!(f, " INCLUDE ({})", self.included.join(", "))?;
+        }
+        if self.unique {
+            write!(f, " UNIQUE")?;
+        }
+        if let Some(filter) = &self.filter {
+            write!(f, " WHERE {}", filter)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Hash, Serialize, Deserialize)]
+pub struct Reference {
+    pub constraint_name: String,
+    pub schema: String,
+    pub table: String,
+    pub column: String,
+    pub referenced_schema: String,
+    pub referenced_table: String,
+    pub referenced_column: String,
+    pub is_disabled: bool,
+    /// Whether the referenced column belongs to the referenced table's primary
+    /// key rather than to another unique key.
+    pub references_pk: bool,
+    /// The `Catalog.Schema.Name` synonym the reference was declared against,
+    /// when `--include-synonyms` resolved it to its base table.
+    #[serde(default)]
+    pub via_synonym: Option<String>,
+}
+
+/// Where a diagram was extracted from, for `--server-info-note`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ServerInfo {
+    pub server_name: String,
+    /// The first line of `@@VERSION`, e.g. `Microsoft SQL Server 2019 (RTM-CU16) ... - 15.0.4223.1 (X64)`.
+    pub version: String,
+    /// The diagrammed databases, one per catalog.
+    pub databases: Vec<String>,
+}
+
+impl ServerInfo {
+    /// Builds the server info from the `@@VERSION`, `@@SERVERNAME` and
+    /// `DB_NAME()` values; `@@SERVERNAME` is NULL until a server is named.
+    pub fn new(version: &str, server_name: Option<&str>, database: &str) -> ServerInfo {
+        ServerInfo {
+            server_name: server_name.unwrap_or("(unnamed)").trim().to_string(),
+            version: version.lines().next().unwrap_or_default().trim().to_string(),
+            databases: vec![database.to_string()],
+        }
+    }
+
+    /// The lines of the server note, one `label: value` pair each.
+    pub fn note_lines(&self) -> Vec<String> {
+        let label = if self.databases.len() == 1 { "Database" } else { "Databases" };
+        vec![
+            format!("Server: {}", self.server_name),
+            format!("Version: {}", self.version),
+            format!("{}: {}", label, self.databases.join(", ")),
+        ]
+    }
+}
+
+#[derive(Clone, Hash, Serialize, Deserialize)]
+pub struct DatabaseSchema {
+    pub tables: Vec<Table>,
+    pub references: Vec<Reference>,
+}
+
+impl Table {
+    /// Whether no two rows share the values of `columns` taken together: the
+    /// primary key or an unfiltered unique index is made up of columns among
+    /// them. A unique index over only some of `columns` is enough; one that
+    /// also spans other columns is not.
+    pub fn is_unique_over(&self, columns: &[&str]) -> bool {
+        let covered = |key: &[String]| !key.is_empty() && key.iter().all(|column| columns.contains(&column.as_str()));
+        covered(&self.primary_key)
+            || self.indexes.iter().any(|index| index.unique && index.filter.is_none() && covered(&index.columns))
+    }
+
+    /// The reserved size in megabytes.
+    pub fn size_mb(&self) -> Option<f64> {
+        self.size_kb.map(|kb| kb as f64 / 1024.0)
+    }
+
+    /// A column-less stub standing in for a table outside the diagrammed set.
+    pub fn external_stub(schema: &str, name: &str) -> Table {
+        Table {
+            schema: schema.to_string(),
+            name: name.to_string(),
+            columns: Vec::new(),
+            primary_key: Vec::new(),
+            description: None,
+            properties: BTreeMap::new(),
+            external: true,
+            table_type: false,
+            filegroup: None,
+            history_table: None,
+            triggers: Vec::new(),
+            procedures: Vec::new(),
+            views: Vec::new(),
+            synonyms: Vec::new(),
+            indexes: Vec::new(),
+            row_count: None,
+            size_kb: None,
+            modified_at: None,
+            last_used: None,
+            annotation: None,
+            sample_rows: Vec::new(),
+        }
+    }
+}
+
+impl DatabaseSchema {
+    /// A hash of the whole schema, used to tell whether it changed between
+    /// two extractions. Only meaningful within a single run of the program.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// A SHA-256, as lowercase hex, over the structure alone: tables, their
+    /// columns and types, primary keys and foreign keys. Unlike `fingerprint`
+    /// it is stable across runs, so it can tell whether two environments'
+    /// schemas match. Descriptions, statistics, external stubs and the order
+    /// tables and references were read in don't change it.
+    pub fn structural_fingerprint(&self) -> String {
+        let mut lines: Vec<String> = self
+            .tables
+            .iter()
+            .filter(|table| !table.external)
+            .map(|table| {
+                let columns: Vec<(&str, String)> =
+                    table.columns.iter().map(|column| (column.name.as_str(), column.data_type.to_string())).collect();
+                serde_json::json!(["table", table.schema, table.name, columns, table.primary_key]).to_string()
+            })
+            .collect();
+        lines.extend(self.references.iter().map(|reference| {
+            serde_json::json!([
+                "reference",
+                reference.schema,
+                reference.table,
+                reference.constraint_name,
+                reference.column,
+                reference.referenced_schema,
+                reference.referenced_table,
+                reference.referenced_column
+            ])
+            .to_string()
+        }));
+        lines.sort();
+        format!("{:x}", Sha256::digest(lines.join("\n")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{column, two_triangles};
+
+    #[test]
+    fn fingerprint_changes_only_with_the_schema() {
+        assert_eq!(two_triangles().fingerprint(), two_triangles().fingerprint());
+
+        let mut added_column = two_triangles();
+        added_column.tables[0].columns.push(column("Name:nvarchar(50)"));
+        assert_ne!(added_column.fingerprint(), two_triangles().fingerprint());
+
+        let mut dropped_reference = two_triangles();
+        dropped_reference.references.pop();
+        assert_ne!(dropped_reference.fingerprint(), two_triangles().fingerprint());
+
+        let mut counted = two_triangles();
+        counted.tables[0].row_count = Some(10);
+        assert_ne!(counted.fingerprint(), two_triangles().fingerprint());
+    }
+
+    fn data_type(base: &str, length: Option<i64>, precision: Option<u8>, scale: Option<u8>, is_max: bool) -> DataType {
+        DataType {
+            base: base.to_string(),
+            length,
+            precision,
+            scale,
+            is_max,
+        }
+    }
+
+    #[test]
+    fn declarations_parse_into_their_parameters() {
+        let cases = [
+            ("nvarchar(max)", data_type("nvarchar", None, None, None, true)),
+            ("decimal(18,2)", data_type("decimal", None, Some(18), Some(2), false)),
+            ("int", data_type("int", None, None, None, false)),
+        ];
+        for (declaration, expected) in cases {
+            let parsed = DataType::parse(declaration);
+            assert_eq!(parsed, expected);
+            assert_eq!(parsed.to_string(), declaration);
+        }
+    }
+
+    #[test]
+    fn information_schema_fields_keep_only_declared_parameters() {
+        let cases = [
+            (DataType::from_information_schema("nvarchar", Some(-1), None, None), "nvarchar(max)"),
+            (DataType::from_information_schema("DECIMAL", None, Some(18), Some(2)), "decimal(18,2)"),
+            (DataType::from_information_schema("int", None, Some(10), Some(0)), "int"),
+        ];
+        for (parsed, declaration) in cases {
+            assert_eq!(parsed, DataType::parse(declaration));
+        }
+    }
+
+    #[test]
+    fn money_and_numeric_render_without_bogus_parameters() {
+        let reported = [
+            (DataType::from_information_schema("money", None, Some(19), Some(4)), "money"),
+            (DataType::from_information_schema("smallmoney", None, Some(10), Some(4)), "smallmoney"),
+            (DataType::from_information_schema("numeric", None, Some(10), Some(4)), "numeric(10,4)"),
+            (DataType::from_information_schema("decimal", None, Some(0), Some(0)), "decimal"),
+            (DataType::from_information_schema("decimal", None, None, None), "decimal"),
+        ];
+        for (parsed, rendered) in reported {
+            assert_eq!(parsed.to_string(), rendered);
+        }
+        assert_eq!(DataType::parse("money(19,4)"), data_type("money", None, None, None, false));
+        assert_eq!(DataType::parse("smallmoney").to_string(), "smallmoney");
+        assert_eq!(DataType::parse("numeric(10,4)"), data_type("numeric", None, Some(10), Some(4), false));
+    }
+
+    #[test]
+    fn structural_fingerprint_ignores_order_and_cosmetic_details() {
+        let baseline = two_triangles().structural_fingerprint();
+        assert_eq!(baseline.len(), 64);
+
+        let mut reordered = two_triangles();
+        reordered.tables.reverse();
+        reordered.references.reverse();
+        assert_eq!(reordered.structural_fingerprint(), baseline);
+
+        let mut described = two_triangles();
+        described.tables[0].description = Some("Parents".to_string());
+        described.tables[1].row_count = Some(10);
+        described.tables.push(Table::external_stub("other", "Stub"));
+        assert_eq!(described.structural_fingerprint(), baseline);
+
+        let mut retyped = two_triangles();
+        retyped.tables[0].columns[0].data_type = DataType::new("bigint");
+        assert_ne!(retyped.structural_fingerprint(), baseline);
+        let mut rekeyed = two_triangles();
+        rekeyed.tables[0].primary_key.clear();
+        assert_ne!(rekeyed.structural_fingerprint(), baseline);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::model::DatabaseSchema;
+
+/// How table and column names are cased in the rendered output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IdentifierCase {
+    #[default]
+    Preserve,
+    Lower,
+    Upper,
+}
+
+impl IdentifierCase {
+    pub fn parse(case: &str) -> Option<IdentifierCase> {
+        match case {
+            "preserve" => Some(IdentifierCase::Preserve),
+            "lower" => Some(IdentifierCase::Lower),
+            "upper" => Some(IdentifierCase::Upper),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            IdentifierCase::Preserve => name.to_string(),
+            IdentifierCase::Lower => name.to_lowercase(),
+            IdentifierCase::Upper => name.to_uppercase(),
+        }
+    }
+
+    /// Recases the table and column names of a copy of the schema meant for
+    /// rendering, leaving schema names and the extracted model alone.
+    pub fn apply_to_schema(&self, mut schema: DatabaseSchema) -> DatabaseSchema {
+        if *self == IdentifierCase::Preserve {
+            return schema;
+        }
+        for table in &mut schema.tables {
+            table.name = self.apply(&table.name);
+            for column in &mut table.columns {
+                column.name = self.apply(&column.name);
+            }
+            for column in &mut table.primary_key {
+                *column = self.apply(column);
+            }
+        }
+        for reference in &mut schema.references {
+            reference.table = self.apply(&reference.table);
+            reference.column = self.apply(&reference.column);
+            reference.referenced_table = self.apply(&reference.referenced_table);
+            reference.referenced_column = self.apply(&reference.referenced_column);
+        }
+        schema
+    }
+}
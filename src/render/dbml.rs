@@ -0,0 +1,161 @@
+use super::{group_by_constraint, OutputFormat, RenderOptions};
+use crate::error::DiagramError;
+use crate::model::DatabaseSchema;
+
+/// How identifiers are quoted in generated DBML/DDL text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuoteStyle {
+    /// `[name]`, as in T-SQL.
+    Brackets,
+    /// `"name"`, as in ANSI SQL and DBML.
+    #[default]
+    Ansi,
+    /// `` `name` ``, as in MySQL-flavoured DBML.
+    Backtick,
+    /// `name`, left as is.
+    None,
+}
+
+impl QuoteStyle {
+    pub fn parse(style: &str) -> Option<QuoteStyle> {
+        match style {
+            "brackets" => Some(QuoteStyle::Brackets),
+            "ansi" => Some(QuoteStyle::Ansi),
+            "backtick" => Some(QuoteStyle::Backtick),
+            "none" => Some(QuoteStyle::None),
+            _ => None,
+        }
+    }
+
+    /// Quotes `identifier`, doubling any closing quote character inside it.
+    pub fn quote(&self, identifier: &str) -> String {
+        match self {
+            QuoteStyle::Brackets => format!("[{}]", identifier.replace(']', "]]")),
+            QuoteStyle::Ansi => format!("\"{}\"", identifier.replace('"', "\"\"")),
+            QuoteStyle::Backtick => format!("`{}`", identifier.replace('`', "``")),
+            QuoteStyle::None => identifier.to_string(),
+        }
+    }
+}
+
+/// A single-quoted DBML string literal.
+fn dbml_string(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+pub fn generate_dbml(schema: &DatabaseSchema, options: &RenderOptions) -> String {
+    let quote = |identifier: &str| options.quote_style.quote(identifier);
+    let (indent, nested) = (options.indent(2, 1), options.indent(2, 2));
+    let blank_line = if options.compact_output { "" } else { "\n" };
+    let mut dbml = String::new();
+    for table in &schema.tables {
+        dbml.push_str(&format!("Table {}.{} {{\n", quote(&table.schema), quote(&table.name)));
+        for column in options.visible_columns(schema, table) {
+            let is_single_pk = table.primary_key.len() == 1 && table.primary_key[0] == column.name;
+            let settings = if is_single_pk { " [pk]" } else { "" };
+            dbml.push_str(&format!("{}{} {}{}\n", indent, quote(&column.name), column.data_type, settings));
+        }
+        let mut indexes = Vec::new();
+        if table.primary_key.len() > 1 {
+            let key: Vec<String> = table.primary_key.iter().map(|column| quote(column)).collect();
+            indexes.push(format!("({}) [pk]", key.join(", ")));
+        }
+        for index in table.indexes.iter().filter(|_| options.show_indexes) {
+            let key: Vec<String> = index.columns.iter().map(|column| quote(column)).collect();
+            let mut settings = vec![format!("name: {}", dbml_string(&index.name))];
+            if index.unique {
+                settings.push("unique".to_string());
+            }
+            if let Some(filter) = &index.filter {
+                settings.push(format!("note: {}", dbml_string(&format!("WHERE {}", filter))));
+            }
+            indexes.push(format!("({}) [{}]", key.join(", "), settings.join(", ")));
+        }
+        if !indexes.is_empty() {
+            dbml.push_str(&format!("{}{}indexes {{\n", blank_line, indent));
+            for index in indexes {
+                dbml.push_str(&format!("{}{}\n", nested, index));
+            }
+            dbml.push_str(&format!("{}}}\n", indent));
+        }
+        dbml.push_str("}\n");
+        dbml.push_str(blank_line);
+    }
+    for group in group_by_constraint(&schema.references) {
+        let first = group[0];
+        let columns = |names: Vec<&str>| {
+            if names.len() == 1 {
+                quote(names[0])
+            } else {
+                let quoted: Vec<String> = names.iter().map(|name| quote(name)).collect();
+                format!("({})", quoted.join(", "))
+            }
+        };
+        dbml.push_str(&format!(
+            "Ref {}: {}.{}.{} > {}.{}.{}\n",
+            quote(&first.constraint_name),
+            quote(&first.schema),
+            quote(&first.table),
+            columns(group.iter().map(|reference| reference.column.as_str()).collect()),
+            quote(&first.referenced_schema),
+            quote(&first.referenced_table),
+            columns(group.iter().map(|reference| reference.referenced_column.as_str()).collect()),
+        ));
+    }
+    dbml
+}
+
+pub struct DbmlFormat;
+
+impl OutputFormat for DbmlFormat {
+    fn name(&self) -> &str {
+        "dbml"
+    }
+
+    fn extension(&self) -> &str {
+        "dbml"
+    }
+
+    fn comment(&self, line: &str) -> Option<String> {
+        Some(format!("// {}", line))
+    }
+
+    fn render(&self, schema: &DatabaseSchema, options: &RenderOptions) -> Result<String, DiagramError> {
+        Ok(generate_dbml(schema, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{schema, table};
+
+    #[test]
+    fn quote_escapes_the_closing_character() {
+        let name = "Select [\"x`]";
+        assert_eq!(QuoteStyle::Brackets.quote(name), "[Select [\"x`]]]");
+        assert_eq!(QuoteStyle::Ansi.quote(name), "\"Select [\"\"x`]\"");
+        assert_eq!(QuoteStyle::Backtick.quote(name), "`Select [\"x``]`");
+        assert_eq!(QuoteStyle::None.quote(name), name);
+    }
+
+    #[test]
+    fn reserved_column_names_are_quoted_in_each_style() {
+        let schema = schema(vec![table("dbo.Order", &["Order", "Key:nvarchar(10)"], &["Order"])], Vec::new());
+        for (style, table, order, key) in [
+            ("brackets", "[dbo].[Order]", "[Order]", "[Key]"),
+            ("ansi", "\"dbo\".\"Order\"", "\"Order\"", "\"Key\""),
+            ("backtick", "`dbo`.`Order`", "`Order`", "`Key`"),
+            ("none", "dbo.Order", "Order", "Key"),
+        ] {
+            let options = RenderOptions {
+                quote_style: QuoteStyle::parse(style).unwrap(),
+                ..RenderOptions::default()
+            };
+            let dbml = generate_dbml(&schema, &options);
+            let expected = format!("Table {} {{\n  {} int [pk]\n  {} nvarchar(10)\n}}\n", table, order, key);
+            assert!(dbml.starts_with(&expected), "--quote-style {}:\n{}", style, dbml);
+        }
+        assert_eq!(QuoteStyle::parse("mysql"), None);
+    }
+}
@@ -0,0 +1,169 @@
+use super::{
+    column_line, columns_summary, edge_groups, edge_label, find_table, is_one_to_one, navigation_hint, reference_label,
+    truncation_line, OutputFormat, RenderOptions,
+};
+use crate::error::DiagramError;
+use crate::model::{DatabaseSchema, Reference, Table};
+use std::collections::HashMap;
+
+/// Builds a port identifier for every column of `table`.
+///
+/// Column names may contain characters that are not valid in record ports
+/// (`order#`, spaces, ...), so they are reduced to `[A-Za-z0-9_]` and
+/// suffixed when two columns collapse to the same identifier.
+pub fn column_ports(table: &Table) -> HashMap<&str, String> {
+    let mut ports = HashMap::new();
+    let mut used: Vec<String> = Vec::new();
+    for column in &table.columns {
+        let base: String = column
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+            .collect();
+        let base = format!("c_{}", base);
+        let mut port = base.clone();
+        let mut suffix = 2;
+        while used.contains(&port) {
+            port = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        used.push(port.clone());
+        ports.insert(column.name.as_str(), port);
+    }
+    ports
+}
+
+fn quote(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn escape_record(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if matches!(c, '{' | '}' | '|' | '<' | '>' | '"' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+pub fn generate_dot(schema: &DatabaseSchema, options: &RenderOptions) -> String {
+    let mut dot = String::new();
+    dot.push_str("digraph schema {\n");
+    let indent = options.indent(2, 1);
+    dot.push_str(&format!("{}node [shape=record];\n", indent));
+
+    let mut ports: HashMap<(&str, &str), HashMap<&str, String>> = HashMap::new();
+    for table in &schema.tables {
+        let table_ports = column_ports(table);
+        let mut label = escape_record(options.display_name(&table.schema, &table.name));
+        for column in options.visible_columns(schema, table) {
+            label.push_str(&format!(
+                "|<{}> {}\\l",
+                table_ports[column.name.as_str()],
+                escape_record(&column_line(column, options))
+            ));
+        }
+        let truncated = options.truncated_columns(schema, table);
+        if truncated > 0 {
+            label.push_str(&format!("|{}\\l", escape_record(&truncation_line(truncated))));
+        }
+        if options.columns_summary && !table.external {
+            label.push_str(&format!("|{}\\l", escape_record(&columns_summary(schema, table))));
+        }
+        let style = if table.external { ", style=dashed" } else { "" };
+        dot.push_str(&format!("{}{} [label=\"{{{}}}\"{}];\n", indent, quote(&table.name), label, style));
+        ports.insert((table.schema.as_str(), table.name.as_str()), table_ports);
+    }
+
+    let endpoint = |table_schema: &str, table: &str, column: &str| {
+        let port = ports
+            .get(&(table_schema, table))
+            .and_then(|table_ports| table_ports.get(column))
+            .filter(|_| {
+                find_table(schema, table_schema, table).is_some_and(|model| options.shows_column(schema, model, column))
+            });
+        match port {
+            Some(port) => format!("{}:{}", quote(table), port),
+            None => quote(table),
+        }
+    };
+    let groups: Vec<Vec<&Reference>> = if options.merge_parallel_edges || options.verbose_labels {
+        edge_groups(&schema.references, options)
+    } else {
+        schema.references.iter().map(|reference| vec![reference]).collect()
+    };
+    for group in groups {
+        let mut style = String::new();
+        if group.iter().all(|reference| reference.is_disabled) {
+            style.push_str(", style=dashed");
+        }
+        if group.iter().any(|reference| !reference.references_pk) {
+            style.push_str(", arrowhead=odot");
+        }
+        if options.fk_cardinality {
+            let many = if is_one_to_one(schema, &group) { "0..1" } else { "*" };
+            style.push_str(&format!(", taillabel=\"{}\", headlabel=\"1\"", many));
+        }
+        let (from, to, mut label) = match group.as_slice() {
+            [reference] => (
+                endpoint(&reference.schema, &reference.table, &reference.column),
+                endpoint(&reference.referenced_schema, &reference.referenced_table, &reference.referenced_column),
+                reference_label(reference, options),
+            ),
+            _ => (quote(&group[0].table), quote(&group[0].referenced_table), edge_label(&group)),
+        };
+        if options.ef_hints {
+            label.push_str(&format!(" [{}]", navigation_hint(schema, &group)));
+        }
+        dot.push_str(&format!("{}{} -> {} [label={}{}];\n", indent, from, to, quote(&label), style));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+pub struct DotFormat;
+
+impl OutputFormat for DotFormat {
+    fn name(&self) -> &str {
+        "dot"
+    }
+
+    fn extension(&self) -> &str {
+        "dot"
+    }
+
+    fn comment(&self, line: &str) -> Option<String> {
+        Some(format!("// {}", line))
+    }
+
+    fn render(&self, schema: &DatabaseSchema, options: &RenderOptions) -> Result<String, DiagramError> {
+        Ok(generate_dot(schema, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{reference, schema, table};
+
+    #[test]
+    fn ports_for_unsafe_column_names_are_sanitized_and_kept_apart() {
+        let schema = schema(
+            vec![
+                table("dbo.Orders", &["order#", "order_"], &["order#"]),
+                table("dbo.Lines", &["Id", "order#"], &["Id"]),
+            ],
+            vec![reference("FK_Lines_Orders", "dbo.Lines.order#", "dbo.Orders.order_")],
+        );
+        let ports = column_ports(&schema.tables[0]);
+        assert_eq!(ports["order#"], "c_order_");
+        assert_eq!(ports["order_"], "c_order__2");
+
+        let dot = generate_dot(&schema, &RenderOptions::default());
+        let orders = "\"Orders\" [label=\"{Orders|<c_order_> order# : int\\l|<c_order__2> order_ : int\\l}\"];";
+        assert!(dot.contains(orders), "{}", dot);
+        assert!(dot.contains("\"Lines\":c_order_ -> \"Orders\":c_order__2 "), "{}", dot);
+    }
+}
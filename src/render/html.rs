@@ -0,0 +1,157 @@
+use super::{column_line, columns_summary, truncation_line, via_synonym, OutputFormat, RenderOptions};
+use crate::error::DiagramError;
+use crate::model::DatabaseSchema;
+
+/// Hides the tables whose name doesn't contain the search text, and the
+/// relationships that touch none of the tables left.
+const SCRIPT: &str = r#"const search = document.getElementById("search");
+search.addEventListener("input", () => {
+  const query = search.value.trim().toLowerCase();
+  for (const table of document.querySelectorAll("details.table")) {
+    table.hidden = query !== "" && !table.dataset.name.includes(query);
+  }
+  for (const relationship of document.querySelectorAll("li.relationship")) {
+    relationship.hidden = query !== ""
+      && !relationship.dataset.from.includes(query)
+      && !relationship.dataset.to.includes(query);
+  }
+});
+"#;
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+details.table { border: 1px solid #ccc; border-radius: 4px; margin: 0.5em 0; padding: 0.5em; }
+summary { cursor: pointer; font-weight: bold; }
+table { border-collapse: collapse; margin-top: 0.5em; }
+th, td { border: 1px solid #ddd; padding: 0.2em 0.6em; text-align: left; }
+#search { font-size: 1em; padding: 0.3em; width: 20em; }
+";
+
+/// Escapes the characters that are markup in HTML text and attribute values.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Renders a self-contained HTML page with a collapsible section per table,
+/// a list of relationships and a search box that filters both by table name.
+pub fn generate_html(schema: &DatabaseSchema, options: &RenderOptions) -> String {
+    let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Schema</title>\n");
+    html.push_str(&format!("<style>\n{}</style>\n", STYLE));
+    html.push_str("</head>\n<body>\n<h1>Schema</h1>\n");
+    html.push_str("<input type=\"search\" id=\"search\" placeholder=\"Filter tables\" aria-label=\"Filter tables\">\n");
+
+    html.push_str("<h2>Tables</h2>\n");
+    for table in &schema.tables {
+        let heading = format!("{}.{}", table.schema, table.name);
+        html.push_str(&format!(
+            "<details class=\"table\" data-name=\"{}\">\n<summary>{}</summary>\n",
+            escape(&heading.to_lowercase()),
+            escape(&heading)
+        ));
+        if let Some(description) = &table.description {
+            html.push_str(&format!("<p>{}</p>\n", escape(description)));
+        }
+        if options.columns_summary {
+            html.push_str(&format!("<p>{}</p>\n", escape(&columns_summary(schema, table))));
+        }
+        let columns = options.visible_columns(schema, table);
+        if !columns.is_empty() {
+            html.push_str("<table>\n<tr><th>Column</th><th>Key</th></tr>\n");
+            for column in columns {
+                let is_foreign_key = schema.references.iter().any(|reference| {
+                    reference.schema == table.schema && reference.table == table.name && reference.column == column.name
+                });
+                let key = match (table.primary_key.contains(&column.name), is_foreign_key) {
+                    (true, true) => "PK, FK",
+                    (true, false) => "PK",
+                    (false, true) => "FK",
+                    (false, false) => "",
+                };
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td></tr>\n",
+                    escape(&column_line(column, options)),
+                    key
+                ));
+            }
+            let truncated = options.truncated_columns(schema, table);
+            if truncated > 0 {
+                html.push_str(&format!("<tr><td colspan=\"2\">{}</td></tr>\n", truncation_line(truncated)));
+            }
+            html.push_str("</table>\n");
+        }
+        html.push_str("</details>\n");
+    }
+
+    html.push_str("<h2>Relationships</h2>\n<ul>\n");
+    for reference in &schema.references {
+        let from = format!("{}.{}", reference.schema, reference.table);
+        let to = format!("{}.{}", reference.referenced_schema, reference.referenced_table);
+        html.push_str(&format!(
+            "<li class=\"relationship\" data-from=\"{}\" data-to=\"{}\">{}.{} → {}.{} ({}{})</li>\n",
+            escape(&from.to_lowercase()),
+            escape(&to.to_lowercase()),
+            escape(&from),
+            escape(&reference.column),
+            escape(&to),
+            escape(&reference.referenced_column),
+            escape(&reference.constraint_name),
+            escape(&via_synonym(reference))
+        ));
+    }
+    html.push_str("</ul>\n");
+
+    html.push_str(&format!("<script>\n{}</script>\n</body>\n</html>\n", SCRIPT));
+    html
+}
+
+pub struct HtmlFormat;
+
+impl OutputFormat for HtmlFormat {
+    fn name(&self) -> &str {
+        "html"
+    }
+
+    fn extension(&self) -> &str {
+        "html"
+    }
+
+    fn comment(&self, line: &str) -> Option<String> {
+        Some(format!("<!-- {} -->", line.replace("-->", "- ->")))
+    }
+
+    fn render(&self, schema: &DatabaseSchema, options: &RenderOptions) -> Result<String, DiagramError> {
+        Ok(generate_html(schema, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{reference, schema, table};
+
+    #[test]
+    fn html_has_a_section_per_table_and_the_search_input() {
+        let schema = schema(
+            vec![
+                table("dbo.Customer", &["Id"], &["Id"]),
+                table("sales.Orders", &["Id", "CustomerId"], &["Id"]),
+            ],
+            vec![reference("FK_Orders_Customer", "sales.Orders.CustomerId", "dbo.Customer.Id")],
+        );
+        let html = generate_html(&schema, &RenderOptions::default());
+        assert!(html.contains("<input type=\"search\" id=\"search\""), "{}", html);
+        for (name, heading) in [("dbo.customer", "dbo.Customer"), ("sales.orders", "sales.Orders")] {
+            let section = format!("<details class=\"table\" data-name=\"{}\">\n<summary>{}</summary>", name, heading);
+            assert!(html.contains(&section), "{}", html);
+        }
+        assert!(html.contains(
+            "<li class=\"relationship\" data-from=\"sales.orders\" data-to=\"dbo.customer\">\
+             sales.Orders.CustomerId → dbo.Customer.Id (FK_Orders_Customer)</li>"
+        ));
+        assert!(!html.contains("src=\"http"), "the page must not load anything external");
+    }
+}
@@ -0,0 +1,130 @@
+use super::{OutputFormat, RenderOptions};
+use crate::error::DiagramError;
+use crate::model::{Column, DataType, DatabaseSchema, Table};
+use serde_json::Value;
+use std::io;
+
+/// The keys of a serialized `T`, in serde_json's sorted order.
+fn keys<T: serde::Serialize>(value: &T) -> Vec<String> {
+    match serde_json::to_value(value) {
+        Ok(Value::Object(map)) => map.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The field names of a table and of a column, as they appear in the JSON.
+fn field_names() -> (Vec<String>, Vec<String>) {
+    let column = Column {
+        name: String::new(),
+        data_type: DataType::new(""),
+        description: None,
+        bound_default: None,
+        bound_rule: None,
+        collation: None,
+        fulltext_catalog: None,
+        computed: false,
+    };
+    (keys(&Table::external_stub("", "")), keys(&column))
+}
+
+/// Parses a `--json-fields` list such as `name,data_type`, rejecting names
+/// that are neither a table nor a column field.
+pub fn parse_json_fields(list: &str) -> Result<Vec<String>, String> {
+    let (table_fields, column_fields) = field_names();
+    let fields: Vec<String> = list
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(str::to_string)
+        .collect();
+    if let Some(unknown) = fields
+        .iter()
+        .find(|field| !table_fields.contains(field) && !column_fields.contains(field))
+    {
+        let mut known: Vec<&String> = table_fields.iter().chain(&column_fields).collect();
+        known.sort_unstable();
+        known.dedup();
+        let known: Vec<&str> = known.into_iter().map(String::as_str).collect();
+        return Err(format!("unknown field '{}'; expected one of {}", unknown, known.join(", ")));
+    }
+    Ok(fields)
+}
+
+/// Serializes the whole model as JSON. With `fields`, each table keeps only
+/// the listed table fields, plus `columns` when any column field is listed,
+/// and each column only the listed column fields; listing just `columns`
+/// keeps them whole. References are kept whole.
+pub fn generate_json(schema: &DatabaseSchema, fields: &[String]) -> Result<String, DiagramError> {
+    let to_io = |err: serde_json::Error| DiagramError::Io(io::Error::other(err));
+    let mut value = serde_json::to_value(schema).map_err(to_io)?;
+    if !fields.is_empty() {
+        let (_, column_fields) = field_names();
+        let project_columns = fields.iter().any(|field| column_fields.contains(field));
+        let tables = value.get_mut("tables").and_then(Value::as_array_mut).into_iter().flatten();
+        for table in tables.filter_map(Value::as_object_mut) {
+            table.retain(|key, _| fields.contains(key) || (key == "columns" && project_columns));
+            if !project_columns {
+                continue;
+            }
+            let columns = table.get_mut("columns").and_then(Value::as_array_mut).into_iter().flatten();
+            for column in columns.filter_map(Value::as_object_mut) {
+                column.retain(|key, _| fields.contains(key));
+            }
+        }
+    }
+    let mut json = serde_json::to_string_pretty(&value).map_err(to_io)?;
+    json.push('\n');
+    Ok(json)
+}
+
+pub struct JsonFormat;
+
+impl OutputFormat for JsonFormat {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn extension(&self) -> &str {
+        "json"
+    }
+
+    fn exports_model(&self) -> bool {
+        true
+    }
+
+    fn render(&self, schema: &DatabaseSchema, options: &RenderOptions) -> Result<String, DiagramError> {
+        generate_json(schema, &options.json_fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{schema, table};
+
+    fn sorted_keys(value: &Value) -> Vec<&str> {
+        value.as_object().unwrap().keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn json_fields_project_tables_and_columns_to_the_listed_keys() {
+        let schema = schema(vec![table("dbo.Customer", &["Id", "Name:nvarchar(50)"], &["Id"])], Vec::new());
+        let fields = parse_json_fields("name, data_type,columns").unwrap();
+        let value: Value = serde_json::from_str(&generate_json(&schema, &fields).unwrap()).unwrap();
+        let customer = &value["tables"][0];
+        assert_eq!(sorted_keys(customer), ["columns", "name"]);
+        assert_eq!(customer["name"], "Customer");
+        let name = &customer["columns"][1];
+        assert_eq!(sorted_keys(name), ["data_type", "name"]);
+        assert_eq!(name["data_type"]["base"], "nvarchar");
+        // References are not projected
+        assert!(value["references"].is_array());
+    }
+
+    #[test]
+    fn unknown_json_fields_are_rejected() {
+        let err = parse_json_fields("name,colour").unwrap_err();
+        assert!(err.starts_with("unknown field 'colour'; expected one of"), "{}", err);
+        assert!(err.contains("data_type") && err.contains("primary_key"), "{}", err);
+    }
+}
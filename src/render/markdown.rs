@@ -0,0 +1,193 @@
+use super::{column_line, OutputFormat, RenderOptions};
+use crate::error::DiagramError;
+use crate::model::DatabaseSchema;
+use std::collections::{BTreeMap, HashMap};
+
+/// Hands out heading anchors the way GitHub does: the lowercased heading with
+/// punctuation dropped and spaces turned into hyphens, suffixed `-1`, `-2`,
+/// ... when the same anchor comes up again.
+#[derive(Default)]
+struct Slugger {
+    seen: HashMap<String, usize>,
+}
+
+impl Slugger {
+    fn slug(&mut self, heading: &str) -> String {
+        let base: String = heading
+            .to_lowercase()
+            .chars()
+            .filter_map(|c| match c {
+                ' ' => Some('-'),
+                '-' | '_' => Some(c),
+                c if c.is_alphanumeric() => Some(c),
+                _ => None,
+            })
+            .collect();
+        let mut slug = base.clone();
+        while self.seen.contains_key(&slug) {
+            let count = self.seen.get_mut(&base).expect("the base anchor was seen first");
+            *count += 1;
+            slug = format!("{}-{}", base, count);
+        }
+        self.seen.insert(slug.clone(), 0);
+        slug
+    }
+}
+
+/// Escapes the characters that would end a table cell or link text early.
+pub(super) fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+}
+
+/// Renders a Markdown document with a section per table, preceded by a table
+/// of contents grouped by schema that links to each section.
+pub fn generate_markdown(schema: &DatabaseSchema, options: &RenderOptions) -> String {
+    let mut slugger = Slugger::default();
+    slugger.slug("Schema");
+    slugger.slug("Contents");
+
+    let mut sections = String::new();
+    let mut contents: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for table in &schema.tables {
+        let heading = format!("{}.{}", table.schema, table.name);
+        let anchor = slugger.slug(&heading);
+        contents
+            .entry(&table.schema)
+            .or_default()
+            .push(format!("  - [{}](#{})", escape(&table.name), anchor));
+
+        sections.push_str(&format!("## {}\n\n", heading));
+        if let Some(description) = &table.description {
+            sections.push_str(&format!("{}\n\n", description));
+        }
+        let columns = options.visible_columns(schema, table);
+        if !columns.is_empty() {
+            sections.push_str("| Column | Key |\n| --- | --- |\n");
+            for column in columns {
+                let is_foreign_key = schema.references.iter().any(|reference| {
+                    reference.schema == table.schema && reference.table == table.name && reference.column == column.name
+                });
+                let key = match (table.primary_key.contains(&column.name), is_foreign_key) {
+                    (true, true) => "PK, FK",
+                    (true, false) => "PK",
+                    (false, true) => "FK",
+                    (false, false) => "",
+                };
+                sections.push_str(&format!("| {} | {} |\n", escape(&column_line(column, options)), key));
+            }
+            sections.push('\n');
+        }
+        let references: Vec<String> = schema
+            .references
+            .iter()
+            .filter(|reference| reference.schema == table.schema && reference.table == table.name)
+            .map(|reference| {
+                format!(
+                    "- {} → {}.{} ({})\n",
+                    escape(&reference.column),
+                    escape(&reference.referenced_table),
+                    escape(&reference.referenced_column),
+                    escape(&reference.constraint_name)
+                )
+            })
+            .collect();
+        if !references.is_empty() {
+            sections.push_str("References:\n\n");
+            sections.push_str(&references.concat());
+            sections.push('\n');
+        }
+    }
+
+    let mut markdown = String::from("# Schema\n\n## Contents\n\n");
+    for (schema_name, entries) in contents {
+        markdown.push_str(&format!("- {}\n", escape(schema_name)));
+        for entry in entries {
+            markdown.push_str(&entry);
+            markdown.push('\n');
+        }
+    }
+    markdown.push('\n');
+    markdown.push_str(&sections);
+    markdown
+}
+
+pub struct MarkdownFormat;
+
+impl OutputFormat for MarkdownFormat {
+    fn name(&self) -> &str {
+        "markdown"
+    }
+
+    fn extension(&self) -> &str {
+        "md"
+    }
+
+    fn comment(&self, line: &str) -> Option<String> {
+        Some(format!("<!-- {} -->", line.replace("-->", "- ->")))
+    }
+
+    fn render(&self, schema: &DatabaseSchema, options: &RenderOptions) -> Result<String, DiagramError> {
+        Ok(generate_markdown(schema, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{schema, table};
+
+    #[test]
+    fn slugs_follow_github() {
+        let mut slugger = Slugger::default();
+        assert_eq!(slugger.slug("sales.Order Lines"), "salesorder-lines");
+        assert_eq!(slugger.slug("dbo.Straße_2"), "dbostraße_2");
+        assert_eq!(slugger.slug("a"), "a");
+        assert_eq!(slugger.slug("a-1"), "a-1");
+        assert_eq!(slugger.slug("a"), "a-2");
+        assert_eq!(slugger.slug("A"), "a-3");
+    }
+
+    #[test]
+    fn contents_links_match_the_section_headings() {
+        let schema = schema(
+            vec![
+                table("sales.Order", &["Id"], &["Id"]),
+                table("dbo.Contents", &["Id"], &["Id"]),
+                table("sales.order", &["Id"], &["Id"]),
+                table("dbo.Audit|Log", &[], &[]),
+            ],
+            Vec::new(),
+        );
+        let markdown = generate_markdown(&schema, &RenderOptions::default());
+        let contents: Vec<&str> = markdown.lines().skip(4).take_while(|line| !line.is_empty()).collect();
+        assert_eq!(
+            contents,
+            [
+                "- dbo",
+                "  - [Contents](#dbocontents)",
+                "  - [Audit\\|Log](#dboauditlog)",
+                "- sales",
+                "  - [Order](#salesorder)",
+                "  - [order](#salesorder-1)",
+            ]
+        );
+
+        // Each anchor is the one GitHub gives its heading, after the two above
+        let mut slugger = Slugger::default();
+        slugger.slug("Schema");
+        slugger.slug("Contents");
+        let anchors: Vec<(String, String)> = markdown
+            .lines()
+            .filter_map(|line| line.strip_prefix("## "))
+            .skip(1)
+            .map(|heading| (heading.to_string(), slugger.slug(heading)))
+            .collect();
+        assert_eq!(anchors.len(), 4);
+        for (heading, anchor) in anchors {
+            assert!(contents.iter().any(|line| line.ends_with(&format!("(#{})", anchor))), "{} has no link", heading);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use super::markdown::escape;
+use super::{OutputFormat, RenderOptions};
+use crate::error::DiagramError;
+use crate::model::DatabaseSchema;
+
+fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The schema-qualified table names and, for each pair of them, how many
+/// foreign key columns the first has referencing the second.
+fn reference_counts(schema: &DatabaseSchema) -> (Vec<String>, Vec<Vec<usize>>) {
+    let position = |schema_name: &str, name: &str| {
+        schema.tables.iter().position(|table| table.schema == schema_name && table.name == name)
+    };
+    let mut counts = vec![vec![0usize; schema.tables.len()]; schema.tables.len()];
+    for reference in &schema.references {
+        let from = position(&reference.schema, &reference.table);
+        let to = position(&reference.referenced_schema, &reference.referenced_table);
+        if let (Some(from), Some(to)) = (from, to) {
+            counts[from][to] += 1;
+        }
+    }
+    let names = schema.tables.iter().map(|table| format!("{}.{}", table.schema, table.name)).collect();
+    (names, counts)
+}
+
+pub fn generate_matrix(schema: &DatabaseSchema, _options: &RenderOptions) -> String {
+    let (names, counts) = reference_counts(schema);
+    let mut matrix = String::new();
+    matrix.push_str("table");
+    for name in &names {
+        matrix.push_str(&format!(",{}", csv_field(name)));
+    }
+    matrix.push('\n');
+    for (name, row) in names.iter().zip(&counts) {
+        matrix.push_str(&csv_field(name));
+        for count in row {
+            matrix.push_str(&format!(",{}", count));
+        }
+        matrix.push('\n');
+    }
+    matrix
+}
+
+/// The adjacency matrix as a Markdown table, rows referencing columns.
+pub fn generate_matrix_markdown(schema: &DatabaseSchema, _options: &RenderOptions) -> String {
+    let (names, counts) = reference_counts(schema);
+    let mut matrix = String::new();
+    matrix.push_str("| table |");
+    for name in &names {
+        matrix.push_str(&format!(" {} |", escape(name)));
+    }
+    matrix.push_str("\n| --- |");
+    matrix.push_str(&" ---: |".repeat(names.len()));
+    matrix.push('\n');
+    for (name, row) in names.iter().zip(&counts) {
+        matrix.push_str(&format!("| {} |", escape(name)));
+        for count in row {
+            matrix.push_str(&format!(" {} |", count));
+        }
+        matrix.push('\n');
+    }
+    matrix
+}
+
+pub struct MatrixFormat;
+
+impl OutputFormat for MatrixFormat {
+    fn name(&self) -> &str {
+        "matrix"
+    }
+
+    fn extension(&self) -> &str {
+        "matrix.csv"
+    }
+
+    fn render(&self, schema: &DatabaseSchema, options: &RenderOptions) -> Result<String, DiagramError> {
+        Ok(generate_matrix(schema, options))
+    }
+}
+
+pub struct MatrixMarkdownFormat;
+
+impl OutputFormat for MatrixMarkdownFormat {
+    fn name(&self) -> &str {
+        "matrix-markdown"
+    }
+
+    fn extension(&self) -> &str {
+        "matrix.md"
+    }
+
+    fn comment(&self, line: &str) -> Option<String> {
+        Some(format!("<!-- {} -->", line.replace("-->", "- ->")))
+    }
+
+    fn render(&self, schema: &DatabaseSchema, options: &RenderOptions) -> Result<String, DiagramError> {
+        Ok(generate_matrix_markdown(schema, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{reference, schema, table};
+
+    fn sample() -> DatabaseSchema {
+        schema(
+            vec![
+                table("dbo.Customer", &["Id"], &["Id"]),
+                table("sales.Customer", &["Id", "ParentId"], &["Id"]),
+                table("sales.Order", &["Id", "CustomerId", "ShipToId", "BillToId"], &["Id"]),
+            ],
+            vec![
+                reference("FK_Order_ShipTo", "sales.Order.ShipToId", "sales.Customer.Id"),
+                reference("FK_Order_BillTo", "sales.Order.BillToId", "sales.Customer.Id"),
+                reference("FK_Order_Customer", "sales.Order.CustomerId", "dbo.Customer.Id"),
+                reference("FK_Customer_Parent", "sales.Customer.ParentId", "sales.Customer.Id"),
+            ],
+        )
+    }
+
+    #[test]
+    fn counts_foreign_key_columns_per_schema_qualified_pair() {
+        assert_eq!(
+            generate_matrix(&sample(), &RenderOptions::default()),
+            "table,dbo.Customer,sales.Customer,sales.Order\n\
+             dbo.Customer,0,0,0\n\
+             sales.Customer,0,1,0\n\
+             sales.Order,1,2,0\n"
+        );
+    }
+
+    #[test]
+    fn markdown_matrix_has_the_same_cells() {
+        assert_eq!(
+            generate_matrix_markdown(&sample(), &RenderOptions::default()),
+            "| table | dbo.Customer | sales.Customer | sales.Order |\n\
+             | --- | ---: | ---: | ---: |\n\
+             | dbo.Customer | 0 | 0 | 0 |\n\
+             | sales.Customer | 0 | 1 | 0 |\n\
+             | sales.Order | 1 | 2 | 0 |\n"
+        );
+    }
+}
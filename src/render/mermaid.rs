@@ -0,0 +1,221 @@
+use super::{edge_groups, edge_label, is_one_to_one, navigation_hint, OutputFormat, RenderOptions};
+use crate::error::DiagramError;
+use crate::model::{DatabaseSchema, Table};
+use std::collections::HashMap;
+
+/// Reduces a name to the characters Mermaid accepts in identifiers.
+fn mermaid_identifier(name: &str) -> String {
+    let mut identifier: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+    identifier
+}
+
+/// An identifier per table, keyed by schema and name, built from `base`.
+/// Tables whose names reduce to the same identifier, or to `end`, which
+/// closes a subgraph, get a numbered suffix.
+fn table_identifiers(schema: &DatabaseSchema, base: impl Fn(&Table) -> String) -> HashMap<(&str, &str), String> {
+    let mut ids: HashMap<(&str, &str), String> = HashMap::new();
+    let mut used: Vec<String> = Vec::new();
+    for table in &schema.tables {
+        let base = mermaid_identifier(&base(table));
+        let mut id = base.clone();
+        let mut suffix = 2;
+        while id.eq_ignore_ascii_case("end") || used.contains(&id) {
+            id = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        used.push(id.clone());
+        ids.insert((table.schema.as_str(), table.name.as_str()), id);
+    }
+    ids
+}
+
+/// Draws an erDiagram with an entity per table, identified by its
+/// schema-qualified name.
+pub fn generate_mermaid(schema: &DatabaseSchema, options: &RenderOptions) -> String {
+    let (indent, nested) = (options.indent(4, 1), options.indent(4, 2));
+    let mut mermaid = String::new();
+    mermaid.push_str("erDiagram\n");
+    let ids = table_identifiers(schema, |table| format!("{}.{}", table.schema, table.name));
+    // A referenced table left out of the diagram keeps its qualified name
+    let id = |table_schema: &str, table_name: &str| match ids.get(&(table_schema, table_name)) {
+        Some(id) => id.clone(),
+        None => mermaid_identifier(&format!("{}.{}", table_schema, table_name)),
+    };
+    for table in &schema.tables {
+        let columns = options.visible_columns(schema, table);
+        if columns.is_empty() {
+            mermaid.push_str(&format!("{}{}\n", indent, id(&table.schema, &table.name)));
+            continue;
+        }
+        mermaid.push_str(&format!("{}{} {{\n", indent, id(&table.schema, &table.name)));
+        for column in columns {
+            mermaid.push_str(&format!(
+                "{}{} {}\n",
+                nested,
+                mermaid_identifier(&column.data_type.base),
+                mermaid_identifier(&column.name)
+            ));
+        }
+        mermaid.push_str(&format!("{}}}\n", indent));
+    }
+    for group in edge_groups(&schema.references, options) {
+        let mut label = edge_label(&group);
+        if group.iter().any(|reference| !reference.references_pk) {
+            label.push_str(" (unique)");
+        }
+        if options.ef_hints {
+            label.push_str(&format!(" [{}]", navigation_hint(schema, &group)));
+        }
+        let line = if group.iter().all(|reference| reference.is_disabled) { ".." } else { "--" };
+        let many = if options.fk_cardinality && is_one_to_one(schema, &group) { "|o" } else { "}o" };
+        mermaid.push_str(&format!(
+            "{}{} {}{}|| {} : \"{}\"\n",
+            indent,
+            id(&group[0].schema, &group[0].table),
+            many,
+            line,
+            id(&group[0].referenced_schema, &group[0].referenced_table),
+            label.replace('"', "'")
+        ));
+    }
+    mermaid
+}
+
+/// Draws the table dependency graph as a Mermaid `flowchart LR`: a node per
+/// table labelled with its name, schema-qualified when another schema has a
+/// table of that name, and an arrow from each referencing table to the table
+/// it references, without columns.
+pub fn generate_mermaid_flow(schema: &DatabaseSchema, options: &RenderOptions) -> String {
+    let indent = options.indent(4, 1);
+    let mut flow = String::from("flowchart LR\n");
+    let ids = table_identifiers(schema, |table| table.name.clone());
+    for table in &schema.tables {
+        let id = &ids[&(table.schema.as_str(), table.name.as_str())];
+        let mut label = options.display_name(&table.schema, &table.name).to_string();
+        if schema.tables.iter().filter(|other| other.name == table.name).count() > 1 {
+            label = format!("{}.{}", table.schema, label);
+        }
+        let label = label.replace('"', "#quot;");
+        flow.push_str(&format!("{}{}[\"{}\"]\n", indent, id, label));
+    }
+    for group in edge_groups(&schema.references, options) {
+        let first = group[0];
+        let from = ids.get(&(first.schema.as_str(), first.table.as_str()));
+        let to = ids.get(&(first.referenced_schema.as_str(), first.referenced_table.as_str()));
+        if let Some((from, to)) = from.zip(to) {
+            let arrow = if group.iter().all(|reference| reference.is_disabled) { "-.->" } else { "-->" };
+            flow.push_str(&format!("{}{} {} {}\n", indent, from, arrow, to));
+        }
+    }
+    flow
+}
+
+pub struct MermaidFormat;
+
+impl OutputFormat for MermaidFormat {
+    fn name(&self) -> &str {
+        "mermaid"
+    }
+
+    fn extension(&self) -> &str {
+        "mmd"
+    }
+
+    fn comment(&self, line: &str) -> Option<String> {
+        Some(format!("%% {}", line))
+    }
+
+    fn render(&self, schema: &DatabaseSchema, options: &RenderOptions) -> Result<String, DiagramError> {
+        Ok(generate_mermaid(schema, options))
+    }
+}
+
+pub struct MermaidFlowFormat;
+
+impl OutputFormat for MermaidFlowFormat {
+    fn name(&self) -> &str {
+        "mermaid-flow"
+    }
+
+    fn extension(&self) -> &str {
+        "flow.mmd"
+    }
+
+    fn comment(&self, line: &str) -> Option<String> {
+        Some(format!("%% {}", line))
+    }
+
+    fn render(&self, schema: &DatabaseSchema, options: &RenderOptions) -> Result<String, DiagramError> {
+        Ok(generate_mermaid_flow(schema, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{reference, schema, table};
+
+    #[test]
+    fn entities_are_schema_qualified_and_unique() {
+        let schema = schema(
+            vec![
+                table("dbo.Order", &["Id"], &["Id"]),
+                table("sales.Order", &["Id", "LegacyId"], &["Id"]),
+                table("a.b_c", &[], &[]),
+                table("a_b.c", &[], &[]),
+            ],
+            vec![reference("FK_Order_Legacy", "sales.Order.LegacyId", "dbo.Order.Id")],
+        );
+        assert_eq!(
+            generate_mermaid(&schema, &RenderOptions::default()),
+            "erDiagram\n    \
+             dbo_Order {\n        int Id\n    }\n    \
+             sales_Order {\n        int Id\n        int LegacyId\n    }\n    \
+             a_b_c\n    \
+             a_b_c_2\n    \
+             sales_Order }o--|| dbo_Order : \"LegacyId → Id\"\n"
+        );
+    }
+
+    #[test]
+    fn identifiers_avoid_end_and_leading_digits() {
+        assert_eq!(mermaid_identifier("2024 Sales"), "_2024_Sales");
+        let schema = schema(vec![table("dbo.end", &[], &[]), table("sales.end", &[], &[])], Vec::new());
+        assert_eq!(
+            generate_mermaid_flow(&schema, &RenderOptions::default()),
+            "flowchart LR\n    end_2[\"dbo.end\"]\n    end_3[\"sales.end\"]\n"
+        );
+    }
+
+    #[test]
+    fn flowcharts_draw_one_arrow_per_foreign_key_without_columns() {
+        let schema = schema(
+            vec![
+                table("dbo.Customer", &["Id", "Name:nvarchar(50)"], &["Id"]),
+                table("dbo.Orders", &["Id", "CustomerId", "ShipToId"], &["Id"]),
+                table("dbo.Order Line", &["Id", "OrderId"], &["Id"]),
+            ],
+            vec![
+                reference("FK_Orders_Customer", "dbo.Orders.CustomerId", "dbo.Customer.Id"),
+                reference("FK_Orders_ShipTo", "dbo.Orders.ShipToId", "dbo.Customer.Id"),
+                reference("FK_Line_Orders", "dbo.Order Line.OrderId", "dbo.Orders.Id"),
+            ],
+        );
+        assert_eq!(
+            generate_mermaid_flow(&schema, &RenderOptions::default()),
+            "flowchart LR\n    \
+             Customer[\"Customer\"]\n    \
+             Orders[\"Orders\"]\n    \
+             Order_Line[\"Order Line\"]\n    \
+             Orders --> Customer\n    \
+             Orders --> Customer\n    \
+             Order_Line --> Orders\n"
+        );
+    }
+}
@@ -0,0 +1,1030 @@
+mod case;
+mod dbml;
+mod dot;
+mod html;
+mod json;
+mod markdown;
+mod matrix;
+mod mermaid;
+mod plantuml;
+mod plantuml_json;
+mod text;
+mod yaml;
+
+pub use case::IdentifierCase;
+pub use dbml::{generate_dbml, DbmlFormat, QuoteStyle};
+pub use dot::{column_ports, generate_dot, DotFormat};
+pub use html::{generate_html, HtmlFormat};
+pub use json::{generate_json, parse_json_fields, JsonFormat};
+pub use markdown::{generate_markdown, MarkdownFormat};
+pub use matrix::{generate_matrix, generate_matrix_markdown, MatrixFormat, MatrixMarkdownFormat};
+pub use mermaid::{generate_mermaid, generate_mermaid_flow, MermaidFlowFormat, MermaidFormat};
+pub use plantuml::{
+    generate_plantuml, render_plantuml, ArrowStyle, AttributeIcons, ColorBy, Layout, PlantUmlFormat, PlantUmlStream,
+    ReferenceStyle,
+};
+pub use plantuml_json::{generate_plantuml_json, PlantUmlJsonFormat};
+pub use text::{generate_text, TextFormat};
+pub use yaml::{generate_yaml, YamlFormat};
+
+use crate::error::DiagramError;
+use crate::filter::{glob_match, NamePattern};
+use crate::model::{Column, DatabaseSchema, Reference, ServerInfo, Table};
+use std::io::Write;
+
+/// Extended property listing the columns a table should show when
+/// `respect_column_hints` is set, e.g. `Id, Name, Status`.
+pub const COLUMN_HINTS_PROPERTY: &str = "DiagramColumns";
+
+/// Column name globs flagged as personal data when `--pii-patterns` is given
+/// without a list of its own.
+pub const DEFAULT_PII_PATTERNS: &str =
+    "ssn,*_ssn,email,*_email,*email_address,dob,*_dob,*birth_date,*birthdate,phone,*_phone,*phone_number,passport*,*tax_id";
+
+/// An output format selectable with `--format`.
+pub trait OutputFormat: Sync {
+    /// The name used to select the format on the command line.
+    fn name(&self) -> &str;
+
+    /// The extension of the files this format produces.
+    fn extension(&self) -> &str;
+
+    /// Whether the format serializes the model as extracted, so `--case`
+    /// leaves its names alone.
+    fn exports_model(&self) -> bool {
+        false
+    }
+
+    /// Formats `line` as a comment, or `None` when the format has no comment
+    /// syntax.
+    fn comment(&self, _line: &str) -> Option<String> {
+        None
+    }
+
+    fn render(&self, schema: &DatabaseSchema, options: &RenderOptions) -> Result<String, DiagramError>;
+
+    /// Writes the rendered output; formats that can stream override this.
+    fn write(&self, schema: &DatabaseSchema, writer: &mut dyn Write, options: &RenderOptions) -> Result<(), DiagramError> {
+        writer.write_all(self.render(schema, options)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// The `header_comment` lines as comments for the top of a file in
+/// `format`, each ending in a newline. Empty for formats without comments.
+pub fn header_banner(format: &dyn OutputFormat, options: &RenderOptions) -> String {
+    options
+        .header_comment
+        .iter()
+        .flat_map(|text| text.lines())
+        .filter_map(|line| format.comment(line))
+        .map(|comment| comment + "\n")
+        .collect()
+}
+
+/// Every registered output format, in the order they are listed in `--help`.
+pub static FORMATS: &[&dyn OutputFormat] = &[
+    &PlantUmlFormat,
+    &MermaidFormat,
+    &MermaidFlowFormat,
+    &DotFormat,
+    &DbmlFormat,
+    &MatrixFormat,
+    &MatrixMarkdownFormat,
+    &TextFormat,
+    &YamlFormat,
+    &JsonFormat,
+    &MarkdownFormat,
+    &HtmlFormat,
+    &PlantUmlJsonFormat,
+];
+
+/// Looks up a registered format by its `--format` name.
+pub fn lookup_format(name: &str) -> Option<&'static dyn OutputFormat> {
+    FORMATS.iter().copied().find(|format| format.name() == name)
+}
+
+/// The names of all registered formats.
+pub fn format_names() -> Vec<&'static str> {
+    FORMATS.iter().map(|format| format.name()).collect()
+}
+
+/// Rendering-time settings shared by the generators, kept separate from
+/// `ExtractOptions` so the same model can be rendered in different ways.
+#[derive(Clone, Default)]
+pub struct RenderOptions {
+    /// Draws tables as bare nodes with only their relationships.
+    pub relationships_only: bool,
+    /// Limits column lists to names matching these globs, plus key columns.
+    pub columns_match: Vec<String>,
+    /// Leaves columns matching these globs, such as `RowVersion` or
+    /// `Modified*`, out of every table's column list unless they are key
+    /// columns. The JSON and YAML exports keep them.
+    pub hide_columns: Vec<String>,
+    /// Shows at most this many columns per table, plus any key columns, and
+    /// a line counting the rest.
+    pub max_columns_per_table: Option<usize>,
+    /// Shows only the columns among each table's first N by ordinal
+    /// position, plus any key columns, and a line counting the rest.
+    pub first_columns: Option<usize>,
+    /// Lays out PlantUML class bodies of more than 20 columns as this many
+    /// side-by-side lists, key columns first.
+    pub wrap_columns: Option<usize>,
+    /// Shortens PlantUML class labels to this many characters, wiring the
+    /// truncated classes up through `t1`, `t2`, ... aliases.
+    pub max_table_name_length: Option<usize>,
+    /// Tables drawn as bare nodes while the rest keep their columns.
+    pub no_columns_for: Vec<NamePattern>,
+    /// Display names from `--rename-map`, used for PlantUML and DOT labels
+    /// only; relationships stay wired by physical name. The first matching
+    /// table pattern wins.
+    pub display_names: Vec<(NamePattern, String)>,
+    /// Matches `no_columns_for` patterns and column hints case-sensitively.
+    pub case_sensitive: bool,
+    /// Limits tables carrying a `DiagramColumns` property to the listed columns.
+    pub respect_column_hints: bool,
+    /// Tightens the PlantUML layout with `hide empty members` and `hide circle`.
+    pub compact: bool,
+    /// Replaces PlantUML and DOT column lists with a `columns_summary` line.
+    pub columns_summary: bool,
+    /// Leaves data types out of attribute lists where the format allows it.
+    pub hide_types: bool,
+    /// Marks tables without a primary key with a `<<no-pk>>` stereotype.
+    pub highlight_no_pk: bool,
+    /// Marks edges that are part of a circular foreign-key chain.
+    pub highlight_cycles: bool,
+    /// Adds a floating note listing table descriptions.
+    pub purpose_legend: bool,
+    /// Attaches table and column descriptions to PlantUML classes and
+    /// attributes as `[[{...}]]` tooltips, shown on hover in SVG output.
+    pub svg_tooltips: bool,
+    /// Adds a floating note tallying columns by base data type.
+    pub type_legend: bool,
+    /// Adds a floating note naming the server, version and database the
+    /// diagram was extracted from.
+    pub server_info: Option<ServerInfo>,
+    /// Lines of a floating PlantUML note recording the tool version and the
+    /// options the diagram was generated with; no note when empty.
+    pub provenance: Vec<String>,
+    /// Shows each table's filegroup in its PlantUML class header.
+    pub show_filegroups: bool,
+    /// Leaves out the filegroup of tables stored on `PRIMARY`.
+    pub hide_primary_filegroup: bool,
+    /// Marks tables that have triggers in their PlantUML class header.
+    pub show_triggers: bool,
+    /// Links each system-versioned table to its history table with a dotted
+    /// PlantUML `<<history>>` edge, when both are drawn.
+    pub history_links: bool,
+    /// Draws up to this many stored procedures as PlantUML `<<proc>>` nodes
+    /// with dashed edges to the tables they reference.
+    pub proc_dependencies: Option<usize>,
+    /// Draws views as PlantUML `<<view>>` nodes with dashed `depends on`
+    /// edges to the tables they select from, and synonyms as `<<synonym>>`
+    /// nodes with dashed `alias` edges to their base tables.
+    pub resolve_indirection: bool,
+    /// Lists each table's indexes below its columns.
+    pub show_indexes: bool,
+    /// Adds a `<<rows: N>>` stereotype to PlantUML class headers.
+    pub show_row_counts: bool,
+    /// Adds a `<<size: N MB>>` stereotype to PlantUML class headers.
+    pub show_sizes: bool,
+    /// Appends the approximate child-to-parent row ratio (e.g. `~100:1`) to
+    /// PlantUML relationship labels.
+    pub cardinality_hints: bool,
+    /// Draws foreign keys that make up the child's whole primary key as
+    /// PlantUML generalizations (`Parent <|-- Child`).
+    pub detect_inheritance: bool,
+    /// Annotates columns whose collation differs from the database default.
+    pub highlight_collations: bool,
+    /// Marks columns whose names match these globs, case-insensitively, with
+    /// a `<<PII>>` marker.
+    pub pii_patterns: Vec<String>,
+    /// Marks tables modified after this `YYYY-MM-DDTHH:MM:SS` time with a
+    /// `<<modified>>` stereotype.
+    pub modified_since: Option<String>,
+    /// Adds a `<<last used: YYYY-MM-DD>>` stereotype, or `<<no usage stats>>`
+    /// for tables without usage statistics, to PlantUML class headers.
+    pub show_usage: bool,
+    /// Marks tables last used before this `YYYY-MM-DDTHH:MM:SS` time with a
+    /// `<<cold>>` stereotype.
+    pub cold_since: Option<String>,
+    /// Marks PlantUML relationships whose two columns differ in data type
+    /// with `<<type mismatch>>`.
+    pub highlight_type_mismatches: bool,
+    /// Tags PlantUML key columns with PK/FK/U stereotype spots.
+    pub key_spots: bool,
+    /// Marks PlantUML columns in both the primary key and a foreign key with
+    /// one `PFK` spot, in place of separate PK and FK spots.
+    pub pfk_marker: bool,
+    /// Tags the columns of each composite PlantUML primary key `<<PK1>>` and
+    /// those of composite unique indexes `<<UK1>>`, `<<UK2>>`, ..., with a
+    /// note beside the class listing each key's columns together.
+    pub group_composite_keys: bool,
+    /// Prefixes PlantUML members with an icon per role, such as a key for
+    /// primary key columns, leaving plain columns unadorned.
+    pub attribute_icons: Option<AttributeIcons>,
+    /// Identifier quoting used by the DBML generator.
+    pub quote_style: QuoteStyle,
+    /// Connector used for PlantUML foreign key edges.
+    pub arrow: ArrowStyle,
+    /// Whether PlantUML edges connect columns or class headers.
+    pub reference_style: ReferenceStyle,
+    /// Ranks PlantUML classes by `analysis::layers`, referenced tables above
+    /// the tables referencing them, grouping each layer in a `together` block.
+    pub layered: bool,
+    /// Replaces the PlantUML edges of tables with more relationships than
+    /// this with a note listing the related tables.
+    pub max_edges_per_table: Option<usize>,
+    /// Colours PlantUML class backgrounds, e.g. by schema.
+    pub color_by: ColorBy,
+    /// Seeds the arbitrary choices of `color_by` and `layered`.
+    pub seed: Option<u64>,
+    /// Draws one edge per pair of related tables, even when several foreign
+    /// keys link them.
+    pub merge_parallel_edges: bool,
+    /// Labels edges with both sides of each column pair (`a → b`), drawing a
+    /// composite foreign key as one edge listing all of its pairs.
+    pub verbose_labels: bool,
+    /// Appends the Entity Framework navigation properties each foreign key
+    /// would scaffold to, such as `Order.Customer ↔ Customer.Orders`, to its
+    /// edge label.
+    pub ef_hints: bool,
+    /// Marks each relationship one-to-one or one-to-many from whether the
+    /// foreign key's referencing columns are unique together: `"0..1"` or
+    /// `"*"` against `"1"` in PlantUML and DOT, `|o` or `}o` in Mermaid.
+    pub fk_cardinality: bool,
+    /// Direction PlantUML lays the diagram out in.
+    pub layout: Layout,
+    /// A hub table whose PlantUML edges get direction hints that spread its
+    /// neighbours around it.
+    pub pivot: Option<NamePattern>,
+    /// Wraps each cluster of related PlantUML classes in a `together` block.
+    /// Ignored when grouping by property, since packages already cluster.
+    pub together: bool,
+    /// Wraps each community of tightly linked PlantUML classes in a
+    /// `together` block, splitting large clusters to reduce edge crossings.
+    /// Ignored when grouping by property.
+    pub cluster_layout: bool,
+    /// Groups PlantUML classes into packages by this extended property.
+    pub group_by_property: Option<String>,
+    /// Draws `Database.Table` tables, as `--combined` names them, as classes
+    /// labelled with the bare table name under a `Database_Table` alias.
+    /// PlantUML would otherwise read the dot as a namespace separator.
+    pub database_packages: bool,
+    /// Maximum line width for the text format, defaulting to 80 columns.
+    pub text_width: Option<usize>,
+    /// Lines written as comments at the top of every generated file, see
+    /// `header_banner`.
+    pub header_comment: Vec<String>,
+    /// The table and column fields the JSON export keeps; empty keeps all.
+    pub json_fields: Vec<String>,
+    /// Spaces per nesting level, in place of each format's usual width.
+    pub indent: Option<usize>,
+    /// Leaves out the indentation and blank lines the formats don't need.
+    pub compact_output: bool,
+}
+
+impl RenderOptions {
+    /// The options used for the zoomed-out overview diagram: the same
+    /// settings, but with tables drawn as bare nodes.
+    pub fn overview(&self) -> RenderOptions {
+        RenderOptions {
+            relationships_only: true,
+            ..self.clone()
+        }
+    }
+
+    /// The indentation for `depth` nesting levels: `native` spaces per level
+    /// unless `--indent` or `--compact-output` says otherwise.
+    pub fn indent(&self, native: usize, depth: usize) -> String {
+        let width = if self.compact_output { 0 } else { self.indent.unwrap_or(native) };
+        " ".repeat(width * depth)
+    }
+
+    /// Whether `column` matches a `pii_patterns` glob.
+    pub fn is_pii(&self, column: &str) -> bool {
+        self.pii_patterns.iter().any(|pattern| glob_match(pattern, column, false))
+    }
+
+    /// Whether the given table should be rendered with its column list.
+    pub fn shows_columns(&self, schema: &str, table: &str) -> bool {
+        !self.relationships_only
+            && !self.columns_summary
+            && !self
+                .no_columns_for
+                .iter()
+                .any(|pattern| pattern.matches(schema, table, self.case_sensitive))
+    }
+
+    /// The columns of `table` that should be rendered, in model order.
+    pub fn visible_columns<'a>(&self, schema: &DatabaseSchema, table: &'a Table) -> Vec<&'a Column> {
+        self.column_list(schema, table).0
+    }
+
+    /// How many columns `first_columns` and `max_columns_per_table` cut from
+    /// the rendered list.
+    pub fn truncated_columns(&self, schema: &DatabaseSchema, table: &Table) -> usize {
+        self.column_list(schema, table).1
+    }
+
+    fn column_list<'a>(&self, schema: &DatabaseSchema, table: &'a Table) -> (Vec<&'a Column>, usize) {
+        if !self.shows_columns(&table.schema, &table.name) {
+            return (Vec::new(), 0);
+        }
+        let hints: Option<Vec<&str>> = table
+            .properties
+            .get(COLUMN_HINTS_PROPERTY)
+            .filter(|_| self.respect_column_hints)
+            .map(|hints| hints.split(',').map(str::trim).collect());
+        let keys = if self.columns_match.is_empty()
+            && self.hide_columns.is_empty()
+            && self.max_columns_per_table.is_none()
+            && self.first_columns.is_none()
+        {
+            Vec::new()
+        } else {
+            key_columns(schema, table)
+        };
+        let mut cut = 0;
+        let candidates: Vec<&Column> = table
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| match &hints {
+                Some(hints) => hints.iter().any(|hint| self.same_name(hint, &column.name)),
+                None => true,
+            })
+            .filter(|(_, column)| {
+                self.columns_match.is_empty()
+                    || keys.contains(&column.name.as_str())
+                    || self
+                        .columns_match
+                        .iter()
+                        .any(|pattern| glob_match(pattern, &column.name, self.case_sensitive))
+            })
+            .filter(|(_, column)| {
+                keys.contains(&column.name.as_str())
+                    || !self
+                        .hide_columns
+                        .iter()
+                        .any(|pattern| glob_match(pattern, &column.name, self.case_sensitive))
+            })
+            .filter(|(ordinal, column)| {
+                let kept =
+                    self.first_columns.is_none_or(|first| *ordinal < first) || keys.contains(&column.name.as_str());
+                cut += usize::from(!kept);
+                kept
+            })
+            .map(|(_, column)| column)
+            .collect();
+        match self.max_columns_per_table {
+            Some(max) => {
+                // Key columns past the cap are kept so edges can still anchor on them
+                let total = candidates.len();
+                let kept: Vec<&Column> = candidates
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(i, column)| *i < max || keys.contains(&column.name.as_str()))
+                    .map(|(_, column)| column)
+                    .collect();
+                let truncated = cut + total - kept.len();
+                (kept, truncated)
+            }
+            None => (candidates, cut),
+        }
+    }
+
+    /// Whether `column` of `table` is rendered, so edges can anchor on it.
+    pub fn shows_column(&self, schema: &DatabaseSchema, table: &Table, column: &str) -> bool {
+        self.visible_columns(schema, table).iter().any(|visible| visible.name == column)
+    }
+
+    /// The label a table is drawn with: its `--rename-map` display name, or
+    /// its physical name when unmapped.
+    pub fn display_name<'a>(&'a self, table_schema: &str, name: &'a str) -> &'a str {
+        self.display_names
+            .iter()
+            .find(|(pattern, _)| pattern.matches(table_schema, name, self.case_sensitive))
+            .map_or(name, |(_, display)| display.as_str())
+    }
+
+    fn same_name(&self, a: &str, b: &str) -> bool {
+        if self.case_sensitive {
+            a == b
+        } else {
+            a.to_lowercase() == b.to_lowercase()
+        }
+    }
+}
+
+/// The primary key columns of `table` plus the columns on either end of
+/// its references.
+fn key_columns<'a>(schema: &'a DatabaseSchema, table: &'a Table) -> Vec<&'a str> {
+    let mut keys: Vec<&str> = table.primary_key.iter().map(String::as_str).collect();
+    for reference in &schema.references {
+        if reference.schema == table.schema && reference.table == table.name {
+            keys.push(&reference.column);
+        }
+        if reference.referenced_schema == table.schema && reference.referenced_table == table.name {
+            keys.push(&reference.referenced_column);
+        }
+    }
+    keys
+}
+
+/// Formats a column as `name : type`, or just `name` when types are hidden,
+/// followed by any legacy bound default or rule, a `<<FTS: catalog>>`
+/// marker on full-text indexed columns and a `<<PII>>` marker on columns
+/// matching `pii_patterns`.
+fn column_line(column: &Column, options: &RenderOptions) -> String {
+    let mut line = if options.hide_types {
+        column.name.clone()
+    } else {
+        format!("{} : {}", column.name, column.data_type)
+    };
+    let bindings: Vec<String> = column
+        .bound_default
+        .iter()
+        .map(|name| format!("default {}", name))
+        .chain(column.bound_rule.iter().map(|name| format!("rule {}", name)))
+        .chain(
+            column
+                .collation
+                .iter()
+                .filter(|_| options.highlight_collations)
+                .map(|collation| format!("collate {}", collation)),
+        )
+        .collect();
+    if !bindings.is_empty() {
+        line.push_str(&format!(" {{{}}}", bindings.join(", ")));
+    }
+    if let Some(catalog) = &column.fulltext_catalog {
+        line.push_str(&format!(" <<FTS: {}>>", catalog));
+    }
+    if options.is_pii(&column.name) {
+        line.push_str(" <<PII>>");
+    }
+    line
+}
+
+/// Looks up a table by schema and name.
+pub fn find_table<'a>(schema: &'a DatabaseSchema, table_schema: &str, name: &str) -> Option<&'a Table> {
+    schema
+        .tables
+        .iter()
+        .find(|table| table.schema == table_schema && table.name == name)
+}
+
+/// Whether every foreign key in `group` is one-to-one: the child is unique
+/// over the constraint's whole set of referencing columns, so each parent
+/// row has at most one child. All the constraint's columns are checked even
+/// when `group` holds only some of them, as DOT's per-column edges do.
+pub fn is_one_to_one(schema: &DatabaseSchema, group: &[&Reference]) -> bool {
+    let first = group[0];
+    let Some(child) = find_table(schema, &first.schema, &first.table) else {
+        return false;
+    };
+    group.iter().all(|reference| {
+        let columns: Vec<&str> = schema
+            .references
+            .iter()
+            .filter(|other| {
+                other.constraint_name == reference.constraint_name
+                    && other.schema == reference.schema
+                    && other.table == reference.table
+            })
+            .map(|other| other.column.as_str())
+            .collect();
+        child.is_unique_over(&columns)
+    })
+}
+
+/// Groups the per-column references into one entry per foreign key constraint.
+fn group_by_constraint(references: &[Reference]) -> Vec<Vec<&Reference>> {
+    let mut groups: Vec<Vec<&Reference>> = Vec::new();
+    for reference in references {
+        let group = groups.iter_mut().find(|group| {
+            let first = group[0];
+            first.constraint_name == reference.constraint_name
+                && first.schema == reference.schema
+                && first.table == reference.table
+        });
+        match group {
+            Some(group) => group.push(reference),
+            None => groups.push(vec![reference]),
+        }
+    }
+    groups
+}
+
+/// Groups references into one entry per drawn edge: per foreign key
+/// constraint, or per pair of tables when parallel edges are merged.
+fn edge_groups<'a>(references: &'a [Reference], options: &RenderOptions) -> Vec<Vec<&'a Reference>> {
+    if !options.merge_parallel_edges {
+        return group_by_constraint(references);
+    }
+    let mut groups: Vec<Vec<&Reference>> = Vec::new();
+    for reference in references {
+        let group = groups.iter_mut().find(|group| {
+            let first = group[0];
+            first.schema == reference.schema
+                && first.table == reference.table
+                && first.referenced_schema == reference.referenced_schema
+                && first.referenced_table == reference.referenced_table
+        });
+        match group {
+            Some(group) => group.push(reference),
+            None => groups.push(vec![reference]),
+        }
+    }
+    groups
+}
+
+/// Labels an edge with its column pairs (`a → b, c → d`), naming each
+/// constraint when the edge merges several of them.
+fn edge_label(group: &[&Reference]) -> String {
+    let mut constraints: Vec<(&str, Vec<String>)> = Vec::new();
+    for reference in group {
+        let pair = format!("{} → {}{}", reference.column, reference.referenced_column, via_synonym(reference));
+        match constraints.iter_mut().find(|(name, _)| *name == reference.constraint_name) {
+            Some((_, pairs)) => pairs.push(pair),
+            None => constraints.push((&reference.constraint_name, vec![pair])),
+        }
+    }
+    if let [(_, pairs)] = constraints.as_slice() {
+        return pairs.join(", ");
+    }
+    constraints
+        .iter()
+        .map(|(name, pairs)| format!("{}: {}", name, pairs.join(", ")))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Labels a single-column edge with its column, or with both columns
+/// (`a → b`) under `verbose_labels`.
+fn reference_label(reference: &Reference, options: &RenderOptions) -> String {
+    if options.verbose_labels {
+        format!("{} → {}{}", reference.column, reference.referenced_column, via_synonym(reference))
+    } else {
+        format!("{}{}", reference.column, via_synonym(reference))
+    }
+}
+
+/// The singular of a plural table name such as `Categories` or `Addresses`,
+/// by simple English rules.
+fn singular(word: &str) -> String {
+    let lower = word.to_lowercase();
+    if lower.len() > 3 && lower.ends_with("ies") {
+        format!("{}y", &word[..word.len() - 3])
+    } else if ["sses", "xes", "zes", "ches", "shes"].iter().any(|suffix| lower.ends_with(suffix)) {
+        word[..word.len() - 2].to_string()
+    } else if lower.ends_with('s') && !["ss", "us", "is"].iter().any(|suffix| lower.ends_with(suffix)) {
+        word[..word.len() - 1].to_string()
+    } else {
+        word.to_string()
+    }
+}
+
+/// The plural of a singular name, by simple English rules.
+fn plural(word: &str) -> String {
+    let lower = word.to_lowercase();
+    let vowel_y = ["ay", "ey", "iy", "oy", "uy"].iter().any(|suffix| lower.ends_with(suffix));
+    if lower.ends_with('y') && !vowel_y {
+        format!("{}ies", &word[..word.len() - 1])
+    } else if ["s", "x", "z", "ch", "sh"].iter().any(|suffix| lower.ends_with(suffix)) {
+        format!("{}es", word)
+    } else {
+        format!("{}s", word)
+    }
+}
+
+/// The Entity Framework navigation properties of each constraint in a
+/// group, as `Child.Parent ↔ Parent.Children`. The child side is named after
+/// a single `...Id` column, or the parent entity otherwise, with a
+/// `Navigation` suffix when that clashes with the child or one of its
+/// columns. The parent side is the pluralized child entity, the singular one
+/// when the foreign key is the child's whole primary key, or names the child
+/// side too when the child has several foreign keys to the parent.
+fn navigation_hint(schema: &DatabaseSchema, group: &[&Reference]) -> String {
+    let mut constraints: Vec<Vec<&Reference>> = Vec::new();
+    for &reference in group {
+        match constraints.iter_mut().find(|pairs| pairs[0].constraint_name == reference.constraint_name) {
+            Some(pairs) => pairs.push(reference),
+            None => constraints.push(vec![reference]),
+        }
+    }
+    constraints
+        .iter()
+        .map(|pairs| {
+            let first = pairs[0];
+            let child = singular(&first.table);
+            let parent = singular(&first.referenced_table);
+            let navigation = match pairs.as_slice() {
+                [reference] => {
+                    let lower = reference.column.to_lowercase();
+                    let prefix = if lower.ends_with("id") { &reference.column[..lower.len() - 2] } else { "" };
+                    match prefix.trim_end_matches('_') {
+                        "" => parent.clone(),
+                        prefix => prefix.to_string(),
+                    }
+                }
+                _ => parent.clone(),
+            };
+            let table = find_table(schema, &first.schema, &first.table);
+            let clashes = navigation == child
+                || table.is_some_and(|table| table.columns.iter().any(|column| column.name == navigation));
+            let navigation = if clashes { format!("{}Navigation", navigation) } else { navigation };
+            let one_to_one = is_one_to_one(schema, pairs);
+            let parallel = schema
+                .references
+                .iter()
+                .filter(|reference| {
+                    reference.schema == first.schema
+                        && reference.table == first.table
+                        && reference.referenced_schema == first.referenced_schema
+                        && reference.referenced_table == first.referenced_table
+                })
+                .any(|reference| reference.constraint_name != first.constraint_name);
+            let inverse = if one_to_one {
+                child.clone()
+            } else if parallel {
+                format!("{}{}", child, plural(&navigation))
+            } else {
+                plural(&child)
+            };
+            format!("{}.{} ↔ {}.{}", child, navigation, parent, inverse)
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// The ` (via synonym X)` suffix of a reference resolved through a synonym.
+fn via_synonym(reference: &Reference) -> String {
+    match &reference.via_synonym {
+        Some(synonym) => format!(" (via synonym {})", synonym),
+        None => String::new(),
+    }
+}
+
+/// Parses a `--rename-map` file of physical table names, optionally
+/// schema-qualified, to display names: either a JSON object such as
+/// `{"tbl_cust_mstr": "Customer"}` or CSV lines such as
+/// `sales.tbl_ord_hdr,Order Header`. Blank CSV lines and lines starting with
+/// `#` are skipped.
+pub fn parse_rename_map(text: &str) -> Result<Vec<(NamePattern, String)>, DiagramError> {
+    let invalid = |reason: String| DiagramError::InvalidArgument(format!("invalid rename map: {}", reason));
+    if text.trim_start().starts_with('{') {
+        let names: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(text).map_err(|err| invalid(err.to_string()))?;
+        return names
+            .into_iter()
+            .map(|(physical, display)| match display {
+                serde_json::Value::String(display) => Ok((NamePattern::parse(&physical), display)),
+                _ => Err(invalid(format!("the display name of {} isn't a string", physical))),
+            })
+            .collect();
+    }
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| match line.split_once(',') {
+            Some((physical, display)) if !physical.trim().is_empty() && !display.trim().is_empty() => {
+                Ok((NamePattern::parse(physical.trim()), display.trim().to_string()))
+            }
+            _ => Err(invalid(format!("line {} isn't physical,display", i + 1))),
+        })
+        .collect()
+}
+
+/// The `--columns-summary` line standing in for a table's column list, such
+/// as `12 cols, PK, 3 FKs, 2 indexes`. Composite foreign keys count once.
+pub fn columns_summary(schema: &DatabaseSchema, table: &Table) -> String {
+    let plural = |count: usize, one: &str, many: &str| format!("{} {}", count, if count == 1 { one } else { many });
+    let mut constraints: Vec<&str> = schema
+        .references
+        .iter()
+        .filter(|reference| reference.schema == table.schema && reference.table == table.name)
+        .map(|reference| reference.constraint_name.as_str())
+        .collect();
+    constraints.sort_unstable();
+    constraints.dedup();
+    [
+        plural(table.columns.len(), "col", "cols"),
+        if table.primary_key.is_empty() { "no PK" } else { "PK" }.to_string(),
+        plural(constraints.len(), "FK", "FKs"),
+        plural(table.indexes.len(), "index", "indexes"),
+    ]
+    .join(", ")
+}
+
+/// The line standing in for the columns cut by `first_columns` and
+/// `max_columns_per_table`.
+fn truncation_line(truncated: usize) -> String {
+    format!("… (+{} more)", truncated)
+}
+
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{reference, table, two_triangles};
+
+    /// `two_triangles` with a circular chain a ⇄ c and a second schema.
+    fn sample() -> DatabaseSchema {
+        let mut schema = two_triangles();
+        schema.references.push(reference("FK_a_c", "dbo.a.ParentId", "dbo.c.Id"));
+        schema.tables.push(table("sales.a", &["Id", "ParentId"], &["Id"]));
+        schema.references.push(reference("FK_sales_a", "sales.a.ParentId", "dbo.e.Id"));
+        schema
+    }
+
+    #[test]
+    fn the_same_schema_renders_byte_for_byte_the_same() {
+        let option_sets = [
+            RenderOptions::default(),
+            RenderOptions {
+                layered: true,
+                color_by: ColorBy::Schema,
+                ..RenderOptions::default()
+            },
+            RenderOptions {
+                cluster_layout: true,
+                ..RenderOptions::default()
+            },
+            RenderOptions {
+                together: true,
+                ..RenderOptions::default()
+            },
+        ];
+        for options in &option_sets {
+            for format in FORMATS {
+                let first = format.render(&sample(), options).unwrap();
+                // Each run builds fresh hash maps, whose iteration order differs between them
+                for _ in 0..3 {
+                    assert_eq!(format.render(&sample(), options).unwrap(), first, "--format {}", format.name());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn header_banner_uses_each_format_comment_syntax() {
+        let options = RenderOptions {
+            header_comment: vec!["DO NOT EDIT".to_string(), "see TICKET-1 -->\nsecond line".to_string()],
+            ..RenderOptions::default()
+        };
+        let expected = |open: &str, close: &str| {
+            format!(
+                "{open}DO NOT EDIT{close}\n{open}see TICKET-1 -->{close}\n{open}second line{close}\n",
+                open = open,
+                close = close
+            )
+        };
+        for format in FORMATS {
+            let banner = header_banner(*format, &options);
+            let want = match format.name() {
+                "plantuml" | "plantuml-json" => expected("' ", ""),
+                "mermaid" | "mermaid-flow" => expected("%% ", ""),
+                "dot" | "dbml" => expected("// ", ""),
+                "yaml" => expected("# ", ""),
+                "markdown" | "matrix-markdown" | "html" => "<!-- DO NOT EDIT -->\n\
+                     <!-- see TICKET-1 - -> -->\n\
+                     <!-- second line -->\n"
+                    .to_string(),
+                // CSV, plain text and JSON have no comments
+                "matrix" | "text" | "json" => String::new(),
+                other => panic!("no expected banner for --format {}", other),
+            };
+            assert_eq!(banner, want, "--format {}", format.name());
+        }
+    }
+
+    #[test]
+    fn writing_gives_the_rendered_text() {
+        let options = RenderOptions {
+            layered: true,
+            ..RenderOptions::default()
+        };
+        for format in FORMATS {
+            let mut written = Vec::new();
+            format.write(&sample(), &mut written, &options).unwrap();
+            let rendered = format.render(&sample(), &options).unwrap();
+            assert_eq!(String::from_utf8(written).unwrap(), rendered, "--format {}", format.name());
+        }
+    }
+
+    #[test]
+    fn column_hints_narrow_only_hinted_tables() {
+        let mut hinted = table("dbo.Order", &["Id", "Notes", "Status", "Total"], &["Id"]);
+        hinted.properties.insert(COLUMN_HINTS_PROPERTY.to_string(), "Id, Status".to_string());
+        let schema = crate::testing::schema(vec![hinted, table("dbo.Customer", &["Id", "Name"], &["Id"])], Vec::new());
+        let visible = |options: &RenderOptions, table: &Table| -> Vec<String> {
+            let columns = options.visible_columns(&schema, table);
+            columns.iter().map(|column| column.name.clone()).collect()
+        };
+        let respecting = RenderOptions {
+            respect_column_hints: true,
+            ..RenderOptions::default()
+        };
+        assert_eq!(visible(&respecting, &schema.tables[0]), ["Id", "Status"]);
+        assert_eq!(visible(&respecting, &schema.tables[1]), ["Id", "Name"]);
+        let ignoring = RenderOptions::default();
+        assert_eq!(visible(&ignoring, &schema.tables[0]), ["Id", "Notes", "Status", "Total"]);
+    }
+
+    #[test]
+    fn every_registered_format_renders_and_is_found_by_name() {
+        let empty = crate::testing::schema(Vec::new(), Vec::new());
+        let mut names = Vec::new();
+        for format in FORMATS {
+            let found = lookup_format(format.name()).expect("registered formats are found by name");
+            assert_eq!(found.extension(), format.extension());
+            assert!(!format.extension().is_empty(), "--format {}", format.name());
+            assert!(!format.render(&sample(), &RenderOptions::default()).unwrap().is_empty());
+            format.render(&empty, &RenderOptions::default()).unwrap();
+            names.push(format.name());
+        }
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), FORMATS.len(), "format names are unique");
+        assert!(lookup_format("visio").is_none());
+    }
+
+    #[test]
+    fn columns_match_keeps_matching_and_key_columns() {
+        let schema = crate::testing::schema(
+            vec![
+                table("dbo.Customer", &["Id", "Name", "createdAt", "ModifiedBy"], &["Id"]),
+                table("dbo.Orders", &["Id", "CustomerId", "Total", "CreatedAt"], &["Id"]),
+            ],
+            vec![reference("FK_Orders_Customer", "dbo.Orders.CustomerId", "dbo.Customer.Id")],
+        );
+        let options = RenderOptions {
+            columns_match: vec!["Created*".to_string()],
+            ..RenderOptions::default()
+        };
+        let visible = |table: &Table| -> Vec<String> {
+            let columns = options.visible_columns(&schema, table);
+            columns.iter().map(|column| column.name.clone()).collect()
+        };
+        assert_eq!(visible(&schema.tables[0]), ["Id", "createdAt"]);
+        assert_eq!(visible(&schema.tables[1]), ["Id", "CustomerId", "CreatedAt"]);
+    }
+
+    #[test]
+    fn indent_sets_the_width_of_column_lines() {
+        let schema = crate::testing::schema(vec![table("dbo.Customer", &["Id"], &["Id"])], Vec::new());
+        let render = |format: &str, indent: Option<usize>, compact_output: bool| {
+            let options = RenderOptions {
+                indent,
+                compact_output,
+                ..RenderOptions::default()
+            };
+            lookup_format(format).unwrap().render(&schema, &options).unwrap()
+        };
+        assert!(render("plantuml", None, false).contains("\n  Id : int\n"));
+        assert!(render("plantuml", Some(4), false).contains("\n    Id : int\n"));
+        assert!(render("plantuml", Some(4), true).contains("\nId : int\n"));
+        // Each format keeps its own default width, but --indent applies to all of them
+        assert!(render("mermaid", None, false).contains("\n    dbo_Customer {\n        int Id\n"));
+        assert!(render("mermaid", Some(1), false).contains("\n dbo_Customer {\n  int Id\n"));
+    }
+
+    #[test]
+    fn columns_summary_counts_columns_keys_and_indexes() {
+        let mut line = table("dbo.OrderLine", &["OrderId", "LineNo", "ProductId", "Quantity"], &["OrderId", "LineNo"]);
+        line.indexes.push(crate::model::Index {
+            name: "IX_OrderLine_ProductId".to_string(),
+            columns: vec!["ProductId".to_string()],
+            descending: Vec::new(),
+            included: Vec::new(),
+            unique: false,
+            filter: None,
+        });
+        let schema = crate::testing::schema(
+            vec![line, table("dbo.Orders", &["Id"], &[]), table("dbo.Product", &["Id"], &["Id"])],
+            vec![
+                reference("FK_OrderLine_Orders", "dbo.OrderLine.OrderId", "dbo.Orders.Id"),
+                reference("FK_OrderLine_Product", "dbo.OrderLine.ProductId", "dbo.Product.Id"),
+                reference("FK_OrderLine_Product", "dbo.OrderLine.LineNo", "dbo.Product.Id"),
+            ],
+        );
+        let summary = |name: &str| columns_summary(&schema, find_table(&schema, "dbo", name).unwrap());
+        // The two-column FK_OrderLine_Product counts once
+        assert_eq!(summary("OrderLine"), "4 cols, PK, 2 FKs, 1 index");
+        assert_eq!(summary("Orders"), "1 col, no PK, 0 FKs, 0 indexes");
+    }
+
+    #[test]
+    fn pii_patterns_tag_matching_columns_only() {
+        let customer = table(
+            "dbo.Customer",
+            &["Id", "Name:nvarchar(50)", "Customer_SSN:char(11)", "Email:nvarchar(200)"],
+            &["Id"],
+        );
+        let lines = |patterns: &str| {
+            let options = RenderOptions {
+                pii_patterns: patterns.split(',').map(str::to_string).collect(),
+                ..RenderOptions::default()
+            };
+            customer.columns.iter().map(|column| column_line(column, &options)).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            lines(DEFAULT_PII_PATTERNS),
+            ["Id : int", "Name : nvarchar(50)", "Customer_SSN : char(11) <<PII>>", "Email : nvarchar(200) <<PII>>"]
+        );
+        // A list of its own replaces the built-in one
+        assert_eq!(lines("name")[1..3], ["Name : nvarchar(50) <<PII>>", "Customer_SSN : char(11)"]);
+    }
+
+    #[test]
+    fn ef_hints_name_both_sides_of_a_one_to_many_foreign_key() {
+        let schema = crate::testing::schema(
+            vec![
+                table("dbo.Categories", &["Id"], &["Id"]),
+                table("dbo.Products", &["Id", "CategoryId"], &["Id"]),
+                table("dbo.Address", &["Id"], &["Id"]),
+                table("dbo.Orders", &["Id", "BillingId", "ShippingId"], &["Id"]),
+            ],
+            vec![
+                reference("FK_Products_Categories", "dbo.Products.CategoryId", "dbo.Categories.Id"),
+                reference("FK_Orders_Billing", "dbo.Orders.BillingId", "dbo.Address.Id"),
+                reference("FK_Orders_Shipping", "dbo.Orders.ShippingId", "dbo.Address.Id"),
+            ],
+        );
+        let hint = |position: usize| navigation_hint(&schema, &[&schema.references[position]]);
+        assert_eq!(hint(0), "Product.Category ↔ Category.Products");
+        // Two foreign keys to the same parent name the collection after the child side
+        assert_eq!(hint(1), "Order.Billing ↔ Address.OrderBillings");
+        assert_eq!(hint(2), "Order.Shipping ↔ Address.OrderShippings");
+
+        let options = RenderOptions { ef_hints: true, ..RenderOptions::default() };
+        let plantuml = crate::render::generate_plantuml(&schema, &options);
+        assert!(plantuml.contains(": CategoryId [Product.Category ↔ Category.Products]"), "{}", plantuml);
+        assert_eq!([plural("Box"), plural("Day"), singular("Addresses")], ["Boxes", "Days", "Address"]);
+    }
+
+    #[test]
+    fn composite_foreign_keys_are_one_to_one_only_when_unique_over_all_their_columns() {
+        let unique = |name: &str, columns: &[&str]| crate::model::Index {
+            name: name.to_string(),
+            columns: columns.iter().map(|column| column.to_string()).collect(),
+            descending: Vec::new(),
+            included: Vec::new(),
+            unique: true,
+            filter: None,
+        };
+        let with_indexes = |indexes: Vec<crate::model::Index>| {
+            let mut detail = table("dbo.LineDetail", &["Id", "OrderId", "LineNo", "Note"], &["Id"]);
+            detail.indexes = indexes;
+            crate::testing::schema(
+                vec![table("dbo.OrderLine", &["OrderId", "LineNo"], &["OrderId", "LineNo"]), detail],
+                vec![
+                    reference("FK_LineDetail_OrderLine", "dbo.LineDetail.OrderId", "dbo.OrderLine.OrderId"),
+                    reference("FK_LineDetail_OrderLine", "dbo.LineDetail.LineNo", "dbo.OrderLine.LineNo"),
+                ],
+            )
+        };
+        let one_to_one = |schema: &DatabaseSchema| {
+            let whole: Vec<&Reference> = schema.references.iter().collect();
+            // A single column's edge, as DOT draws them, still checks the whole set
+            let single = is_one_to_one(schema, &[&schema.references[0]]);
+            assert_eq!(single, is_one_to_one(schema, &whole));
+            single
+        };
+        assert!(one_to_one(&with_indexes(vec![unique("UX_Line", &["OrderId", "LineNo"])])));
+        // Neither referencing column is unique on its own
+        assert!(!one_to_one(&with_indexes(Vec::new())));
+        assert!(!one_to_one(&with_indexes(vec![unique("UX_Line_Note", &["OrderId", "LineNo", "Note"])])));
+
+        let schema = with_indexes(vec![unique("UX_Line", &["OrderId", "LineNo"])]);
+        let options = RenderOptions { fk_cardinality: true, ..RenderOptions::default() };
+        let plantuml = crate::render::generate_plantuml(&schema, &options);
+        assert!(plantuml.contains("LineDetail::OrderId \"0..1\" --> \"1\" OrderLine::OrderId"), "{}", plantuml);
+    }
+
+    #[test]
+    fn hide_columns_drops_matches_from_every_table_but_keeps_keys() {
+        let schema = crate::testing::schema(
+            vec![
+                table("dbo.Customer", &["Id", "Name", "ModifiedDate", "ExternalId"], &["Id"]),
+                table("dbo.Orders", &["Id", "CustomerId", "modifieddate"], &["Id"]),
+            ],
+            vec![reference("FK_Orders_Customer", "dbo.Orders.CustomerId", "dbo.Customer.Id")],
+        );
+        let options = RenderOptions {
+            hide_columns: vec!["ModifiedDate".to_string(), "*Id".to_string()],
+            ..RenderOptions::default()
+        };
+        let visible = |name: &str| -> Vec<&str> {
+            let table = find_table(&schema, "dbo", name).unwrap();
+            options.visible_columns(&schema, table).iter().map(|column| column.name.as_str()).collect()
+        };
+        assert_eq!(visible("Customer"), ["Id", "Name"]);
+        assert_eq!(visible("Orders"), ["Id", "CustomerId"]);
+    }
+}
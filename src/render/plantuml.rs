@@ -0,0 +1,2215 @@
+use super::{
+    column_line, columns_summary, edge_groups, edge_label, find_table, group_by_constraint, is_one_to_one,
+    navigation_hint, reference_label, single_line, truncation_line, OutputFormat, RenderOptions,
+};
+use crate::analysis::{
+    communities, connected_components, cycle_references, layers, procedure_dependencies, type_counts, type_mismatch,
+    view_dependencies,
+};
+use crate::error::DiagramError;
+use crate::model::{Column, DatabaseSchema, Reference, Table};
+use crate::rng::SeededRng;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+/// The PlantUML connector drawn for foreign key edges.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrowStyle {
+    /// `-->`
+    #[default]
+    Solid,
+    /// `..>`
+    Dashed,
+    /// `*-->`, for ownership relationships.
+    Composition,
+    /// `o-->`
+    Aggregation,
+}
+
+impl ArrowStyle {
+    pub fn parse(style: &str) -> Option<ArrowStyle> {
+        match style {
+            "solid" => Some(ArrowStyle::Solid),
+            "dashed" => Some(ArrowStyle::Dashed),
+            "composition" => Some(ArrowStyle::Composition),
+            "aggregation" => Some(ArrowStyle::Aggregation),
+            _ => None,
+        }
+    }
+
+    /// The connector for an edge, dotted when its foreign key is disabled.
+    pub fn connector(&self, disabled: bool) -> &'static str {
+        match (self, disabled) {
+            (ArrowStyle::Solid, false) => "-->",
+            (ArrowStyle::Solid | ArrowStyle::Dashed, _) => "..>",
+            (ArrowStyle::Composition, false) => "*-->",
+            (ArrowStyle::Composition, true) => "*..>",
+            (ArrowStyle::Aggregation, false) => "o-->",
+            (ArrowStyle::Aggregation, true) => "o..>",
+        }
+    }
+}
+
+/// The icons `--attribute-icons` prefixes PlantUML members with, by the
+/// column's role.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeIcons {
+    pub primary_key: String,
+    pub foreign_key: String,
+    pub computed: String,
+}
+
+impl Default for AttributeIcons {
+    fn default() -> AttributeIcons {
+        AttributeIcons {
+            primary_key: "🔑".to_string(),
+            foreign_key: "🔗".to_string(),
+            computed: "⚙".to_string(),
+        }
+    }
+}
+
+impl AttributeIcons {
+    /// Parses an `--attribute-icon-set` list such as `pk=#,fk=>`, overriding
+    /// the default icon of each role it names: `pk`, `fk` or `computed`.
+    pub fn parse(list: &str) -> Result<AttributeIcons, String> {
+        let mut icons = AttributeIcons::default();
+        for entry in list.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (role, icon) = entry
+                .split_once('=')
+                .map(|(role, icon)| (role.trim(), icon.trim().to_string()))
+                .ok_or_else(|| format!("'{}' isn't role=icon", entry))?;
+            match role {
+                "pk" => icons.primary_key = icon,
+                "fk" => icons.foreign_key = icon,
+                "computed" => icons.computed = icon,
+                _ => return Err(format!("unknown role '{}', expected pk, fk or computed", role)),
+            }
+        }
+        Ok(icons)
+    }
+
+    /// The icons for each role `column` has, in primary key, foreign key and
+    /// computed order, or nothing for a plain column.
+    fn prefix(&self, schema: &DatabaseSchema, table: &Table, column: &Column) -> String {
+        let is_foreign_key = schema.references.iter().any(|reference| {
+            reference.schema == table.schema && reference.table == table.name && reference.column == column.name
+        });
+        [
+            (table.primary_key.contains(&column.name), &self.primary_key),
+            (is_foreign_key, &self.foreign_key),
+            (column.computed, &self.computed),
+        ]
+        .into_iter()
+        .filter(|(has_role, _)| *has_role)
+        .map(|(_, icon)| icon.as_str())
+        .collect()
+    }
+}
+
+/// The member line of `column`, prefixed with its role icons under
+/// `--attribute-icons`.
+fn member_line(schema: &DatabaseSchema, table: &Table, column: &Column, options: &RenderOptions) -> String {
+    let line = column_line(column, options);
+    match options.attribute_icons.as_ref().map(|icons| icons.prefix(schema, table, column)) {
+        Some(prefix) if !prefix.is_empty() => format!("{} {}", prefix, line),
+        _ => line,
+    }
+}
+
+/// What PlantUML foreign key edges are anchored on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReferenceStyle {
+    /// `A::col --> B::col`, when both columns are drawn.
+    #[default]
+    Columns,
+    /// `A --> B`, labelled with the column.
+    Tables,
+}
+
+impl ReferenceStyle {
+    pub fn parse(style: &str) -> Option<ReferenceStyle> {
+        match style {
+            "columns" => Some(ReferenceStyle::Columns),
+            "tables" => Some(ReferenceStyle::Tables),
+            _ => None,
+        }
+    }
+}
+
+/// What PlantUML class backgrounds are coloured by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorBy {
+    #[default]
+    None,
+    /// Each database schema gets its own colour from `SCHEMA_PALETTE`.
+    Schema,
+}
+
+impl ColorBy {
+    pub fn parse(color_by: &str) -> Option<ColorBy> {
+        match color_by {
+            "none" => Some(ColorBy::None),
+            "schema" => Some(ColorBy::Schema),
+            _ => None,
+        }
+    }
+}
+
+/// The background colours `--color-by schema` picks from.
+const SCHEMA_PALETTE: &[&str] = &[
+    "#AED6F1", "#A9DFBF", "#F9E79F", "#F5B7B1", "#D7BDE2", "#A3E4D7", "#FAD7A0", "#D5DBDB", "#F8C471", "#85C1E9",
+    "#ABEBC6", "#EDBB99",
+];
+
+/// The palette colour of a schema, picked by an FNV-1a hash of its lowercased
+/// name. Hashing rather than numbering schemas in order keeps a schema's
+/// colour the same whichever other schemas are diagrammed, and unlike std's
+/// hasher FNV-1a doesn't change between Rust releases. A `--seed` remixes the
+/// hash, trading every schema's colour for another stable one.
+fn schema_color(schema: &str, seed: Option<u64>) -> &'static str {
+    let hash = schema
+        .to_lowercase()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+    let hash = seed.map_or(hash, |seed| SeededRng::new(hash ^ seed).next_u64());
+    SCHEMA_PALETTE[(hash % SCHEMA_PALETTE.len() as u64) as usize]
+}
+
+/// The overall direction PlantUML lays the diagram out in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Layout {
+    #[default]
+    TopDown,
+    LeftRight,
+}
+
+impl Layout {
+    pub fn parse(layout: &str) -> Option<Layout> {
+        match layout {
+            "top-down" => Some(Layout::TopDown),
+            "left-right" => Some(Layout::LeftRight),
+            _ => None,
+        }
+    }
+}
+
+/// The PlantUML identifiers of classes. Tables named longer than
+/// `max_table_name_length` are declared with a truncated label, tables
+/// sharing a name with one in another schema with a schema-qualified label
+/// and renamed tables with their display name, all wired up through a short
+/// alias (`t1`, `t2`, ...), numbered in order of first appearance, so their
+/// edges connect to the right class.
+#[derive(Default)]
+struct ClassIds {
+    /// The alias and label of each aliased `(schema, table)`.
+    aliases: HashMap<(String, String), (String, String)>,
+    /// The schema of the table each unaliased name stands for.
+    owners: HashMap<String, String>,
+}
+
+impl ClassIds {
+    fn new(schema: &DatabaseSchema, options: &RenderOptions) -> ClassIds {
+        let tables: Vec<(&str, &str)> = schema
+            .tables
+            .iter()
+            .map(|table| (table.schema.as_str(), table.name.as_str()))
+            .chain(schema.references.iter().flat_map(|reference| {
+                [
+                    (reference.schema.as_str(), reference.table.as_str()),
+                    (reference.referenced_schema.as_str(), reference.referenced_table.as_str()),
+                ]
+            }))
+            .collect();
+        let mut schemas: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for (table_schema, name) in &tables {
+            schemas.entry(name).or_default().insert(table_schema);
+        }
+        let mut ids = ClassIds::default();
+        for (table_schema, name) in tables {
+            ids.register(table_schema, name, schemas[name].len() > 1, options);
+        }
+        ids
+    }
+
+    /// Adds a table seen after construction, qualifying it if another
+    /// schema's table already stands for its name.
+    fn add(&mut self, table_schema: &str, name: &str, options: &RenderOptions) {
+        self.register(table_schema, name, false, options);
+    }
+
+    fn register(&mut self, table_schema: &str, name: &str, collides: bool, options: &RenderOptions) {
+        let key = (table_schema.to_string(), name.to_string());
+        if self.aliases.contains_key(&key) || self.owners.get(name).is_some_and(|owner| owner == table_schema) {
+            return;
+        }
+        let display = options.display_name(table_schema, name);
+        if let Some((database, table)) = name.split_once('.').filter(|_| options.database_packages) {
+            let base: String = format!("{}_{}", database, table)
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+                .collect();
+            let mut alias = base.clone();
+            let mut suffix = 2;
+            while self.aliases.values().any(|(used, _)| *used == alias) {
+                alias = format!("{}_{}", base, suffix);
+                suffix += 1;
+            }
+            let label = if display != name { display } else { table };
+            self.aliases.insert(key, (alias, label.to_string()));
+            return;
+        }
+        let qualified = collides || self.owners.contains_key(name);
+        let long = options.max_table_name_length.is_some_and(|max| display.chars().count() > max);
+        if qualified || long || display != name {
+            let label = if display != name {
+                display.to_string()
+            } else if qualified {
+                format!("{}.{}", table_schema, name)
+            } else {
+                name.to_string()
+            };
+            let alias = format!("t{}", self.aliases.len() + 1);
+            self.aliases.insert(key, (alias, label));
+        } else {
+            self.owners.insert(key.1, key.0);
+        }
+    }
+
+    /// The identifier edges and notes use for the class of a table.
+    fn id<'a>(&'a self, table_schema: &str, name: &'a str) -> &'a str {
+        self.aliases
+            .get(&(table_schema.to_string(), name.to_string()))
+            .map_or(name, |(alias, _)| alias.as_str())
+    }
+
+    /// What follows `class` in the declaration of a table.
+    fn declaration(&self, table_schema: &str, name: &str, options: &RenderOptions) -> String {
+        match self.aliases.get(&(table_schema.to_string(), name.to_string())) {
+            Some((alias, label)) => {
+                let label = match options.max_table_name_length {
+                    Some(max) if label.chars().count() > max => {
+                        format!("{}…", label.chars().take(max.saturating_sub(1)).collect::<String>())
+                    }
+                    _ => label.clone(),
+                };
+                format!("\"{}\" as {}", label.replace('"', "'"), alias)
+            }
+            None => name.to_string(),
+        }
+    }
+}
+
+/// Anchors an edge on the column when it is drawn, unless edges connect
+/// tables, or on the class otherwise.
+fn endpoint(
+    schema: &DatabaseSchema,
+    ids: &ClassIds,
+    options: &RenderOptions,
+    table_schema: &str,
+    table: &str,
+    column: &str,
+) -> String {
+    match find_table(schema, table_schema, table) {
+        Some(model)
+            if options.reference_style == ReferenceStyle::Columns && options.shows_column(schema, model, column) =>
+        {
+            format!("{}::{}", ids.id(table_schema, table), column)
+        }
+        _ => ids.id(table_schema, table).to_string(),
+    }
+}
+
+/// Name of the package holding tables that lack the grouping property.
+const UNGROUPED: &str = "Ungrouped";
+
+/// Groups tables by the value of an extended property, in order of first
+/// appearance, with tables lacking the property collected last.
+fn group_by_property<'a>(schema: &'a DatabaseSchema, property: &str) -> Vec<(&'a str, Vec<&'a Table>)> {
+    let mut groups: Vec<(&str, Vec<&Table>)> = Vec::new();
+    let mut ungrouped = Vec::new();
+    for table in &schema.tables {
+        match table.properties.get(property) {
+            Some(value) => match groups.iter_mut().find(|(group, _)| *group == value.as_str()) {
+                Some((_, tables)) => tables.push(table),
+                None => groups.push((value.as_str(), vec![table])),
+            },
+            None => ungrouped.push(table),
+        }
+    }
+    if !ungrouped.is_empty() {
+        groups.push((UNGROUPED, ungrouped));
+    }
+    groups
+}
+
+fn write_class<W: Write>(
+    writer: &mut W,
+    schema: &DatabaseSchema,
+    table: &Table,
+    ids: &ClassIds,
+    options: &RenderOptions,
+) -> io::Result<()> {
+    let indent = options.indent(2, 1);
+    let declaration = ids.declaration(&table.schema, &table.name, options);
+    if table.external {
+        return writeln!(writer, "class {} <<external>>", declaration);
+    }
+    let mut header = format!("class {}", declaration);
+    if table.table_type {
+        header.push_str(" <<table type>>");
+    } else if options.highlight_no_pk && table.primary_key.is_empty() {
+        header.push_str(" <<no-pk>>");
+    }
+    if let Some(filegroup) = table.filegroup.as_deref().filter(|_| options.show_filegroups) {
+        if !(options.hide_primary_filegroup && filegroup.eq_ignore_ascii_case("PRIMARY")) {
+            header.push_str(&format!(" <<filegroup: {}>>", filegroup));
+        }
+    }
+    if options.show_triggers && !table.triggers.is_empty() {
+        let instead_of = table.triggers.iter().filter(|trigger| trigger.instead_of).count();
+        if instead_of > 0 {
+            header.push_str(&format!(" <<triggers: {}, {} instead of>>", table.triggers.len(), instead_of));
+        } else {
+            header.push_str(&format!(" <<triggers: {}>>", table.triggers.len()));
+        }
+    }
+    if let (Some(modified), Some(since)) = (&table.modified_at, &options.modified_since) {
+        if modified > since {
+            header.push_str(" <<modified>>");
+        }
+    }
+    if options.show_usage && !table.table_type {
+        match &table.last_used {
+            Some(used) => {
+                header.push_str(&format!(" <<last used: {}>>", &used[..used.len().min(10)]));
+                if options.cold_since.as_ref().is_some_and(|since| used < since) {
+                    header.push_str(" <<cold>>");
+                }
+            }
+            None => header.push_str(" <<no usage stats>>"),
+        }
+    }
+    if let Some(rows) = table.row_count.filter(|_| options.show_row_counts) {
+        header.push_str(&format!(" <<rows: {}>>", rows));
+    }
+    if let Some(size) = table.size_mb().filter(|_| options.show_sizes) {
+        header.push_str(&format!(" <<size: {:.1} MB>>", size));
+    }
+    if let Some(description) = table.description.as_deref().filter(|_| options.svg_tooltips) {
+        header.push_str(&format!(" {}", tooltip(description)));
+    }
+    if options.color_by == ColorBy::Schema {
+        header.push_str(&format!(" {}", schema_color(&table.schema, options.seed)));
+    }
+    writeln!(writer, "{} {{", header)?;
+    if options.columns_summary {
+        writeln!(writer, "{}{}", indent, columns_summary(schema, table))?;
+        return writeln!(writer, "}}");
+    }
+    let columns = options.visible_columns(schema, table);
+    match options.wrap_columns.filter(|&groups| groups > 1 && columns.len() > WRAP_COLUMNS_THRESHOLD) {
+        Some(groups) => write_wrapped_columns(writer, schema, table, columns, groups, options)?,
+        None => write_columns(writer, schema, table, columns, options)?,
+    }
+    let truncated = options.truncated_columns(schema, table);
+    if truncated > 0 {
+        writeln!(writer, "{}{}", indent, truncation_line(truncated))?;
+    }
+    if options.show_indexes && !table.indexes.is_empty() {
+        writeln!(writer, "{}--", indent)?;
+        for index in &table.indexes {
+            writeln!(writer, "{}{}", indent, index)?;
+        }
+    }
+    writeln!(writer, "}}")
+}
+
+fn write_columns<W: Write>(
+    writer: &mut W,
+    schema: &DatabaseSchema,
+    table: &Table,
+    columns: Vec<&Column>,
+    options: &RenderOptions,
+) -> io::Result<()> {
+    let indent = options.indent(2, 1);
+    for column in columns {
+        let mut line = member_line(schema, table, column, options);
+        let spots = if options.key_spots {
+            key_spots(schema, table, &column.name, options.pfk_marker)
+        } else {
+            (options.pfk_marker && is_pk_and_fk(schema, table, &column.name)).then(|| PFK_SPOT.to_string())
+        };
+        if let Some(spots) = spots {
+            line.push_str(&format!(" {}", spots));
+        }
+        if options.group_composite_keys {
+            for (tag, _, _) in composite_keys(table).iter().filter(|(_, _, key)| key.contains(&column.name)) {
+                line.push_str(&format!(" <<{}>>", tag));
+            }
+        }
+        if let Some(description) = column.description.as_deref().filter(|_| options.svg_tooltips) {
+            line.push_str(&format!(" {}", tooltip(description)));
+        }
+        writeln!(writer, "{}{}", indent, line)?;
+    }
+    Ok(())
+}
+
+/// Writes the columns as a Creole table of `groups` equal lists
+/// side by side, filled top to bottom with the key columns first. Cells hold
+/// only the column line, since spots and tooltips don't work inside a table.
+fn write_wrapped_columns<W: Write>(
+    writer: &mut W,
+    schema: &DatabaseSchema,
+    table: &Table,
+    mut columns: Vec<&Column>,
+    groups: usize,
+    options: &RenderOptions,
+) -> io::Result<()> {
+    let indent = options.indent(2, 1);
+    columns.sort_by_key(|column| key_spots(schema, table, &column.name, false).is_none());
+    let rows = columns.len().div_ceil(groups);
+    for row in 0..rows {
+        let cells: Vec<String> = (0..groups)
+            .map(|group| {
+                columns
+                    .get(group * rows + row)
+                    .map(|column| format!(" {} ", member_line(schema, table, column, options)))
+                    .unwrap_or_else(|| " ".to_string())
+            })
+            .collect();
+        writeln!(writer, "{}|{}|", indent, cells.join("|"))?;
+    }
+    Ok(())
+}
+
+/// A link with no target and `text` as its tooltip, which PlantUML's SVG
+/// output shows on hover. Braces and brackets would end the link early, so
+/// they become parentheses.
+fn tooltip(text: &str) -> String {
+    let text: String = single_line(text)
+        .chars()
+        .map(|c| match c {
+            '{' | '[' => '(',
+            '}' | ']' => ')',
+            c => c,
+        })
+        .collect();
+    format!("[[{{{}}}]]", text)
+}
+
+/// Class bodies with more visible columns than this are split by
+/// `RenderOptions::wrap_columns`.
+const WRAP_COLUMNS_THRESHOLD: usize = 20;
+
+const PK_SPOT: &str = "<< (P,#FFAA00) PK >>";
+const FK_SPOT: &str = "<< (F,#AAAAFF) FK >>";
+const UNIQUE_SPOT: &str = "<< (U,#AAFFAA) U >>";
+const PFK_SPOT: &str = "<< (K,#FFCC66) PFK >>";
+
+/// Whether `column` is both in the primary key of `table` and the child
+/// column of one of its foreign keys, as in junction and weak-entity tables.
+fn is_pk_and_fk(schema: &DatabaseSchema, table: &Table, column: &str) -> bool {
+    table.primary_key.iter().any(|key| key == column)
+        && schema.references.iter().any(|reference| {
+            reference.schema == table.schema && reference.table == table.name && reference.column == column
+        })
+}
+
+/// The spotted stereotypes for a key column: primary key, foreign key, or a
+/// unique key that another table's foreign key points at. With `combine`, a
+/// column in both the primary key and a foreign key gets a single PFK spot.
+fn key_spots(schema: &DatabaseSchema, table: &Table, column: &str, combine: bool) -> Option<String> {
+    let mut spots = Vec::new();
+    let in_table = |table_schema: &str, name: &str| table_schema == table.schema && name == table.name;
+    if combine && is_pk_and_fk(schema, table, column) {
+        spots.push(PFK_SPOT);
+    } else {
+        if table.primary_key.iter().any(|key| key == column) {
+            spots.push(PK_SPOT);
+        }
+        if schema
+            .references
+            .iter()
+            .any(|reference| in_table(&reference.schema, &reference.table) && reference.column == column)
+        {
+            spots.push(FK_SPOT);
+        }
+    }
+    if schema.references.iter().any(|reference| {
+        !reference.references_pk
+            && in_table(&reference.referenced_schema, &reference.referenced_table)
+            && reference.referenced_column == column
+    }) {
+        spots.push(UNIQUE_SPOT);
+    }
+    (!spots.is_empty()).then(|| spots.join(" "))
+}
+
+/// The composite keys of `table` with the tag their columns carry and a
+/// description: the primary key as `PK1`, then unfiltered unique indexes as
+/// `UK1`, `UK2`, ... in index order. Single-column keys are left out.
+fn composite_keys(table: &Table) -> Vec<(String, String, &[String])> {
+    let mut keys = Vec::new();
+    if table.primary_key.len() > 1 {
+        keys.push(("PK1".to_string(), "composite key".to_string(), table.primary_key.as_slice()));
+    }
+    let unique = table.indexes.iter().filter(|index| index.unique && index.filter.is_none() && index.columns.len() > 1);
+    for (n, index) in unique.enumerate() {
+        keys.push((format!("UK{}", n + 1), format!("unique key {}", index.name), index.columns.as_slice()));
+    }
+    keys
+}
+
+fn table_pair(reference: &Reference) -> (&str, &str, &str, &str) {
+    (
+        &reference.schema,
+        &reference.table,
+        &reference.referenced_schema,
+        &reference.referenced_table,
+    )
+}
+
+/// Writes one edge for a group of references between the same two tables.
+/// A lone reference is anchored on its columns; a merged group links the
+/// classes and lists every column pair.
+fn write_edge<W: Write>(
+    writer: &mut W,
+    schema: &DatabaseSchema,
+    ids: &ClassIds,
+    options: &RenderOptions,
+    group: &[&Reference],
+    in_cycle: bool,
+    direction: Option<&str>,
+) -> io::Result<()> {
+    let first = group[0];
+    if options.detect_inheritance && is_inheritance(schema, group) {
+        let disabled = group.iter().all(|reference| reference.is_disabled);
+        let arrow = match (direction, disabled) {
+            // The arrow points back at the parent, so the direction flips
+            (Some(direction), false) => format!("<|-{}-", opposite(direction)),
+            (Some(direction), true) => format!("<|.{}.", opposite(direction)),
+            (None, false) => "<|--".to_string(),
+            (None, true) => "<|..".to_string(),
+        };
+        return writeln!(
+            writer,
+            "{} {} {}",
+            ids.id(&first.referenced_schema, &first.referenced_table),
+            arrow,
+            ids.id(&first.schema, &first.table)
+        );
+    }
+    let mut arrow = options.arrow.connector(group.iter().all(|reference| reference.is_disabled)).to_string();
+    if let Some(direction) = direction {
+        // `-->` becomes `-up->`, `*..>` becomes `*.up.>`
+        let line = arrow.find(['-', '.']).unwrap_or(0);
+        arrow.insert_str(line + 1, direction);
+    }
+    if options.fk_cardinality {
+        let many = if is_one_to_one(schema, group) { "0..1" } else { "*" };
+        arrow = format!("\"{}\" {} \"1\"", many, arrow);
+    }
+    let mut key = String::new();
+    if group.iter().any(|reference| !reference.references_pk) {
+        key.push_str(" <<unique>>");
+    }
+    if in_cycle {
+        key.push_str(" <<cycle>>");
+    }
+    if options.highlight_type_mismatches && group.iter().any(|reference| type_mismatch(schema, reference).is_some()) {
+        key.push_str(" <<type mismatch>>");
+    }
+    let (from, to, mut label) = match group {
+        [reference] => (
+            endpoint(schema, ids, options, &reference.schema, &reference.table, &reference.column),
+            endpoint(
+                schema,
+                ids,
+                options,
+                &reference.referenced_schema,
+                &reference.referenced_table,
+                &reference.referenced_column
+            ),
+            reference_label(reference, options),
+        ),
+        _ => (
+            ids.id(&first.schema, &first.table).to_string(),
+            ids.id(&first.referenced_schema, &first.referenced_table).to_string(),
+            edge_label(group),
+        ),
+    };
+    if options.ef_hints {
+        label.push_str(&format!(" [{}]", navigation_hint(schema, group)));
+    }
+    if options.cardinality_hints {
+        let child = find_table(schema, &first.schema, &first.table);
+        let parent = find_table(schema, &first.referenced_schema, &first.referenced_table);
+        if let Some(ratio) = child.zip(parent).and_then(|(child, parent)| cardinality_ratio(child, parent)) {
+            label.push_str(&format!(" ({})", ratio));
+        }
+    }
+    writeln!(writer, "{} {} {} : {}{}", from, arrow, to, label, key)
+}
+
+/// Whether the foreign key columns of `group` are exactly its table's primary
+/// key, pointing at another table's primary key: table-per-type inheritance.
+fn is_inheritance(schema: &DatabaseSchema, group: &[&Reference]) -> bool {
+    let first = group[0];
+    if (first.schema.as_str(), first.table.as_str()) == (first.referenced_schema.as_str(), first.referenced_table.as_str())
+        || !group.iter().all(|reference| reference.references_pk)
+    {
+        return false;
+    }
+    let Some(child) = find_table(schema, &first.schema, &first.table) else {
+        return false;
+    };
+    let columns: HashSet<&str> = group.iter().map(|reference| reference.column.as_str()).collect();
+    let key: HashSet<&str> = child.primary_key.iter().map(String::as_str).collect();
+    !key.is_empty() && columns == key
+}
+
+/// The approximate number of `child` rows per `parent` row, as `~100:1`, or
+/// `~1:4` when parents outnumber children. Tables without a row count or
+/// with no rows get no ratio.
+fn cardinality_ratio(child: &Table, parent: &Table) -> Option<String> {
+    let (children, parents) = (child.row_count.filter(|&rows| rows > 0)?, parent.row_count.filter(|&rows| rows > 0)?);
+    if children >= parents {
+        Some(format!("~{}:1", (children as f64 / parents as f64).round()))
+    } else {
+        Some(format!("~1:{}", (parents as f64 / children as f64).round()))
+    }
+}
+
+/// Directions handed out, in turn, to the tables around the pivot.
+const PIVOT_DIRECTIONS: [&str; 4] = ["up", "right", "down", "left"];
+
+fn opposite(direction: &str) -> &'static str {
+    match direction {
+        "up" => "down",
+        "down" => "up",
+        "left" => "right",
+        _ => "left",
+    }
+}
+
+/// Direction hints for the edges touching the pivot table, keyed by the
+/// edge's table pair, so that its neighbours are spread around it.
+fn pivot_directions<'a>(
+    schema: &'a DatabaseSchema,
+    options: &RenderOptions,
+) -> HashMap<(&'a str, &'a str, &'a str, &'a str), &'static str> {
+    let mut directions = HashMap::new();
+    let pivot = match &options.pivot {
+        Some(pattern) => schema
+            .tables
+            .iter()
+            .find(|table| pattern.matches(&table.schema, &table.name, options.case_sensitive)),
+        None => None,
+    };
+    let Some(pivot) = pivot else {
+        return directions;
+    };
+    let is_pivot = |table_schema: &str, name: &str| table_schema == pivot.schema && name == pivot.name;
+    let mut neighbours: Vec<(&str, &str)> = Vec::new();
+    for reference in &schema.references {
+        let from_pivot = is_pivot(&reference.schema, &reference.table);
+        let to_pivot = is_pivot(&reference.referenced_schema, &reference.referenced_table);
+        if from_pivot == to_pivot {
+            continue;
+        }
+        let neighbour = if from_pivot {
+            (reference.referenced_schema.as_str(), reference.referenced_table.as_str())
+        } else {
+            (reference.schema.as_str(), reference.table.as_str())
+        };
+        let slot = match neighbours.iter().position(|known| *known == neighbour) {
+            Some(slot) => slot,
+            None => {
+                neighbours.push(neighbour);
+                neighbours.len() - 1
+            }
+        };
+        let outward = PIVOT_DIRECTIONS[slot % PIVOT_DIRECTIONS.len()];
+        // An edge points from its source, so place the neighbour outward
+        // from the pivot whichever end it sits on
+        let direction = if from_pivot { outward } else { opposite(outward) };
+        directions.insert(table_pair(reference), direction);
+    }
+    directions
+}
+
+/// Points the edges of `--layered` diagrams up, keyed by the edge's table
+/// pair, so every referenced table is drawn above the tables referencing it.
+/// The references `layers` broke cycles at keep the default direction.
+fn layered_directions<'a>(
+    schema: &'a DatabaseSchema,
+    options: &RenderOptions,
+) -> HashMap<(&'a str, &'a str, &'a str, &'a str), &'static str> {
+    let (layers, _) = layers(schema, options.seed);
+    let layer: HashMap<(&str, &str), usize> = layers
+        .iter()
+        .enumerate()
+        .flat_map(|(i, tables)| tables.iter().map(move |table| ((table.schema.as_str(), table.name.as_str()), i)))
+        .collect();
+    schema
+        .references
+        .iter()
+        .filter(|reference| {
+            let from = layer.get(&(reference.schema.as_str(), reference.table.as_str()));
+            let to = layer.get(&(reference.referenced_schema.as_str(), reference.referenced_table.as_str()));
+            matches!((from, to), (Some(from), Some(to)) if to < from)
+        })
+        .map(|reference| (table_pair(reference), "up"))
+        .collect()
+}
+
+/// The tables with more relationships than `max_edges_per_table`, whose
+/// edges are replaced by a `hub_note`. Each foreign key constraint counts
+/// once, and a self-reference once.
+fn hub_tables<'a>(schema: &'a DatabaseSchema, options: &RenderOptions) -> HashSet<(&'a str, &'a str)> {
+    let Some(max) = options.max_edges_per_table else {
+        return HashSet::new();
+    };
+    let mut counts: HashMap<(&str, &str), usize> = HashMap::new();
+    for group in group_by_constraint(&schema.references) {
+        let reference = group[0];
+        let from = (reference.schema.as_str(), reference.table.as_str());
+        let to = (reference.referenced_schema.as_str(), reference.referenced_table.as_str());
+        *counts.entry(from).or_default() += 1;
+        if to != from {
+            *counts.entry(to).or_default() += 1;
+        }
+    }
+    counts.into_iter().filter(|&(_, count)| count > max).map(|(table, _)| table).collect()
+}
+
+/// The lines of the note listing a hub table's relationships: `→ Parent
+/// (columns)` for each foreign key it declares, then `← Child (columns)` for
+/// each one pointing at it.
+fn hub_note(schema: &DatabaseSchema, table: &Table) -> Vec<String> {
+    let groups = group_by_constraint(&schema.references);
+    let columns = |group: &[&Reference]| {
+        group.iter().map(|reference| reference.column.as_str()).collect::<Vec<_>>().join(", ")
+    };
+    let outgoing = groups
+        .iter()
+        .filter(|group| group[0].schema == table.schema && group[0].table == table.name)
+        .map(|group| format!("→ {} ({})", group[0].referenced_table, columns(group)));
+    let incoming = groups
+        .iter()
+        .filter(|group| group[0].referenced_schema == table.schema && group[0].referenced_table == table.name)
+        .map(|group| format!("← {} ({})", group[0].table, columns(group)));
+    outgoing.chain(incoming).collect()
+}
+
+/// Longest sample value shown before it is cut short with `…`.
+const SAMPLE_VALUE_WIDTH: usize = 24;
+
+fn sample_value(value: Option<&str>) -> String {
+    let value = match value {
+        Some(value) => single_line(value),
+        None => return "NULL".to_string(),
+    };
+    let mut shown: String = value.chars().take(SAMPLE_VALUE_WIDTH).collect();
+    if value.chars().count() > SAMPLE_VALUE_WIDTH {
+        shown.pop();
+        shown.push('…');
+    }
+    shown.replace('|', "~|")
+}
+
+/// Formats a table's sample rows as the lines of a creole table, limited to
+/// the visible columns.
+fn sample_note(schema: &DatabaseSchema, table: &Table, options: &RenderOptions) -> Vec<String> {
+    let visible: Vec<usize> = options
+        .visible_columns(schema, table)
+        .iter()
+        .filter_map(|column| table.columns.iter().position(|candidate| candidate.name == column.name))
+        .collect();
+    if visible.is_empty() || table.sample_rows.is_empty() {
+        return Vec::new();
+    }
+    let header: Vec<String> = visible.iter().map(|&i| format!("= {}", table.columns[i].name)).collect();
+    let mut lines = vec![format!("|{}|", header.join("|"))];
+    for row in &table.sample_rows {
+        let values: Vec<String> = visible
+            .iter()
+            .map(|&i| format!(" {} ", sample_value(row.get(i).and_then(|value| value.as_deref()))))
+            .collect();
+        lines.push(format!("|{}|", values.join("|")));
+    }
+    lines
+}
+
+/// Writes the PlantUML class diagram straight to `writer`.
+pub fn render_plantuml<W: Write>(schema: &DatabaseSchema, writer: &mut W, options: &RenderOptions) -> io::Result<()> {
+    let ids = ClassIds::new(schema, options);
+    write_prelude(writer, options)?;
+    match &options.group_by_property {
+        Some(property) => {
+            for (group, tables) in group_by_property(schema, property) {
+                writeln!(writer, "package \"{}\" {{", group.replace('"', "'"))?;
+                for table in tables {
+                    write_class(writer, schema, table, &ids, options)?;
+                }
+                writeln!(writer, "}}")?;
+            }
+        }
+        None if options.layered => {
+            for layer in layers(schema, options.seed).0 {
+                if layer.len() == 1 {
+                    write_class(writer, schema, layer[0], &ids, options)?;
+                    continue;
+                }
+                writeln!(writer, "together {{")?;
+                for table in layer {
+                    write_class(writer, schema, table, &ids, options)?;
+                }
+                writeln!(writer, "}}")?;
+            }
+        }
+        None if options.together || options.cluster_layout => {
+            let clusters = if options.cluster_layout {
+                communities(schema)
+            } else {
+                connected_components(schema)
+            };
+            for component in clusters {
+                if component.len() == 1 {
+                    write_class(writer, schema, component[0], &ids, options)?;
+                    continue;
+                }
+                writeln!(writer, "together {{")?;
+                for table in component {
+                    write_class(writer, schema, table, &ids, options)?;
+                }
+                writeln!(writer, "}}")?;
+            }
+        }
+        None => {
+            for table in &schema.tables {
+                write_class(writer, schema, table, &ids, options)?;
+            }
+        }
+    }
+    write_epilogue(writer, schema, &ids, options)
+}
+
+fn write_prelude<W: Write>(writer: &mut W, options: &RenderOptions) -> io::Result<()> {
+    writeln!(writer, "@startuml")?;
+    if options.compact {
+        writeln!(writer, "hide empty members")?;
+        writeln!(writer, "hide circle")?;
+    }
+    if options.layout == Layout::LeftRight {
+        writeln!(writer, "left to right direction")?;
+    }
+    Ok(())
+}
+
+/// Writes everything after the classes: annotation and sample notes,
+/// relationships and legends.
+fn write_epilogue<W: Write>(
+    writer: &mut W,
+    schema: &DatabaseSchema,
+    ids: &ClassIds,
+    options: &RenderOptions,
+) -> io::Result<()> {
+    let indent = options.indent(2, 1);
+    for table in &schema.tables {
+        if let Some(annotation) = &table.annotation {
+            writeln!(writer, "note top of {}", ids.id(&table.schema, &table.name))?;
+            for line in annotation.lines() {
+                writeln!(writer, "{}{}", indent, line)?;
+            }
+            writeln!(writer, "end note")?;
+        }
+        let note = sample_note(schema, table, options);
+        if !note.is_empty() {
+            writeln!(writer, "note bottom of {}", ids.id(&table.schema, &table.name))?;
+            for line in note {
+                writeln!(writer, "{}{}", indent, line)?;
+            }
+            writeln!(writer, "end note")?;
+        }
+    }
+    if options.group_composite_keys {
+        let tables = schema.tables.iter().filter(|table| !table.external);
+        for table in tables.filter(|table| options.shows_columns(&table.schema, &table.name)) {
+            let keys = composite_keys(table);
+            if keys.is_empty() {
+                continue;
+            }
+            writeln!(writer, "note left of {}", ids.id(&table.schema, &table.name))?;
+            for (tag, description, columns) in keys {
+                writeln!(writer, "{}{}: {} ({})", indent, tag, description, columns.join(", "))?;
+            }
+            writeln!(writer, "end note")?;
+        }
+    }
+    let hubs = hub_tables(schema, options);
+    for table in schema.tables.iter().filter(|table| hubs.contains(&(table.schema.as_str(), table.name.as_str()))) {
+        writeln!(writer, "note right of {}", ids.id(&table.schema, &table.name))?;
+        for line in hub_note(schema, table) {
+            writeln!(writer, "{}{}", indent, line)?;
+        }
+        writeln!(writer, "end note")?;
+    }
+    let drawn = |reference: &Reference| {
+        !hubs.contains(&(reference.schema.as_str(), reference.table.as_str()))
+            && !hubs.contains(&(reference.referenced_schema.as_str(), reference.referenced_table.as_str()))
+    };
+    let in_cycle = if options.highlight_cycles {
+        cycle_references(schema)
+    } else {
+        vec![false; schema.references.len()]
+    };
+    let directions = if options.layered {
+        layered_directions(schema, options)
+    } else {
+        pivot_directions(schema, options)
+    };
+    if options.merge_parallel_edges || options.verbose_labels {
+        let cycle_pairs: HashSet<(&str, &str, &str, &str)> = schema
+            .references
+            .iter()
+            .zip(in_cycle)
+            .filter(|(_, in_cycle)| *in_cycle)
+            .map(|(reference, _)| table_pair(reference))
+            .collect();
+        for group in edge_groups(&schema.references, options) {
+            if !drawn(group[0]) {
+                continue;
+            }
+            let pair = table_pair(group[0]);
+            let direction = directions.get(&pair).copied();
+            write_edge(writer, schema, ids, options, &group, cycle_pairs.contains(&pair), direction)?;
+        }
+    } else {
+        for (reference, in_cycle) in schema.references.iter().zip(in_cycle) {
+            if !drawn(reference) {
+                continue;
+            }
+            let direction = directions.get(&table_pair(reference)).copied();
+            write_edge(writer, schema, ids, options, &[reference], in_cycle, direction)?;
+        }
+    }
+    if options.history_links {
+        for table in &schema.tables {
+            let Some((history_schema, history_table)) = &table.history_table else {
+                continue;
+            };
+            if find_table(schema, history_schema, history_table).is_some() {
+                writeln!(
+                    writer,
+                    "{} .. {} : <<history>>",
+                    ids.id(&table.schema, &table.name),
+                    ids.id(history_schema, history_table)
+                )?;
+            }
+        }
+    }
+    if let Some(limit) = options.proc_dependencies {
+        for (i, (procedure, tables)) in procedure_dependencies(schema).into_iter().take(limit).enumerate() {
+            let id = format!("p{}", i + 1);
+            writeln!(writer, "class \"{}\" as {} <<proc>>", procedure.replace('"', "'"), id)?;
+            for table in tables {
+                writeln!(writer, "{} ..> {}", id, ids.id(&table.schema, &table.name))?;
+            }
+        }
+    }
+    if options.resolve_indirection {
+        for (i, (view, tables)) in view_dependencies(schema).into_iter().enumerate() {
+            let id = format!("v{}", i + 1);
+            writeln!(writer, "class \"{}\" as {} <<view>>", view.replace('"', "'"), id)?;
+            for table in tables {
+                writeln!(writer, "{} ..> {} : depends on", id, ids.id(&table.schema, &table.name))?;
+            }
+        }
+        let synonyms = schema
+            .tables
+            .iter()
+            .flat_map(|table| table.synonyms.iter().map(move |synonym| (synonym, table)));
+        for (i, (synonym, table)) in synonyms.enumerate() {
+            let id = format!("s{}", i + 1);
+            writeln!(writer, "class \"{}\" as {} <<synonym>>", synonym.replace('"', "'"), id)?;
+            writeln!(writer, "{} ..> {} : alias", id, ids.id(&table.schema, &table.name))?;
+        }
+    }
+    if options.purpose_legend {
+        let described: Vec<&Table> = schema.tables.iter().filter(|table| table.description.is_some()).collect();
+        if !described.is_empty() {
+            writeln!(writer, "note as PurposeLegend")?;
+            for table in described {
+                let description = table.description.as_deref().unwrap_or_default();
+                writeln!(writer, "{}{}: {}", indent, table.name, single_line(description))?;
+            }
+            writeln!(writer, "end note")?;
+        }
+    }
+    if options.type_legend {
+        let counts = type_counts(schema);
+        if !counts.is_empty() {
+            writeln!(writer, "note as TypeLegend")?;
+            for (data_type, count) in counts {
+                writeln!(writer, "{}{}: {}", indent, data_type, count)?;
+            }
+            writeln!(writer, "end note")?;
+        }
+    }
+    if let Some(server_info) = &options.server_info {
+        writeln!(writer, "note as ServerInfo")?;
+        for line in server_info.note_lines() {
+            writeln!(writer, "{}{}", indent, line)?;
+        }
+        writeln!(writer, "end note")?;
+    }
+    if !options.provenance.is_empty() {
+        writeln!(writer, "note as Provenance")?;
+        for line in &options.provenance {
+            writeln!(writer, "{}{}", indent, line)?;
+        }
+        writeln!(writer, "end note")?;
+    }
+    writeln!(writer, "@enduml")?;
+    Ok(())
+}
+
+/// Writes a PlantUML diagram class by class as tables arrive, for schemas
+/// too large to wait for. Classes are written as a plain list, since
+/// packages and `together` blocks need every table up front; relationships
+/// are written by `finish` once the whole schema is known.
+pub struct PlantUmlStream<'a, W: Write> {
+    writer: W,
+    options: &'a RenderOptions,
+    /// The references classes are drawn against, for key columns and spots.
+    references: DatabaseSchema,
+    written: HashSet<(String, String)>,
+    ids: ClassIds,
+}
+
+impl<'a, W: Write> PlantUmlStream<'a, W> {
+    pub fn start(mut writer: W, references: Vec<Reference>, options: &'a RenderOptions) -> io::Result<Self> {
+        write_prelude(&mut writer, options)?;
+        let references = DatabaseSchema { tables: Vec::new(), references };
+        Ok(PlantUmlStream {
+            writer,
+            options,
+            ids: ClassIds::new(&references, options),
+            references,
+            written: HashSet::new(),
+        })
+    }
+
+    pub fn write_table(&mut self, table: &Table) -> io::Result<()> {
+        self.ids.add(&table.schema, &table.name, self.options);
+        write_class(&mut self.writer, &self.references, table, &self.ids, self.options)?;
+        self.written.insert((table.schema.clone(), table.name.clone()));
+        self.writer.flush()
+    }
+
+    /// Writes the classes of `schema` not streamed yet, such as external
+    /// stubs, then its relationships, and returns the writer.
+    pub fn finish(mut self, schema: &DatabaseSchema) -> io::Result<W> {
+        for table in &schema.tables {
+            if !self.written.contains(&(table.schema.clone(), table.name.clone())) {
+                self.ids.add(&table.schema, &table.name, self.options);
+                write_class(&mut self.writer, schema, table, &self.ids, self.options)?;
+            }
+        }
+        write_epilogue(&mut self.writer, schema, &self.ids, self.options)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+pub fn generate_plantuml(schema: &DatabaseSchema, options: &RenderOptions) -> String {
+    let mut buffer = Vec::new();
+    render_plantuml(schema, &mut buffer, options).expect("writing to a Vec<u8> cannot fail");
+    String::from_utf8(buffer).expect("PlantUML output is valid UTF-8")
+}
+
+pub struct PlantUmlFormat;
+
+impl OutputFormat for PlantUmlFormat {
+    fn name(&self) -> &str {
+        "plantuml"
+    }
+
+    fn extension(&self) -> &str {
+        "puml"
+    }
+
+    fn comment(&self, line: &str) -> Option<String> {
+        Some(format!("' {}", line))
+    }
+
+    fn render(&self, schema: &DatabaseSchema, options: &RenderOptions) -> Result<String, DiagramError> {
+        Ok(generate_plantuml(schema, options))
+    }
+
+    fn write(&self, schema: &DatabaseSchema, writer: &mut dyn Write, options: &RenderOptions) -> Result<(), DiagramError> {
+        let mut writer = writer;
+        render_plantuml(schema, &mut writer, options)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ServerInfo;
+    use crate::testing::{reference, schema, table, two_triangles};
+
+    /// The classes of each `together` block, by alias or name, in order.
+    fn together_blocks(plantuml: &str) -> Vec<Vec<String>> {
+        let mut blocks = Vec::new();
+        let mut current: Option<Vec<String>> = None;
+        let mut in_class = false;
+        for line in plantuml.lines() {
+            match line {
+                "together {" => current = Some(Vec::new()),
+                "}" if in_class => in_class = false,
+                "}" => blocks.extend(current.take()),
+                _ if line.starts_with("class ") => {
+                    in_class = line.ends_with('{');
+                    let class = line.trim_end_matches(" {");
+                    let class = class.rsplit_once(" as ").map_or(&class["class ".len()..], |(_, alias)| alias);
+                    if let Some(block) = current.as_mut() {
+                        block.push(class.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        blocks
+    }
+
+    #[test]
+    fn cluster_layout_puts_each_community_together() {
+        let options = RenderOptions {
+            cluster_layout: true,
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&two_triangles(), &options);
+        assert_eq!(together_blocks(&plantuml), [["a", "b", "c"], ["d", "e", "f"]]);
+    }
+
+    /// A three-table cycle across two schemas, giving `--seed` both a chain
+    /// to break and schemas to colour.
+    fn cycle() -> DatabaseSchema {
+        let columns = ["Id", "NextId"];
+        schema(
+            vec![
+                table("dbo.a", &columns, &["Id"]),
+                table("dbo.b", &columns, &["Id"]),
+                table("sales.c", &columns, &["Id"]),
+            ],
+            vec![
+                reference("FK_a_b", "dbo.a.NextId", "dbo.b.Id"),
+                reference("FK_b_c", "dbo.b.NextId", "sales.c.Id"),
+                reference("FK_c_a", "sales.c.NextId", "dbo.a.Id"),
+            ],
+        )
+    }
+
+    #[test]
+    fn the_same_seed_renders_the_same_diagram() {
+        let render = |seed| {
+            let options = RenderOptions {
+                layered: true,
+                color_by: ColorBy::Schema,
+                seed,
+                ..RenderOptions::default()
+            };
+            generate_plantuml(&cycle(), &options)
+        };
+        assert_eq!(render(Some(7)), render(Some(7)));
+        let outputs: HashSet<String> = (0..16).map(|seed| render(Some(seed))).collect();
+        assert!(outputs.len() > 1, "every seed rendered the same diagram");
+    }
+
+    /// The lines of `plantuml` declaring a class.
+    fn class_lines(plantuml: &str) -> Vec<&str> {
+        plantuml.lines().filter(|line| line.starts_with("class ")).collect()
+    }
+
+    #[test]
+    fn usage_marks_tables_with_and_without_usage_rows() {
+        let mut tables = vec![
+            table("dbo.Recent", &["Id"], &["Id"]),
+            table("dbo.Stale", &["Id"], &["Id"]),
+            table("dbo.Unused", &["Id"], &["Id"]),
+        ];
+        tables[0].last_used = Some("2026-09-30T08:15:00".to_string());
+        tables[1].last_used = Some("2024-02-01T23:59:59".to_string());
+        let options = RenderOptions {
+            show_usage: true,
+            cold_since: Some("2025-01-01T00:00:00".to_string()),
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&schema(tables, Vec::new()), &options);
+        assert_eq!(
+            class_lines(&plantuml),
+            [
+                "class Recent <<last used: 2026-09-30>> {",
+                "class Stale <<last used: 2024-02-01>> <<cold>> {",
+                "class Unused <<no usage stats>> {",
+            ]
+        );
+
+        let recent = schema(vec![table("dbo.Recent", &["Id"], &["Id"])], Vec::new());
+        let plantuml = generate_plantuml(&recent, &RenderOptions::default());
+        assert_eq!(class_lines(&plantuml), ["class Recent {"]);
+    }
+
+    #[test]
+    fn highlight_no_pk_marks_only_keyless_tables() {
+        let tables = vec![table("dbo.Customer", &["Id"], &["Id"]), table("dbo.Log", &["Message"], &[])];
+        let keyed = schema(tables, Vec::new());
+        assert_eq!(crate::analysis::design_warnings(&keyed), ["table dbo.Log has no primary key"]);
+
+        let options = RenderOptions {
+            highlight_no_pk: true,
+            ..RenderOptions::default()
+        };
+        assert_eq!(class_lines(&generate_plantuml(&keyed, &options)), ["class Customer {", "class Log <<no-pk>> {"]);
+        let plain = generate_plantuml(&keyed, &RenderOptions::default());
+        assert!(!plain.contains("<<no-pk>>"), "{}", plain);
+    }
+
+    /// The lines inside the floating note `note as {name}`, or `None` when
+    /// there is no such note.
+    fn note<'a>(plantuml: &'a str, name: &str) -> Option<Vec<&'a str>> {
+        let mut lines = plantuml.lines().skip_while(|line| *line != format!("note as {}", name));
+        lines.next()?;
+        Some(lines.take_while(|line| *line != "end note").collect())
+    }
+
+    #[test]
+    fn purpose_legend_lists_only_described_tables() {
+        let mut tables = vec![
+            table("dbo.Customer", &["Id"], &["Id"]),
+            table("dbo.Scratch", &["Id"], &["Id"]),
+            table("dbo.Order", &["Id"], &["Id"]),
+        ];
+        tables[0].description = Some("People who buy".to_string());
+        tables[2].description = Some("One purchase,\nwith its lines".to_string());
+        let options = RenderOptions {
+            purpose_legend: true,
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&schema(tables, Vec::new()), &options);
+        assert_eq!(
+            note(&plantuml, "PurposeLegend").unwrap(),
+            ["  Customer: People who buy", "  Order: One purchase, with its lines"]
+        );
+
+        let undescribed = schema(vec![table("dbo.Scratch", &["Id"], &["Id"])], Vec::new());
+        assert_eq!(note(&generate_plantuml(&undescribed, &options), "PurposeLegend"), None);
+    }
+
+    /// A customer table referenced by an order table.
+    fn orders() -> DatabaseSchema {
+        schema(
+            vec![
+                table("dbo.Customer", &["Id", "Name:nvarchar(50)"], &["Id"]),
+                table("dbo.Orders", &["Id", "CustomerId"], &["Id"]),
+            ],
+            vec![reference("FK_Orders_Customer", "dbo.Orders.CustomerId", "dbo.Customer.Id")],
+        )
+    }
+
+    #[test]
+    fn default_options_render_the_baseline_diagram() {
+        let baseline = "@startuml\n\
+            class Customer {\n  Id : int\n  Name : nvarchar(50)\n}\n\
+            class Orders {\n  Id : int\n  CustomerId : int\n}\n\
+            Orders::CustomerId --> Customer::Id : CustomerId\n\
+            @enduml\n";
+        assert_eq!(generate_plantuml(&orders(), &RenderOptions::default()), baseline);
+
+        let options = RenderOptions {
+            relationships_only: true,
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&orders(), &options);
+        assert_ne!(plantuml, baseline);
+        assert!(!plantuml.contains(" : int"), "{}", plantuml);
+    }
+
+    /// The member lines of the class declared by `class`, up to its `}`.
+    fn members<'a>(plantuml: &'a str, class: &str) -> Vec<&'a str> {
+        let mut lines = plantuml.lines().skip_while(|line| !line.starts_with(class));
+        lines.next().expect("the class is declared");
+        lines.take_while(|line| *line != "}").collect()
+    }
+
+    #[test]
+    fn no_columns_for_empties_only_matching_tables() {
+        let options = RenderOptions {
+            no_columns_for: crate::filter::parse_patterns("dbo.cust*"),
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&orders(), &options);
+        assert!(members(&plantuml, "class Customer").is_empty(), "{}", plantuml);
+        assert_eq!(members(&plantuml, "class Orders"), ["  Id : int", "  CustomerId : int"]);
+        assert!(plantuml.contains("\nOrders::CustomerId --> Customer : CustomerId\n"), "{}", plantuml);
+    }
+
+    #[test]
+    fn group_by_property_packages_tables_by_value() {
+        let mut tables = vec![
+            table("dbo.Invoice", &["Id"], &["Id"]),
+            table("dbo.Shipment", &["Id"], &["Id"]),
+            table("dbo.Payment", &["Id"], &["Id"]),
+            table("dbo.Scratch", &["Id"], &["Id"]),
+        ];
+        for (table, domain) in tables.iter_mut().zip(["Billing", "Logistics", "Billing"]) {
+            table.properties.insert("Domain".to_string(), domain.to_string());
+        }
+        let options = RenderOptions {
+            group_by_property: Some("Domain".to_string()),
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&schema(tables, Vec::new()), &options);
+        let outline: Vec<_> = plantuml
+            .lines()
+            .filter(|line| line.starts_with("package ") || line.starts_with("class "))
+            .collect();
+        assert_eq!(
+            outline,
+            [
+                "package \"Billing\" {",
+                "class Invoice {",
+                "class Payment {",
+                "package \"Logistics\" {",
+                "class Shipment {",
+                "package \"Ungrouped\" {",
+                "class Scratch {",
+            ]
+        );
+    }
+
+    #[test]
+    fn compact_hides_empty_members_and_show_types_false_drops_types() {
+        let options = RenderOptions {
+            compact: true,
+            hide_types: true,
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&orders(), &options);
+        assert!(plantuml.starts_with("@startuml\nhide empty members\nhide circle\n"), "{}", plantuml);
+        assert_eq!(members(&plantuml, "class Customer"), ["  Id", "  Name"]);
+        let plain = generate_plantuml(&orders(), &RenderOptions::default());
+        assert!(!plain.contains("hide "), "{}", plain);
+    }
+
+    #[test]
+    fn type_legend_counts_columns_by_base_type() {
+        let schema = schema(
+            vec![
+                table("dbo.Customer", &["Id", "Name:nvarchar(50)", "Balance:money"], &["Id"]),
+                table(
+                    "dbo.Orders",
+                    &["Id", "CustomerId", "Total:MONEY", "Ref:uniqueidentifier", "Note:nvarchar(max)"],
+                    &["Id"],
+                ),
+            ],
+            Vec::new(),
+        );
+        let options = RenderOptions {
+            type_legend: true,
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&schema, &options);
+        assert_eq!(
+            note(&plantuml, "TypeLegend").expect("a type legend"),
+            ["  int: 3", "  money: 2", "  nvarchar: 2", "  uniqueidentifier: 1"]
+        );
+        assert!(note(&generate_plantuml(&schema, &RenderOptions::default()), "TypeLegend").is_none());
+    }
+
+    #[test]
+    fn each_arrow_style_emits_its_connector() {
+        let cases = [
+            ("solid", "-->"),
+            ("dashed", "..>"),
+            ("composition", "*-->"),
+            ("aggregation", "o-->"),
+        ];
+        for (style, connector) in cases {
+            let options = RenderOptions {
+                arrow: ArrowStyle::parse(style).expect("a known style"),
+                ..RenderOptions::default()
+            };
+            let plantuml = generate_plantuml(&orders(), &options);
+            let edge = format!("\nOrders::CustomerId {} Customer::Id : CustomerId\n", connector);
+            assert!(plantuml.contains(&edge), "--arrow {}: {}", style, plantuml);
+        }
+        assert_eq!(ArrowStyle::default(), ArrowStyle::Solid);
+        assert_eq!(ArrowStyle::parse("wavy"), None);
+    }
+
+    #[test]
+    fn left_right_layout_and_together_wrap_each_component() {
+        let mut schema = orders();
+        schema.tables.push(table("dbo.Setting", &["Id"], &["Id"]));
+        let options = RenderOptions {
+            layout: Layout::parse("left-right").expect("a known layout"),
+            together: true,
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&schema, &options);
+        assert!(plantuml.starts_with("@startuml\nleft to right direction\n"), "{}", plantuml);
+        assert_eq!(together_blocks(&plantuml), [["Customer", "Orders"]]);
+        assert!(plantuml.contains("}\nclass Setting {\n"), "{}", plantuml);
+        let top_down = generate_plantuml(&schema, &RenderOptions::default());
+        assert!(!top_down.contains("direction"), "{}", top_down);
+        assert!(together_blocks(&top_down).is_empty());
+    }
+
+    #[test]
+    fn filegroups_mark_class_headers_unless_primary_is_hidden() {
+        let mut tables = vec![table("dbo.Archive", &["Id"], &["Id"]), table("dbo.Customer", &["Id"], &["Id"])];
+        tables[0].filegroup = Some("ARCHIVE_FG".to_string());
+        tables[1].filegroup = Some("PRIMARY".to_string());
+        let schema = schema(tables, Vec::new());
+        let options = RenderOptions {
+            show_filegroups: true,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            class_lines(&generate_plantuml(&schema, &options)),
+            ["class Archive <<filegroup: ARCHIVE_FG>> {", "class Customer <<filegroup: PRIMARY>> {"]
+        );
+        let options = RenderOptions {
+            hide_primary_filegroup: true,
+            ..options
+        };
+        assert_eq!(
+            class_lines(&generate_plantuml(&schema, &options)),
+            ["class Archive <<filegroup: ARCHIVE_FG>> {", "class Customer {"]
+        );
+        assert_eq!(
+            class_lines(&generate_plantuml(&schema, &RenderOptions::default())),
+            ["class Archive {", "class Customer {"]
+        );
+    }
+
+    #[test]
+    fn sample_rows_become_a_creole_table_note() {
+        let mut customer = table("dbo.Customer", &["Id", "Name:nvarchar(100)", "Notes:nvarchar(max)"], &["Id"]);
+        let row = |values: [Option<&str>; 3]| values.iter().map(|value| value.map(str::to_string)).collect();
+        customer.sample_rows = vec![
+            row([Some("1"), Some("Ada | Lovelace"), None]),
+            row([Some("2"), Some("Grace\nHopper"), Some("Rear admiral and compiler pioneer")]),
+        ];
+        let plantuml = generate_plantuml(&schema(vec![customer], Vec::new()), &RenderOptions::default());
+        let note: Vec<&str> = plantuml
+            .lines()
+            .skip_while(|line| *line != "note bottom of Customer")
+            .skip(1)
+            .take_while(|line| *line != "end note")
+            .collect();
+        assert_eq!(
+            note,
+            [
+                "  |= Id|= Name|= Notes|",
+                "  | 1 | Ada ~| Lovelace | NULL |",
+                "  | 2 | Grace Hopper | Rear admiral and compil… |",
+            ]
+        );
+    }
+
+    /// Orders referencing Address twice, through distinct constraints.
+    fn two_addresses() -> DatabaseSchema {
+        schema(
+            vec![
+                table("dbo.Address", &["Id"], &["Id"]),
+                table("dbo.Orders", &["Id", "BillingId", "ShippingId"], &["Id"]),
+            ],
+            vec![
+                reference("FK_Orders_Billing", "dbo.Orders.BillingId", "dbo.Address.Id"),
+                reference("FK_Orders_Shipping", "dbo.Orders.ShippingId", "dbo.Address.Id"),
+            ],
+        )
+    }
+
+    #[test]
+    fn merge_parallel_edges_draws_one_labelled_arrow() {
+        let edges = |plantuml: &str| -> Vec<String> {
+            plantuml.lines().filter(|line| line.contains(" --> ")).map(str::to_string).collect()
+        };
+        assert_eq!(
+            edges(&generate_plantuml(&two_addresses(), &RenderOptions::default())),
+            [
+                "Orders::BillingId --> Address::Id : BillingId",
+                "Orders::ShippingId --> Address::Id : ShippingId",
+            ]
+        );
+        let options = RenderOptions {
+            merge_parallel_edges: true,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            edges(&generate_plantuml(&two_addresses(), &options)),
+            ["Orders --> Address : FK_Orders_Billing: BillingId → Id; FK_Orders_Shipping: ShippingId → Id"]
+        );
+    }
+
+    #[test]
+    fn triggers_are_counted_with_instead_of_ones_called_out() {
+        let trigger = |name: &str, instead_of| crate::model::Trigger {
+            name: name.to_string(),
+            instead_of,
+        };
+        let mut tables = vec![table("dbo.Audited", &["Id"], &["Id"]), table("dbo.Guarded", &["Id"], &["Id"])];
+        tables[0].triggers = vec![trigger("TR_Audited_Log", false)];
+        tables[1].triggers = vec![trigger("TR_Guarded_Log", false), trigger("TR_Guarded_Block", true)];
+        let schema = schema(tables, Vec::new());
+        let options = RenderOptions {
+            show_triggers: true,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            class_lines(&generate_plantuml(&schema, &options)),
+            ["class Audited <<triggers: 1>> {", "class Guarded <<triggers: 2, 1 instead of>> {"]
+        );
+        assert_eq!(
+            class_lines(&generate_plantuml(&schema, &RenderOptions::default())),
+            ["class Audited {", "class Guarded {"]
+        );
+    }
+
+    #[test]
+    fn key_spots_mark_primary_and_foreign_key_columns() {
+        let options = RenderOptions {
+            key_spots: true,
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&orders(), &options);
+        assert_eq!(
+            members(&plantuml, "class Customer"),
+            ["  Id : int << (P,#FFAA00) PK >>", "  Name : nvarchar(50)"]
+        );
+        assert_eq!(
+            members(&plantuml, "class Orders"),
+            ["  Id : int << (P,#FFAA00) PK >>", "  CustomerId : int << (F,#AAAAFF) FK >>"]
+        );
+    }
+
+    #[test]
+    fn pivot_spreads_its_neighbours_around_it() {
+        let schema = schema(
+            vec![
+                table("dbo.Customer", &["Id"], &["Id"]),
+                table("dbo.Orders", &["Id", "CustomerId", "StoreId"], &["Id"]),
+                table("dbo.Lines", &["Id", "OrderId", "ProductId"], &["Id"]),
+                table("dbo.Store", &["Id"], &["Id"]),
+                table("dbo.Product", &["Id"], &["Id"]),
+            ],
+            vec![
+                reference("FK_Orders_Customer", "dbo.Orders.CustomerId", "dbo.Customer.Id"),
+                reference("FK_Lines_Orders", "dbo.Lines.OrderId", "dbo.Orders.Id"),
+                reference("FK_Orders_Store", "dbo.Orders.StoreId", "dbo.Store.Id"),
+                reference("FK_Lines_Product", "dbo.Lines.ProductId", "dbo.Product.Id"),
+            ],
+        );
+        let options = RenderOptions {
+            pivot: Some(crate::filter::NamePattern::parse("orders")),
+            ..RenderOptions::default()
+        };
+        let directions = pivot_directions(&schema, &options);
+        let direction = |i: usize| directions.get(&table_pair(&schema.references[i])).copied();
+        assert_eq!([direction(0), direction(1), direction(2)], [Some("up"), Some("left"), Some("down")]);
+        assert_eq!(direction(3), None);
+
+        let plantuml = generate_plantuml(&schema, &options);
+        let edges: Vec<&str> = plantuml.lines().filter(|line| line.contains("->")).collect();
+        assert_eq!(
+            edges,
+            [
+                "Orders::CustomerId -up-> Customer::Id : CustomerId",
+                "Lines::OrderId -left-> Orders::Id : OrderId",
+                "Orders::StoreId -down-> Store::Id : StoreId",
+                "Lines::ProductId --> Product::Id : ProductId",
+            ]
+        );
+    }
+
+    #[test]
+    fn max_columns_per_table_keeps_keys_and_counts_the_rest() {
+        let schema = schema(
+            vec![
+                table("dbo.Customer", &["Id"], &["Id"]),
+                table("dbo.Orders", &["Id", "Placed", "Total", "Notes", "Status", "CustomerId"], &["Id"]),
+            ],
+            vec![reference("FK_Orders_Customer", "dbo.Orders.CustomerId", "dbo.Customer.Id")],
+        );
+        let options = RenderOptions {
+            max_columns_per_table: Some(2),
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&schema, &options);
+        assert_eq!(
+            members(&plantuml, "class Orders"),
+            ["  Id : int", "  Placed : int", "  CustomerId : int", "  … (+3 more)"]
+        );
+        assert_eq!(members(&plantuml, "class Customer"), ["  Id : int"]);
+        assert!(plantuml.contains("\nOrders::CustomerId --> Customer::Id : CustomerId\n"), "{}", plantuml);
+        let json = crate::render::JsonFormat.render(&schema, &options).unwrap();
+        assert!(json.contains("\"Notes\""), "{}", json);
+    }
+
+    /// A plain, non-unique index over `columns`.
+    fn index(name: &str, columns: &[&str]) -> crate::model::Index {
+        crate::model::Index {
+            name: name.to_string(),
+            columns: columns.iter().map(|column| column.to_string()).collect(),
+            descending: Vec::new(),
+            included: Vec::new(),
+            unique: false,
+            filter: None,
+        }
+    }
+
+    #[test]
+    fn filtered_indexes_show_their_predicate() {
+        let mut customer = table("dbo.Customer", &["Id", "Email:nvarchar(200)", "IsActive:bit"], &["Id"]);
+        customer.indexes = vec![
+            crate::model::Index {
+                unique: true,
+                filter: Some("([IsActive]=(1))".to_string()),
+                ..index("UX_Customer_Email", &["Email"])
+            },
+            index("IX_Customer_IsActive", &["IsActive"]),
+        ];
+        let options = RenderOptions {
+            show_indexes: true,
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&schema(vec![customer], Vec::new()), &options);
+        assert_eq!(
+            members(&plantuml, "class Customer"),
+            [
+                "  Id : int",
+                "  Email : nvarchar(200)",
+                "  IsActive : bit",
+                "  --",
+                "  UX_Customer_Email (Email) UNIQUE WHERE ([IsActive]=(1))",
+                "  IX_Customer_IsActive (IsActive)",
+            ]
+        );
+    }
+
+    #[test]
+    fn cardinality_hints_label_edges_with_the_row_ratio() {
+        let options = RenderOptions {
+            cardinality_hints: true,
+            ..RenderOptions::default()
+        };
+        let edge = |customers: Option<u64>, order_rows: Option<u64>| -> String {
+            let mut schema = orders();
+            schema.tables[0].row_count = customers;
+            schema.tables[1].row_count = order_rows;
+            let plantuml = generate_plantuml(&schema, &options);
+            plantuml.lines().find(|line| line.contains("-->")).unwrap().to_string()
+        };
+        assert_eq!(edge(Some(100), Some(10_000)), "Orders::CustomerId --> Customer::Id : CustomerId (~100:1)");
+        assert_eq!(edge(Some(400), Some(100)), "Orders::CustomerId --> Customer::Id : CustomerId (~1:4)");
+        // Empty or uncounted tables have no ratio to show
+        assert_eq!(edge(Some(0), Some(10)), "Orders::CustomerId --> Customer::Id : CustomerId");
+        assert_eq!(edge(None, Some(10)), "Orders::CustomerId --> Customer::Id : CustomerId");
+    }
+
+    #[test]
+    fn shared_primary_keys_render_as_generalization() {
+        let schema = schema(
+            vec![
+                table("dbo.Person", &["Id", "Name"], &["Id"]),
+                table("dbo.Employee", &["Id", "ManagerId"], &["Id"]),
+            ],
+            vec![
+                reference("FK_Employee_Person", "dbo.Employee.Id", "dbo.Person.Id"),
+                reference("FK_Employee_Manager", "dbo.Employee.ManagerId", "dbo.Person.Id"),
+            ],
+        );
+        let edges = |options: &RenderOptions| -> Vec<String> {
+            let plantuml = generate_plantuml(&schema, options);
+            plantuml.lines().filter(|line| line.contains("--")).map(str::to_string).collect()
+        };
+        let options = RenderOptions {
+            detect_inheritance: true,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            edges(&options),
+            ["Person <|-- Employee", "Employee::ManagerId --> Person::Id : ManagerId"]
+        );
+        assert_eq!(
+            edges(&RenderOptions::default()),
+            ["Employee::Id --> Person::Id : Id", "Employee::ManagerId --> Person::Id : ManagerId"]
+        );
+    }
+
+    #[test]
+    fn table_types_render_with_their_stereotype_and_columns() {
+        let mut schema = orders();
+        let mut id_list = table("dbo.OrderIdList", &["OrderId", "Quantity"], &[]);
+        id_list.table_type = true;
+        schema.tables.push(id_list);
+        let options = RenderOptions {
+            highlight_no_pk: true,
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&schema, &options);
+        assert_eq!(class_lines(&plantuml)[2], "class OrderIdList <<table type>> {");
+        assert_eq!(members(&plantuml, "class OrderIdList"), ["  OrderId : int", "  Quantity : int"]);
+        assert_eq!(plantuml.matches("-->").count(), 1, "{}", plantuml);
+    }
+
+    #[test]
+    fn non_default_collations_are_annotated_when_highlighted() {
+        let mut customer = table("dbo.Customer", &["Id", "Code:varchar(10)", "Name:nvarchar(50)"], &["Id"]);
+        // Extraction leaves `collation` unset for columns on the database default
+        customer.columns[1].collation = Some("Latin1_General_BIN2".to_string());
+        let schema = schema(vec![customer], Vec::new());
+        let options = RenderOptions {
+            highlight_collations: true,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            members(&generate_plantuml(&schema, &options), "class Customer"),
+            ["  Id : int", "  Code : varchar(10) {collate Latin1_General_BIN2}", "  Name : nvarchar(50)"]
+        );
+        assert_eq!(
+            members(&generate_plantuml(&schema, &RenderOptions::default()), "class Customer"),
+            ["  Id : int", "  Code : varchar(10)", "  Name : nvarchar(50)"]
+        );
+    }
+
+    #[test]
+    fn since_marks_only_tables_modified_after_the_cutoff() {
+        let mut tables = vec![
+            table("dbo.Before", &["Id"], &["Id"]),
+            table("dbo.AtCutoff", &["Id"], &["Id"]),
+            table("dbo.After", &["Id"], &["Id"]),
+            table("dbo.Undated", &["Id"], &["Id"]),
+        ];
+        tables[0].modified_at = Some("2026-09-30T23:59:59".to_string());
+        tables[1].modified_at = Some("2026-10-01T00:00:00".to_string());
+        tables[2].modified_at = Some("2026-10-01T08:15:00".to_string());
+        let options = RenderOptions {
+            modified_since: Some("2026-10-01T00:00:00".to_string()),
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            class_lines(&generate_plantuml(&schema(tables, Vec::new()), &options)),
+            ["class Before {", "class AtCutoff {", "class After <<modified>> {", "class Undated {"]
+        );
+    }
+
+    #[test]
+    fn sizes_are_shown_in_megabytes_after_row_counts() {
+        let mut schema = orders();
+        schema.tables[0].size_kb = Some(1536);
+        schema.tables[0].row_count = Some(1200);
+        schema.tables[1].size_kb = Some(5120);
+        let options = RenderOptions {
+            show_sizes: true,
+            show_row_counts: true,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            class_lines(&generate_plantuml(&schema, &options)),
+            ["class Customer <<rows: 1200>> <<size: 1.5 MB>> {", "class Orders <<size: 5.0 MB>> {"]
+        );
+        let plain = generate_plantuml(&schema, &RenderOptions::default());
+        assert_eq!(class_lines(&plain), ["class Customer {", "class Orders {"]);
+    }
+
+    #[test]
+    fn svg_tooltips_carry_descriptions_as_links() {
+        let mut schema = orders();
+        schema.tables[0].description = Some("People who\nplace orders".to_string());
+        schema.tables[0].columns[1].description = Some("Full name [legal]".to_string());
+        let options = RenderOptions {
+            svg_tooltips: true,
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&schema, &options);
+        assert_eq!(class_lines(&plantuml)[0], "class Customer [[{People who place orders}]] {");
+        assert_eq!(
+            members(&plantuml, "class Customer"),
+            ["  Id : int", "  Name : nvarchar(50) [[{Full name (legal)}]]"]
+        );
+        assert!(!generate_plantuml(&schema, &RenderOptions::default()).contains("[["));
+    }
+
+    #[test]
+    fn wrap_columns_splits_a_wide_table_into_groups() {
+        let mut names: Vec<String> = (1..=48).map(|i| format!("C{}", i)).collect();
+        names.insert(0, "Id".to_string());
+        names.push("CustomerId".to_string());
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let schema = schema(
+            vec![table("dbo.Customer", &["Id"], &["Id"]), table("dbo.Orders", &names, &["Id"])],
+            vec![reference("FK_Orders_Customer", "dbo.Orders.CustomerId", "dbo.Customer.Id")],
+        );
+        let options = RenderOptions {
+            wrap_columns: Some(3),
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&schema, &options);
+        let rows = members(&plantuml, "class Orders");
+        assert_eq!(rows.len(), 17);
+        assert!(rows.iter().all(|row| row.starts_with("  |") && row.matches('|').count() == 4), "{:?}", rows);
+        // Keys lead the first group, then the rest follow in model order down each group
+        assert_eq!(rows[0], "  | Id : int | C16 : int | C33 : int |");
+        assert_eq!(rows[1], "  | CustomerId : int | C17 : int | C34 : int |");
+        assert_eq!(rows[16], "  | C15 : int | C32 : int | |");
+        // Tables under the threshold keep one member per line
+        assert_eq!(members(&plantuml, "class Customer"), ["  Id : int"]);
+    }
+
+    #[test]
+    fn truncated_table_names_keep_their_edges_through_an_alias() {
+        let long = "CustomerOrderLineItemHistoryArchive";
+        let schema = schema(
+            vec![
+                table("dbo.Orders", &["Id"], &["Id"]),
+                table(&format!("dbo.{}", long), &["Id", "OrderId"], &["Id"]),
+                table("dbo.Note", &["Id", "LineId"], &["Id"]),
+            ],
+            vec![
+                reference("FK_Line_Orders", &format!("dbo.{}.OrderId", long), "dbo.Orders.Id"),
+                reference("FK_Note_Line", "dbo.Note.LineId", &format!("dbo.{}.Id", long)),
+            ],
+        );
+        let options = RenderOptions {
+            max_table_name_length: Some(12),
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&schema, &options);
+        assert_eq!(class_lines(&plantuml), ["class Orders {", "class \"CustomerOrd…\" as t1 {", "class Note {"]);
+        let edges: Vec<&str> = plantuml.lines().filter(|line| line.contains("-->")).collect();
+        assert_eq!(edges, ["t1::OrderId --> Orders::Id : OrderId", "Note::LineId --> t1::Id : LineId"]);
+        assert!(!plantuml.contains(long), "{}", plantuml);
+    }
+
+    #[test]
+    fn verbose_labels_show_both_sides_of_each_foreign_key() {
+        let schema = schema(
+            vec![
+                table("dbo.Customer", &["Id", "Code"], &["Id"]),
+                table("dbo.Product", &["Sku", "Region"], &["Sku", "Region"]),
+                table("dbo.Lines", &["Id", "CustomerCode", "Sku", "Region"], &["Id"]),
+            ],
+            vec![
+                reference("FK_Lines_Customer", "dbo.Lines.CustomerCode", "dbo.Customer.Code"),
+                reference("FK_Lines_Product", "dbo.Lines.Sku", "dbo.Product.Sku"),
+                reference("FK_Lines_Product", "dbo.Lines.Region", "dbo.Product.Region"),
+            ],
+        );
+        let options = RenderOptions {
+            verbose_labels: true,
+            ..RenderOptions::default()
+        };
+        let plantuml = generate_plantuml(&schema, &options);
+        let edges: Vec<&str> = plantuml.lines().filter(|line| line.contains("-->")).collect();
+        assert_eq!(
+            edges,
+            [
+                "Lines::CustomerCode --> Customer::Code : CustomerCode → Code",
+                "Lines --> Product : Sku → Sku, Region → Region",
+            ]
+        );
+    }
+
+    #[test]
+    fn server_info_note_lists_server_version_and_database() {
+        let version = "Microsoft SQL Server 2019 (RTM-CU16) (KB5011644) - 15.0.4223.1 (X64) \n\
+                       \tApr 11 2022 16:24:08 \n\tCopyright (C) 2019 Microsoft Corporation\n";
+        let options = RenderOptions {
+            server_info: Some(ServerInfo::new(version, Some("SQLPROD01 "), "Sales")),
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            note(&generate_plantuml(&orders(), &options), "ServerInfo").expect("a server note"),
+            [
+                "  Server: SQLPROD01",
+                "  Version: Microsoft SQL Server 2019 (RTM-CU16) (KB5011644) - 15.0.4223.1 (X64)",
+                "  Database: Sales",
+            ]
+        );
+
+        let mut unnamed = ServerInfo::new("Microsoft SQL Server 2022", None, "Sales");
+        unnamed.databases.push("Billing".to_string());
+        assert_eq!(
+            unnamed.note_lines(),
+            ["Server: (unnamed)", "Version: Microsoft SQL Server 2022", "Databases: Sales, Billing"]
+        );
+    }
+
+    #[test]
+    fn full_text_columns_are_marked_with_their_catalog() {
+        let mut article = table("dbo.Article", &["Id", "Title:nvarchar(200)", "Body:nvarchar(max)"], &["Id"]);
+        article.columns[2].fulltext_catalog = Some("ArticleSearch".to_string());
+        let plantuml = generate_plantuml(&schema(vec![article], Vec::new()), &RenderOptions::default());
+        assert_eq!(
+            members(&plantuml, "class Article"),
+            ["  Id : int", "  Title : nvarchar(200)", "  Body : nvarchar(max) <<FTS: ArticleSearch>>"]
+        );
+    }
+
+    #[test]
+    fn columns_summary_replaces_the_body_but_keeps_the_arrows() {
+        let options = RenderOptions { columns_summary: true, ..RenderOptions::default() };
+        let plantuml = generate_plantuml(&orders(), &options);
+        assert_eq!(members(&plantuml, "class Orders"), ["  2 cols, PK, 1 FK, 0 indexes"]);
+        assert_eq!(members(&plantuml, "class Customer"), ["  2 cols, PK, 0 FKs, 0 indexes"]);
+        assert!(plantuml.contains("Orders --> Customer : CustomerId"), "{}", plantuml);
+    }
+
+    #[test]
+    fn each_reference_style_emits_its_edge_syntax() {
+        let edges = |reference_style| {
+            let options = RenderOptions { reference_style, ..RenderOptions::default() };
+            let plantuml = generate_plantuml(&orders(), &options);
+            plantuml.lines().filter(|line| line.contains("-->")).map(str::to_string).collect::<Vec<_>>()
+        };
+        assert_eq!(edges(ReferenceStyle::Columns), ["Orders::CustomerId --> Customer::Id : CustomerId"]);
+        assert_eq!(edges(ReferenceStyle::Tables), ["Orders --> Customer : CustomerId"]);
+        assert!(ReferenceStyle::parse("tables") == Some(ReferenceStyle::Tables));
+        assert!(ReferenceStyle::parse("ports").is_none());
+    }
+
+    #[test]
+    fn schema_colours_do_not_depend_on_the_other_schemas() {
+        let header = |tables: Vec<Table>| {
+            let options = RenderOptions { color_by: ColorBy::Schema, ..RenderOptions::default() };
+            let plantuml = generate_plantuml(&schema(tables, Vec::new()), &options);
+            plantuml.lines().find(|line| line.starts_with("class Orders")).unwrap().to_string()
+        };
+        let alone = header(vec![table("sales.Orders", &["Id"], &["Id"])]);
+        let among_others = header(vec![
+            table("dbo.Customer", &["Id"], &["Id"]),
+            table("hr.Employee", &["Id"], &["Id"]),
+            table("sales.Orders", &["Id"], &["Id"]),
+        ]);
+        assert!(alone.contains(" #F5B7B1"), "{}", alone);
+        assert_eq!(alone, among_others);
+        // Pins the FNV-1a pick, which diagrams checked in with --check rely on
+        assert_eq!(schema_color("sales", None), "#F5B7B1");
+        assert_eq!(schema_color("Sales", None), "#F5B7B1");
+        assert_eq!(schema_color("dbo", None), "#D7BDE2");
+        assert_eq!(schema_color("sales", Some(7)), schema_color("sales", Some(7)));
+    }
+
+    #[test]
+    fn hub_tables_over_max_edges_list_their_relationships_in_a_note() {
+        let children = ["Address", "Invoice", "Orders", "Ticket"];
+        let mut tables = vec![table("dbo.Customer", &["Id"], &["Id"]), table("dbo.Country", &["Id"], &["Id"])];
+        let mut references = Vec::new();
+        for child in children {
+            tables.push(table(&format!("dbo.{}", child), &["Id", "CustomerId"], &["Id"]));
+            let constraint = format!("FK_{}_Customer", child);
+            references.push(reference(&constraint, &format!("dbo.{}.CustomerId", child), "dbo.Customer.Id"));
+        }
+        tables[2].columns.push(crate::testing::column("CountryId"));
+        references.push(reference("FK_Address_Country", "dbo.Address.CountryId", "dbo.Country.Id"));
+        let options = RenderOptions { max_edges_per_table: Some(3), ..RenderOptions::default() };
+        let plantuml = generate_plantuml(&schema(tables, references), &options);
+
+        let mut lines = plantuml.lines().skip_while(|line| *line != "note right of Customer");
+        lines.next().expect("the hub gets a note");
+        let note: Vec<&str> = lines.take_while(|line| *line != "end note").collect();
+        let expected: Vec<String> = children.iter().map(|child| format!("  ← {} (CustomerId)", child)).collect();
+        assert_eq!(note, expected);
+        let arrows: Vec<&str> = plantuml.lines().filter(|line| line.contains("-->")).collect();
+        assert_eq!(arrows, ["Address::CountryId --> Country::Id : CountryId"]);
+        assert_eq!(plantuml.matches("note right of").count(), 1, "{}", plantuml);
+    }
+
+    #[test]
+    fn edges_target_the_right_one_of_two_same_named_tables() {
+        for (target, class) in [("sales", "t2"), ("dbo", "t1")] {
+            let schema = schema(
+                vec![
+                    table("dbo.Audit", &["Id"], &["Id"]),
+                    table("sales.Audit", &["Id"], &["Id"]),
+                    table("sales.Orders", &["Id", "AuditId"], &["Id"]),
+                ],
+                vec![reference("FK_Orders_Audit", "sales.Orders.AuditId", &format!("{}.Audit.Id", target))],
+            );
+            let plantuml = generate_plantuml(&schema, &RenderOptions::default());
+            assert_eq!(
+                class_lines(&plantuml),
+                ["class \"dbo.Audit\" as t1 {", "class \"sales.Audit\" as t2 {", "class Orders {"]
+            );
+            let arrows: Vec<&str> = plantuml.lines().filter(|line| line.contains("-->")).collect();
+            assert_eq!(arrows, [format!("Orders::AuditId --> {}::Id : AuditId", class)], "{}", target);
+        }
+    }
+
+    #[test]
+    fn first_columns_keeps_the_leading_columns_and_the_keys() {
+        let schema = schema(
+            vec![
+                table("dbo.Customer", &["Id"], &["Id"]),
+                table("dbo.Orders", &["Id", "Number", "Notes", "Total", "CustomerId"], &["Id"]),
+            ],
+            vec![reference("FK_Orders_Customer", "dbo.Orders.CustomerId", "dbo.Customer.Id")],
+        );
+        let options = RenderOptions { first_columns: Some(2), ..RenderOptions::default() };
+        let plantuml = generate_plantuml(&schema, &options);
+        assert_eq!(
+            members(&plantuml, "class Orders"),
+            ["  Id : int", "  Number : int", "  CustomerId : int", "  … (+2 more)"]
+        );
+        assert_eq!(members(&plantuml, "class Customer"), ["  Id : int"]);
+        assert!(plantuml.contains("Orders::CustomerId --> Customer::Id"), "{}", plantuml);
+    }
+
+    #[test]
+    fn renamed_tables_show_their_display_name_and_keep_their_edges() {
+        let schema = schema(
+            vec![
+                table("dbo.tbl_cust_mstr", &["Id"], &["Id"]),
+                table("dbo.Orders", &["Id", "CustomerId"], &["Id"]),
+            ],
+            vec![reference("FK_Orders_Customer", "dbo.Orders.CustomerId", "dbo.tbl_cust_mstr.Id")],
+        );
+        let display_names = crate::render::parse_rename_map("# physical,display\ndbo.tbl_cust_mstr,Customer Master\n");
+        let options = RenderOptions { display_names: display_names.unwrap(), ..RenderOptions::default() };
+        let plantuml = generate_plantuml(&schema, &options);
+        assert_eq!(class_lines(&plantuml), ["class \"Customer Master\" as t1 {", "class Orders {"]);
+        let arrows: Vec<&str> = plantuml.lines().filter(|line| line.contains("-->")).collect();
+        assert_eq!(arrows, ["Orders::CustomerId --> t1::Id : CustomerId"]);
+        // The JSON export keeps the physical name
+        let json = crate::render::lookup_format("json").unwrap().render(&schema, &options).unwrap();
+        assert!(json.contains("\"tbl_cust_mstr\"") && !json.contains("Customer Master"));
+    }
+
+    #[test]
+    fn index_keys_show_their_direction_and_included_columns_are_listed_apart() {
+        let mut orders = table("dbo.Orders", &["Id", "OrderDate:datetime2", "Total:money"], &["Id"]);
+        orders.indexes = vec![crate::model::Index {
+            descending: vec!["OrderDate".to_string()],
+            included: vec!["Total".to_string()],
+            ..index("IX_Orders_Date", &["OrderDate", "Id"])
+        }];
+        let options = RenderOptions { show_indexes: true, ..RenderOptions::default() };
+        let plantuml = generate_plantuml(&schema(vec![orders], Vec::new()), &options);
+        assert_eq!(
+            members(&plantuml, "class Orders")[4..],
+            ["  IX_Orders_Date (OrderDate DESC, Id) INCLUDE (Total)"]
+        );
+    }
+
+    #[test]
+    fn composite_keys_share_a_tag_and_get_a_note() {
+        let mut line = table("dbo.OrderLine", &["OrderId", "LineNo", "Sku", "Batch"], &["OrderId", "LineNo"]);
+        line.indexes = vec![
+            crate::model::Index { unique: true, ..index("UX_OrderLine_Sku", &["Sku", "Batch"]) },
+            index("IX_OrderLine_Sku", &["Sku", "Batch"]),
+        ];
+        let single = table("dbo.Product", &["Id"], &["Id"]);
+        let options = RenderOptions { group_composite_keys: true, ..RenderOptions::default() };
+        let plantuml = generate_plantuml(&schema(vec![line, single], Vec::new()), &options);
+        assert_eq!(
+            members(&plantuml, "class OrderLine"),
+            ["  OrderId : int <<PK1>>", "  LineNo : int <<PK1>>", "  Sku : int <<UK1>>", "  Batch : int <<UK1>>"]
+        );
+        assert_eq!(members(&plantuml, "class Product"), ["  Id : int"]);
+        let mut lines = plantuml.lines().skip_while(|line| *line != "note left of OrderLine");
+        lines.next().expect("the composite keys get a note");
+        let note: Vec<&str> = lines.take_while(|line| *line != "end note").collect();
+        assert_eq!(
+            note,
+            ["  PK1: composite key (OrderId, LineNo)", "  UK1: unique key UX_OrderLine_Sku (Sku, Batch)"]
+        );
+        assert!(!plantuml.contains("note left of Product"), "{}", plantuml);
+    }
+
+    #[test]
+    fn pfk_marker_combines_the_spots_of_junction_table_keys() {
+        let schema = schema(
+            vec![
+                table("dbo.Student", &["Id"], &["Id"]),
+                table("dbo.Course", &["Id"], &["Id"]),
+                table("dbo.Enrollment", &["StudentId", "CourseId", "Grade"], &["StudentId", "CourseId"]),
+            ],
+            vec![
+                reference("FK_Enrollment_Student", "dbo.Enrollment.StudentId", "dbo.Student.Id"),
+                reference("FK_Enrollment_Course", "dbo.Enrollment.CourseId", "dbo.Course.Id"),
+            ],
+        );
+        let enrollment = |key_spots| {
+            let options = RenderOptions { pfk_marker: true, key_spots, ..RenderOptions::default() };
+            let plantuml = generate_plantuml(&schema, &options);
+            members(&plantuml, "class Enrollment").into_iter().map(str::to_string).collect::<Vec<_>>()
+        };
+        let expected = [
+            format!("  StudentId : int {}", PFK_SPOT),
+            format!("  CourseId : int {}", PFK_SPOT),
+            "  Grade : int".to_string(),
+        ];
+        assert_eq!(enrollment(false), expected);
+        assert_eq!(enrollment(true), expected);
+        assert!(!enrollment(true).iter().any(|line| line.contains(PK_SPOT) || line.contains(FK_SPOT)));
+    }
+
+    #[test]
+    fn attribute_icons_prefix_key_and_computed_columns() {
+        let mut schema = orders();
+        schema.tables[1].columns.push(crate::testing::column("Total:money"));
+        schema.tables[1].columns[2].computed = true;
+        let render = |icons, class| {
+            let options = RenderOptions { attribute_icons: Some(icons), ..RenderOptions::default() };
+            let plantuml = generate_plantuml(&schema, &options);
+            members(&plantuml, class).into_iter().map(str::to_string).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            render(AttributeIcons::default(), "class Orders"),
+            ["  🔑 Id : int", "  🔗 CustomerId : int", "  ⚙ Total : money"]
+        );
+        // Plain columns stay unadorned
+        assert_eq!(render(AttributeIcons::default(), "class Customer"), ["  🔑 Id : int", "  Name : nvarchar(50)"]);
+        let configured = AttributeIcons::parse("pk=#, computed=*").unwrap();
+        assert_eq!(
+            render(configured, "class Orders"),
+            ["  # Id : int", "  🔗 CustomerId : int", "  * Total : money"]
+        );
+        assert!(AttributeIcons::parse("index=!").is_err());
+    }
+
+    #[test]
+    fn resolve_indirection_draws_views_and_synonyms_with_dashed_edges() {
+        let mut schema = orders();
+        for table in &mut schema.tables {
+            table.views.push("dbo.vOrderSummary".to_string());
+        }
+        schema.tables[0].synonyms.push("legacy.Clients".to_string());
+        let lines = |resolve_indirection| {
+            let options = RenderOptions { resolve_indirection, ..RenderOptions::default() };
+            let plantuml = generate_plantuml(&schema, &options);
+            let indirection = plantuml.lines().filter(|line| line.contains("..>") || line.contains("<<"));
+            indirection.map(str::to_string).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            lines(true),
+            [
+                "class \"dbo.vOrderSummary\" as v1 <<view>>",
+                "v1 ..> Customer : depends on",
+                "v1 ..> Orders : depends on",
+                "class \"legacy.Clients\" as s1 <<synonym>>",
+                "s1 ..> Customer : alias",
+            ]
+        );
+        assert!(lines(false).is_empty());
+    }
+}
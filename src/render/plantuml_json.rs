@@ -0,0 +1,108 @@
+use super::{OutputFormat, RenderOptions};
+use crate::error::DiagramError;
+use crate::model::DatabaseSchema;
+
+/// Quotes `text` as a JSON string.
+fn json_string(text: &str) -> String {
+    serde_json::to_string(text).expect("strings serialize to JSON")
+}
+
+/// Renders the schema as a PlantUML `@startjson` catalog tree: schemas, then
+/// tables, then each column mapped to its type. Written by hand rather than
+/// through `serde_json::Map`, which would sort the keys and lose column order.
+pub fn generate_plantuml_json(schema: &DatabaseSchema, options: &RenderOptions) -> String {
+    let mut schema_names: Vec<&str> = Vec::new();
+    for table in &schema.tables {
+        if !schema_names.contains(&table.schema.as_str()) {
+            schema_names.push(&table.schema);
+        }
+    }
+
+    let indent = |depth| options.indent(2, depth);
+    let mut json = String::from("@startjson\n{\n");
+    for (i, schema_name) in schema_names.iter().enumerate() {
+        json.push_str(&format!("{}{}: {{\n", indent(1), json_string(schema_name)));
+        let tables: Vec<_> = schema.tables.iter().filter(|table| table.schema == *schema_name).collect();
+        for (j, table) in tables.iter().enumerate() {
+            let columns: Vec<String> = options
+                .visible_columns(schema, table)
+                .iter()
+                .map(|column| {
+                    format!(
+                        "{}{}: {}",
+                        indent(3),
+                        json_string(&column.name),
+                        json_string(&column.data_type.to_string())
+                    )
+                })
+                .collect();
+            if columns.is_empty() {
+                json.push_str(&format!("{}{}: {{}}", indent(2), json_string(&table.name)));
+            } else {
+                json.push_str(&format!(
+                    "{}{}: {{\n{}\n{}}}",
+                    indent(2),
+                    json_string(&table.name),
+                    columns.join(",\n"),
+                    indent(2)
+                ));
+            }
+            json.push_str(if j + 1 < tables.len() { ",\n" } else { "\n" });
+        }
+        json.push_str(&format!("{}}}{}\n", indent(1), if i + 1 < schema_names.len() { "," } else { "" }));
+    }
+    json.push_str("}\n@endjson\n");
+    json
+}
+
+pub struct PlantUmlJsonFormat;
+
+impl OutputFormat for PlantUmlJsonFormat {
+    fn name(&self) -> &str {
+        "plantuml-json"
+    }
+
+    fn extension(&self) -> &str {
+        "json.puml"
+    }
+
+    fn comment(&self, line: &str) -> Option<String> {
+        Some(format!("' {}", line))
+    }
+
+    fn render(&self, schema: &DatabaseSchema, options: &RenderOptions) -> Result<String, DiagramError> {
+        Ok(generate_plantuml_json(schema, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{reference, schema, table};
+
+    #[test]
+    fn catalog_is_wrapped_json_of_schemas_tables_and_columns() {
+        let schema = schema(
+            vec![
+                table("dbo.Customer", &["Id", "Name:nvarchar(50)"], &["Id"]),
+                table("sales.Orders", &["Id", "CustomerId"], &["Id"]),
+                table("sales.Empty", &[], &[]),
+            ],
+            vec![reference("FK_Orders_Customer", "sales.Orders.CustomerId", "dbo.Customer.Id")],
+        );
+        let rendered = generate_plantuml_json(&schema, &RenderOptions::default());
+        let body = rendered
+            .strip_prefix("@startjson\n")
+            .and_then(|rest| rest.strip_suffix("@endjson\n"))
+            .expect("wrapped in @startjson and @endjson");
+        let catalog: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(
+            catalog,
+            serde_json::json!({
+                "dbo": {"Customer": {"Id": "int", "Name": "nvarchar(50)"}},
+                "sales": {"Orders": {"Id": "int", "CustomerId": "int"}, "Empty": {}},
+            })
+        );
+        assert!(rendered.contains("  \"sales\": {\n    \"Orders\": {\n      \"Id\": \"int\",\n"), "{}", rendered);
+    }
+}
@@ -0,0 +1,128 @@
+use super::{column_line, truncation_line, OutputFormat, RenderOptions};
+use crate::error::DiagramError;
+use crate::model::DatabaseSchema;
+
+/// Width used when the terminal width isn't known.
+const DEFAULT_WIDTH: usize = 80;
+
+/// Pads or truncates `text` to exactly `width` characters.
+fn fit(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        format!("{}{}", text, " ".repeat(width - text.chars().count()))
+    } else {
+        let mut fitted: String = text.chars().take(width.saturating_sub(1)).collect();
+        fitted.push('…');
+        fitted
+    }
+}
+
+/// Renders each table as a box-drawn card followed by its outgoing references.
+pub fn generate_text(schema: &DatabaseSchema, options: &RenderOptions) -> String {
+    // Leave room for the two borders and their padding.
+    let max_inner = options.text_width.unwrap_or(DEFAULT_WIDTH).max(8) - 4;
+    let mut text = String::new();
+    for table in &schema.tables {
+        let mut lines: Vec<String> = options
+            .visible_columns(schema, table)
+            .iter()
+            .map(|column| column_line(column, options))
+            .collect();
+        let truncated = options.truncated_columns(schema, table);
+        if truncated > 0 {
+            lines.push(truncation_line(truncated));
+        }
+        if options.show_indexes {
+            lines.extend(table.indexes.iter().map(|index| format!("index {}", index)));
+        }
+        let inner = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .chain(std::iter::once(table.name.chars().count() + 2))
+            .max()
+            .unwrap_or(0)
+            .min(max_inner);
+
+        let name = fit(&table.name, inner - 2);
+        let name = name.trim_end();
+        let rule = "─".repeat(inner + 1 - name.chars().count() - 2);
+        text.push_str(&format!("┌─ {} {}┐\n", name, rule));
+        for line in &lines {
+            text.push_str(&format!("│ {} │\n", fit(line, inner)));
+        }
+        text.push_str(&format!("└{}┘\n", "─".repeat(inner + 2)));
+
+        for reference in schema
+            .references
+            .iter()
+            .filter(|reference| reference.schema == table.schema && reference.table == table.name)
+        {
+            text.push_str(&format!(
+                "  → {} ({} → {})\n",
+                reference.referenced_table, reference.column, reference.referenced_column
+            ));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+pub struct TextFormat;
+
+impl OutputFormat for TextFormat {
+    fn name(&self) -> &str {
+        "text"
+    }
+
+    fn extension(&self) -> &str {
+        "txt"
+    }
+
+    fn render(&self, schema: &DatabaseSchema, options: &RenderOptions) -> Result<String, DiagramError> {
+        Ok(generate_text(schema, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{reference, schema, table};
+
+    fn orders() -> DatabaseSchema {
+        schema(
+            vec![table("dbo.Customer", &["Id"], &["Id"]), table("dbo.Orders", &["Id", "CustomerId"], &["Id"])],
+            vec![reference("FK_Orders_Customer", "dbo.Orders.CustomerId", "dbo.Customer.Id")],
+        )
+    }
+
+    #[test]
+    fn tables_are_drawn_as_boxes_with_their_references() {
+        let text = generate_text(&orders(), &RenderOptions::default());
+        assert_eq!(
+            text,
+            "┌─ Customer ─┐\n\
+             │ Id : int   │\n\
+             └────────────┘\n\
+             \n\
+             ┌─ Orders ─────────┐\n\
+             │ Id : int         │\n\
+             │ CustomerId : int │\n\
+             └──────────────────┘\n  \
+             → Customer (CustomerId → Id)\n\
+             \n"
+        );
+    }
+
+    #[test]
+    fn long_lines_are_cut_to_the_width() {
+        let options = RenderOptions {
+            text_width: Some(12),
+            ..RenderOptions::default()
+        };
+        let text = generate_text(&orders(), &options);
+        assert!(text.contains("┌─ Custo… ─┐\n"), "{}", text);
+        assert!(text.contains("│ Custome… │\n"), "{}", text);
+        // Reference lines sit outside the cards and aren't cut
+        let mut cards = text.lines().filter(|line| !line.starts_with("  →"));
+        assert!(cards.all(|line| line.chars().count() <= 12), "{}", text);
+    }
+}
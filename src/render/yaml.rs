@@ -0,0 +1,68 @@
+use super::{OutputFormat, RenderOptions};
+use crate::error::DiagramError;
+use crate::model::DatabaseSchema;
+use std::io;
+
+/// Serializes the whole model, ignoring rendering options, for tooling that
+/// prefers YAML.
+pub fn generate_yaml(schema: &DatabaseSchema) -> Result<String, DiagramError> {
+    serde_yaml::to_string(schema).map_err(|err| DiagramError::Io(io::Error::other(err)))
+}
+
+pub struct YamlFormat;
+
+impl OutputFormat for YamlFormat {
+    fn name(&self) -> &str {
+        "yaml"
+    }
+
+    fn extension(&self) -> &str {
+        "yaml"
+    }
+
+    fn exports_model(&self) -> bool {
+        true
+    }
+
+    fn comment(&self, line: &str) -> Option<String> {
+        Some(format!("# {}", line))
+    }
+
+    fn render(&self, schema: &DatabaseSchema, _options: &RenderOptions) -> Result<String, DiagramError> {
+        generate_yaml(schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{reference, schema, table};
+    use serde_yaml::Value;
+
+    #[test]
+    fn yaml_carries_the_model_keys_and_reads_back() {
+        let schema = schema(
+            vec![
+                table("dbo.Customer", &["Id", "Name:nvarchar(50)"], &["Id"]),
+                table("dbo.Orders", &["Id", "CustomerId"], &["Id"]),
+            ],
+            vec![reference("FK_Orders_Customer", "dbo.Orders.CustomerId", "dbo.Customer.Id")],
+        );
+        let yaml = generate_yaml(&schema).unwrap();
+        let value: Value = serde_yaml::from_str(&yaml).unwrap();
+        let customer = &value["tables"][0];
+        assert_eq!(customer["schema"].as_str(), Some("dbo"));
+        assert_eq!(customer["name"].as_str(), Some("Customer"));
+        assert_eq!(customer["primary_key"][0].as_str(), Some("Id"));
+        let name = &customer["columns"][1];
+        assert_eq!(name["name"].as_str(), Some("Name"));
+        assert_eq!(name["data_type"]["base"].as_str(), Some("nvarchar"));
+        assert_eq!(name["data_type"]["length"].as_i64(), Some(50));
+        let fk = &value["references"][0];
+        assert_eq!(fk["constraint_name"].as_str(), Some("FK_Orders_Customer"));
+        assert_eq!(fk["referenced_column"].as_str(), Some("Id"));
+
+        let read_back: DatabaseSchema = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(read_back.fingerprint(), schema.fingerprint());
+    }
+}
@@ -0,0 +1,111 @@
+use crate::model::{Column, DataType, DatabaseSchema, Reference, Table};
+use std::path::PathBuf;
+
+/// A column from `Name` or `Name:type`, such as `Total:decimal(10,2)`; the
+/// type defaults to `int`.
+pub fn column(spec: &str) -> Column {
+    let (name, data_type) = spec.split_once(':').unwrap_or((spec, "int"));
+    Column {
+        name: name.to_string(),
+        data_type: DataType::parse(data_type),
+        description: None,
+        bound_default: None,
+        bound_rule: None,
+        collation: None,
+        fulltext_catalog: None,
+        computed: false,
+    }
+}
+
+/// A table of `schema.name` with `columns` as `column` reads them and the
+/// primary key `primary_key`.
+pub fn table(qualified: &str, columns: &[&str], primary_key: &[&str]) -> Table {
+    let (schema, name) = qualified.split_once('.').expect("a schema-qualified table");
+    Table {
+        columns: columns.iter().map(|spec| column(spec)).collect(),
+        primary_key: primary_key.iter().map(|key| key.to_string()).collect(),
+        external: false,
+        ..Table::external_stub(schema, name)
+    }
+}
+
+/// A primary-key reference `constraint` from the `schema.table.column` at
+/// `from` to the one at `to`.
+pub fn reference(constraint: &str, from: &str, to: &str) -> Reference {
+    let parts = |end: &str| -> (String, String, String) {
+        let mut parts = end.splitn(3, '.').map(str::to_string);
+        let mut next = || parts.next().expect("a schema.table.column reference end");
+        (next(), next(), next())
+    };
+    let ((schema, table, column), (referenced_schema, referenced_table, referenced_column)) = (parts(from), parts(to));
+    Reference {
+        constraint_name: constraint.to_string(),
+        schema,
+        table,
+        column,
+        referenced_schema,
+        referenced_table,
+        referenced_column,
+        is_disabled: false,
+        references_pk: true,
+        via_synonym: None,
+    }
+}
+
+/// An empty directory under the system temp directory, named after the test
+/// `name` and this process so parallel tests and runs don't share one.
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tsql-diagram-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("a writable temp directory");
+    dir
+}
+
+/// A schema of `tables` and `references`.
+pub fn schema(tables: Vec<Table>, references: Vec<Reference>) -> DatabaseSchema {
+    DatabaseSchema { tables, references }
+}
+
+/// The `schema.name` of each table, in order.
+pub fn table_names(schema: &DatabaseSchema) -> Vec<String> {
+    schema.tables.iter().map(|table| format!("{}.{}", table.schema, table.name)).collect()
+}
+
+/// Each reference as `constraint: schema.table.column -> schema.table.column`,
+/// marked ` (disabled)` when it is, in order.
+pub fn reference_lines(schema: &DatabaseSchema) -> Vec<String> {
+    schema
+        .references
+        .iter()
+        .map(|reference| {
+            format!(
+                "{}: {}.{}.{} -> {}.{}.{}{}",
+                reference.constraint_name,
+                reference.schema,
+                reference.table,
+                reference.column,
+                reference.referenced_schema,
+                reference.referenced_table,
+                reference.referenced_column,
+                if reference.is_disabled { " (disabled)" } else { "" }
+            )
+        })
+        .collect()
+}
+
+/// Two triangles, a-b-c and d-e-f, bridged by c → d, with their tables
+/// interleaved in model order.
+pub fn two_triangles() -> DatabaseSchema {
+    let tables = ["a", "d", "b", "e", "c", "f"];
+    let edges = [("b", "a"), ("c", "a"), ("c", "b"), ("e", "d"), ("f", "d"), ("f", "e"), ("c", "d")];
+    schema(
+        tables.iter().map(|name| table(&format!("dbo.{}", name), &["Id", "ParentId"], &["Id"])).collect(),
+        edges
+            .iter()
+            .map(|(from, to)| {
+                let constraint = format!("FK_{}_{}", from, to);
+                reference(&constraint, &format!("dbo.{}.ParentId", from), &format!("dbo.{}.Id", to))
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,181 @@
+use crate::model::{DatabaseSchema, Table};
+use std::collections::HashSet;
+use std::fmt;
+
+/// An inconsistency in a `DatabaseSchema`, as `validate` reports it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// A table whose schema or name is empty, by its position in `tables`.
+    EmptyTableName(usize),
+    /// A column with an empty name: `(schema, table, position)`.
+    EmptyColumnName(String, String, usize),
+    /// A foreign key with an empty constraint name: `(schema, table)`.
+    EmptyConstraintName(String, String),
+    /// More than one table with this `(schema, name)`.
+    DuplicateTable(String, String),
+    /// A foreign key naming a table that isn't in the schema:
+    /// `(constraint, schema, table)`.
+    MissingTable(String, String, String),
+    /// A foreign key naming a column its table doesn't have:
+    /// `(constraint, schema, table, column)`.
+    MissingColumn(String, String, String, String),
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::EmptyTableName(position) => {
+                write!(f, "table {} has an empty schema or name", position + 1)
+            }
+            ValidationIssue::EmptyColumnName(schema, table, position) => {
+                write!(f, "column {} of {}.{} has an empty name", position + 1, schema, table)
+            }
+            ValidationIssue::EmptyConstraintName(schema, table) => {
+                write!(f, "a foreign key on {}.{} has an empty constraint name", schema, table)
+            }
+            ValidationIssue::DuplicateTable(schema, table) => {
+                write!(f, "table {}.{} appears more than once", schema, table)
+            }
+            ValidationIssue::MissingTable(constraint, schema, table) => {
+                write!(f, "foreign key {} names table {}.{}, which doesn't exist", constraint, schema, table)
+            }
+            ValidationIssue::MissingColumn(constraint, schema, table, column) => write!(
+                f,
+                "foreign key {} names column {}.{}.{}, which doesn't exist",
+                constraint, schema, table, column
+            ),
+        }
+    }
+}
+
+/// Checks that `schema` is internally consistent, as a hand-built schema may
+/// not be: every table, column and constraint has a name, no table appears
+/// twice and both ends of every foreign key name an existing table and
+/// column. External stubs carry no columns, so only their tables are checked.
+pub fn validate(schema: &DatabaseSchema) -> Result<(), Vec<ValidationIssue>> {
+    let mut issues = Vec::new();
+    let mut seen = HashSet::new();
+    for (position, table) in schema.tables.iter().enumerate() {
+        if table.schema.is_empty() || table.name.is_empty() {
+            issues.push(ValidationIssue::EmptyTableName(position));
+        }
+        for (position, column) in table.columns.iter().enumerate() {
+            if column.name.is_empty() {
+                issues.push(ValidationIssue::EmptyColumnName(table.schema.clone(), table.name.clone(), position));
+            }
+        }
+        if !seen.insert((table.schema.as_str(), table.name.as_str())) {
+            issues.push(ValidationIssue::DuplicateTable(table.schema.clone(), table.name.clone()));
+        }
+    }
+
+    let find = |schema_name: &str, name: &str| -> Option<&Table> {
+        schema.tables.iter().find(|table| table.schema == schema_name && table.name == name)
+    };
+    for reference in &schema.references {
+        if reference.constraint_name.is_empty() {
+            issues.push(ValidationIssue::EmptyConstraintName(reference.schema.clone(), reference.table.clone()));
+        }
+        let ends = [
+            (&reference.schema, &reference.table, &reference.column),
+            (&reference.referenced_schema, &reference.referenced_table, &reference.referenced_column),
+        ];
+        for (table_schema, table_name, column) in ends {
+            match find(table_schema, table_name) {
+                None => {
+                    let issue = ValidationIssue::MissingTable(
+                        reference.constraint_name.clone(),
+                        table_schema.clone(),
+                        table_name.clone(),
+                    );
+                    // Each column of a composite foreign key names the table again
+                    if !issues.contains(&issue) {
+                        issues.push(issue);
+                    }
+                }
+                Some(table) if !table.external && !table.columns.iter().any(|candidate| &candidate.name == column) => {
+                    issues.push(ValidationIssue::MissingColumn(
+                        reference.constraint_name.clone(),
+                        table_schema.clone(),
+                        table_name.clone(),
+                        column.clone(),
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(issues)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{column, reference, schema, table};
+
+    fn sample() -> DatabaseSchema {
+        schema(
+            vec![
+                table("dbo.Customer", &["Id"], &["Id"]),
+                table("dbo.Order", &["Id", "CustomerId"], &["Id"]),
+            ],
+            vec![reference("FK_Order_Customer", "dbo.Order.CustomerId", "dbo.Customer.Id")],
+        )
+    }
+
+    #[test]
+    fn consistent_schema_passes() {
+        assert_eq!(validate(&sample()), Ok(()));
+    }
+
+    #[test]
+    fn external_stubs_need_no_columns() {
+        let mut schema = sample();
+        schema.tables[0] = Table::external_stub("dbo", "Customer");
+        assert_eq!(validate(&schema), Ok(()));
+    }
+
+    #[test]
+    fn reports_empty_and_duplicate_names() {
+        let mut schema = sample();
+        schema.tables[1].columns.push(column(""));
+        schema.tables.push(table("dbo.Customer", &["Id"], &["Id"]));
+        schema.tables.push(table(".Note", &[], &[]));
+        schema.references[0].constraint_name.clear();
+        assert_eq!(
+            validate(&schema),
+            Err(vec![
+                ValidationIssue::EmptyColumnName("dbo".to_string(), "Order".to_string(), 2),
+                ValidationIssue::DuplicateTable("dbo".to_string(), "Customer".to_string()),
+                ValidationIssue::EmptyTableName(3),
+                ValidationIssue::EmptyConstraintName("dbo".to_string(), "Order".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn reports_missing_tables_once_and_missing_columns() {
+        let mut schema = sample();
+        schema.references.push(reference("FK_Order_Region", "dbo.Order.RegionId", "dbo.Region.Id"));
+        schema.references.push(reference("FK_Order_Region", "dbo.Order.CustomerId", "dbo.Region.Code"));
+        let issues = validate(&schema).unwrap_err();
+        assert_eq!(
+            issues,
+            [
+                ValidationIssue::MissingColumn(
+                    "FK_Order_Region".to_string(),
+                    "dbo".to_string(),
+                    "Order".to_string(),
+                    "RegionId".to_string()
+                ),
+                ValidationIssue::MissingTable("FK_Order_Region".to_string(), "dbo".to_string(), "Region".to_string()),
+            ]
+        );
+        assert_eq!(issues[1].to_string(), "foreign key FK_Order_Region names table dbo.Region, which doesn't exist");
+    }
+}